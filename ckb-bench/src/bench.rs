@@ -1,12 +1,12 @@
-use ckb_testkit::ckb_types::core::{EpochNumberWithFraction, TransactionBuilder, TransactionView};
+use ckb_testkit::ckb_types::core::{EpochNumberWithFraction, TransactionView};
 use ckb_testkit::ckb_types::packed::{CellDep, CellOutput};
 use ckb_testkit::ckb_types::{
     core::cell::CellMeta,
-    packed::{Byte32, CellInput, OutPoint},
+    packed::{Byte32, OutPoint},
     prelude::*,
 };
 use ckb_testkit::util::since_from_absolute_epoch_number_with_fraction;
-use ckb_testkit::{Node, User};
+use ckb_testkit::{NoCollector, Node, TxBuilder, User};
 use crossbeam_channel::{Receiver, Sender};
 use lru::LruCache;
 use std::collections::HashMap;
@@ -38,8 +38,11 @@ impl LiveCellProducer {
                 .min()
                 .unwrap();
             for user in self.users.iter() {
-                let live_cells = user
-                    .get_spendable_single_secp256k1_cells(&self.nodes[0])
+                let mut live_cells = user.get_spendable_single_secp256k1_cells(&self.nodes[0]);
+                if user.has_multisig_config() {
+                    live_cells.extend(user.get_spendable_multisig_cells(&self.nodes[0]));
+                }
+                let live_cells = live_cells
                     .into_iter()
                     // TODO reduce competition
                     .filter(|cell| {
@@ -71,6 +74,8 @@ pub struct TransactionProducer {
     users: HashMap<Byte32, User>,
     cell_deps: Vec<CellDep>,
     n_inout: usize,
+    // shannons per kilobyte
+    fee_rate: u64,
     // #{ lock_hash => live_cell }
     live_cells: HashMap<Byte32, CellMeta>,
     // #{ out_point => live_cell }
@@ -78,7 +83,7 @@ pub struct TransactionProducer {
 }
 
 impl TransactionProducer {
-    pub fn new(users: Vec<User>, cell_deps: Vec<CellDep>, n_inout: usize) -> Self {
+    pub fn new(users: Vec<User>, cell_deps: Vec<CellDep>, n_inout: usize, fee_rate: u64) -> Self {
         let mut users_map = HashMap::new();
         for user in users {
             // To support environment `CKB_BENCH_ENABLE_DATA1_SCRIPT`, we have to index 3
@@ -98,12 +103,19 @@ impl TransactionProducer {
                     .calc_script_hash(),
                 user.clone(),
             );
+            if user.has_multisig_config() {
+                users_map.insert(
+                    user.multisig_lock_script_via_type().calc_script_hash(),
+                    user.clone(),
+                );
+            }
         }
 
         Self {
             users: users_map,
             cell_deps,
             n_inout,
+            fee_rate,
             live_cells: HashMap::new(),
             backlogs: HashMap::new(),
         }
@@ -132,7 +144,12 @@ impl TransactionProducer {
             }
             Err(_) => false,
         };
+        let enabled_multisig_script = enabled_multisig_script();
         ckb_testkit::info!("CKB_BENCH_ENABLE_DATA1_SCRIPT = {}", enabled_data1_script);
+        ckb_testkit::info!(
+            "CKB_BENCH_ENABLE_MULTISIG_SCRIPT = {}",
+            enabled_multisig_script
+        );
         ckb_testkit::info!(
             "CKB_BENCH_ENABLE_INVALID_SINCE_EPOCH = {}",
             enabled_invalid_since_epoch
@@ -162,64 +179,67 @@ impl TransactionProducer {
                 } else {
                     0
                 };
-                let inputs = live_cells
-                    .values()
-                    .map(|cell| {
-                        CellInput::new_builder()
-                            .previous_output(cell.out_point.clone())
-                            .since(since.pack())
-                            .build()
-                    })
-                    .collect::<Vec<_>>();
-                let outputs = live_cells
+                let locks = live_cells
                     .values()
                     .map(|cell| {
                         // use tx_index as random number
                         let tx_index = cell.transaction_info.as_ref().unwrap().index;
                         let user = self.users.get(&lock_hash).expect("should be ok");
+                        if enabled_multisig_script && tx_index % 2 == 0 {
+                            return user.multisig_lock_script_via_type();
+                        }
                         match tx_index % 3 {
-                            0 => CellOutput::new_builder()
-                                .capacity((cell.capacity().as_u64() - 1000).pack())
-                                .lock(user.single_secp256k1_lock_script_via_data())
-                                .build(),
-                            1 => CellOutput::new_builder()
-                                .capacity((cell.capacity().as_u64() - 1000).pack())
-                                .lock(user.single_secp256k1_lock_script_via_type())
-                                .build(),
+                            0 => user.single_secp256k1_lock_script_via_data(),
+                            1 => user.single_secp256k1_lock_script_via_type(),
                             2 => {
                                 if enabled_data1_script {
-                                    CellOutput::new_builder()
-                                        .capacity((cell.capacity().as_u64() - 1000).pack())
-                                        .lock(user.single_secp256k1_lock_script_via_data1())
-                                        .build()
+                                    user.single_secp256k1_lock_script_via_data1()
                                 } else {
-                                    CellOutput::new_builder()
-                                        .capacity((cell.capacity().as_u64() - 1000).pack())
-                                        .lock(user.single_secp256k1_lock_script_via_data())
-                                        .build()
+                                    user.single_secp256k1_lock_script_via_data()
                                 }
                             }
                             _ => unreachable!(),
                         }
                     })
                     .collect::<Vec<_>>();
-                let outputs_data = live_cells.values().map(|_| Default::default());
-                let raw_tx = TransactionBuilder::default()
-                    .inputs(inputs)
+                // NOTE: Inputs and outputs are paired by index. The outputs take the capacities
+                // of their inputs, except the last one, which is the change output paying the
+                // fee at `fee_rate`.
+                let (change_lock, locks) = locks.split_last().expect("n_inout > 0");
+                let outputs = live_cells
+                    .values()
+                    .zip(locks.iter())
+                    .map(|(cell, lock)| {
+                        let output = CellOutput::new_builder()
+                            .capacity(cell.capacity().as_u64().pack())
+                            .lock(lock.clone())
+                            .build();
+                        (output, Default::default())
+                    })
+                    .collect::<Vec<_>>();
+                let signers = live_cells
+                    .values()
+                    .map(|cell| {
+                        let lock_hash = cell.cell_output.calc_lock_hash();
+                        self.users.get(&lock_hash).expect("should be ok").clone()
+                    })
+                    .collect::<Vec<_>>();
+                let signed_tx = match TxBuilder::new()
+                    .since(since)
+                    .inputs(live_cells.values().cloned())
                     .outputs(outputs)
-                    .outputs_data(outputs_data)
                     .cell_deps(self.cell_deps.clone())
-                    .build();
-                // NOTE: We know the transaction's inputs and outputs are paired by index, so this
-                // signed way is okay.
-                let witnesses = live_cells.values().map(|cell| {
-                    let lock_hash = cell.cell_output.calc_lock_hash();
-                    let user = self.users.get(&lock_hash).expect("should be ok");
-                    user.single_secp256k1_signed_witness(&raw_tx)
-                        .as_bytes()
-                        .pack()
-                });
-                let signed_tx = raw_tx.as_advanced_builder().witnesses(witnesses).build();
+                    .fee_rate(self.fee_rate)
+                    .change_lock(change_lock.clone())
+                    .signers(signers)
+                    .build(&mut NoCollector)
+                {
+                    Ok(signed_tx) => signed_tx,
+                    Err(err) => {
+                        ckb_testkit::error!("failed to build transaction, error: {}", err);
+                        continue;
+                    }
+                };
 
                 if transaction_sender.send(signed_tx).is_err() {
                     // SendError occurs, the corresponding transaction receiver is dead
@@ -245,3 +265,17 @@ impl TransactionProducer {
         }
     }
 }
+
+// Environment variable `CKB_BENCH_ENABLE_MULTISIG_SCRIPT` is temporary. When enabled, users
+// own an 1-of-1 multisig lock besides the single secp256k1 locks, and half of the bench
+// outputs are transferred to it.
+pub fn enabled_multisig_script() -> bool {
+    match ::std::env::var("CKB_BENCH_ENABLE_MULTISIG_SCRIPT") {
+        Ok(raw) => {
+            raw.parse()
+                .map_err(|err| ckb_testkit::error!("failed to parse environment variable \"CKB_BENCH_ENABLE_MULTISIG_SCRIPT={}\", error: {}", raw, err))
+                .unwrap_or(false)
+        }
+        Err(_) => false,
+    }
+}
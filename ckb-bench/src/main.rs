@@ -7,13 +7,13 @@ mod watcher;
 #[cfg(test)]
 mod tests;
 
-use crate::bench::{LiveCellProducer, TransactionProducer};
+use crate::bench::{enabled_multisig_script, LiveCellProducer, TransactionProducer};
 use crate::prepare::{collect, derive_privkeys, dispatch};
 use crate::utils::maybe_retry_send_transaction;
 use crate::watcher::Watcher;
 use ckb_testkit::ckb_crypto::secp::Privkey;
 use ckb_testkit::ckb_types::{core::BlockNumber, packed::Byte32, prelude::*, H256};
-use ckb_testkit::{Node, Nodes, User};
+use ckb_testkit::{MultisigConfig, Node, Nodes, User};
 use clap::{crate_version, value_t_or_exit, values_t_or_exit, App, Arg, ArgMatches, SubCommand};
 use crossbeam_channel::bounded;
 use std::env;
@@ -247,6 +247,7 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
                 .collect::<Vec<_>>();
             let n_users = value_t_or_exit!(arguments, "n-users", usize);
             let n_inout = value_t_or_exit!(arguments, "n-inout", usize);
+            let fee_rate = value_t_or_exit!(arguments, "fee-rate", u64);
             let t_tx_interval = {
                 let tx_interval_ms = value_t_or_exit!(arguments, "tx-interval-ms", u64);
                 Duration::from_millis(tx_interval_ms)
@@ -277,6 +278,25 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
                     .map(|privkey| User::new(genesis_block.clone(), Some(privkey)))
                     .collect::<Vec<_>>()
             };
+            let (users, cell_deps) = if enabled_multisig_script() {
+                let users = users
+                    .into_iter()
+                    .map(|user| {
+                        let config =
+                            MultisigConfig::new(0, 1, vec![user.single_secp256k1_pubkey()])
+                                .expect("1-of-1 multisig config");
+                        user.with_multisig_config(config)
+                    })
+                    .collect::<Vec<_>>();
+                let cell_deps = vec![
+                    users[0].single_secp256k1_cell_dep(),
+                    users[0].multisig_cell_dep(),
+                ];
+                (users, cell_deps)
+            } else {
+                let cell_deps = vec![users[0].single_secp256k1_cell_dep()];
+                (users, cell_deps)
+            };
             let is_smoking_test = arguments.is_present("is-smoking-test");
             let (live_cell_sender, live_cell_receiver) = bounded(10000000);
             let (transaction_sender, transaction_receiver) = bounded(1000000);
@@ -284,8 +304,8 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
             wait_for_nodes_sync(&nodes);
             wait_for_indexer_synced(&nodes);
             ckb_testkit::info!(
-                "bench with params --n-users {} --n-inout {} --tx-interval-ms {} --bench-time-ms {}",
-                users.len(), n_inout, t_tx_interval.as_millis(), t_bench.as_millis(),
+                "bench with params --n-users {} --n-inout {} --fee-rate {} --tx-interval-ms {} --bench-time-ms {}",
+                users.len(), n_inout, fee_rate, t_tx_interval.as_millis(), t_bench.as_millis(),
             );
 
             let live_cell_producer = LiveCellProducer::new(users.clone(), nodes.clone());
@@ -293,11 +313,8 @@ pub fn entrypoint(clap_arg_match: ArgMatches<'static>) {
                 live_cell_producer.run(live_cell_sender);
             });
 
-            let transaction_producer = TransactionProducer::new(
-                users.clone(),
-                vec![users[0].single_secp256k1_cell_dep()],
-                n_inout,
-            );
+            let transaction_producer =
+                TransactionProducer::new(users.clone(), cell_deps, n_inout, fee_rate);
             spawn(move || {
                 transaction_producer.run(live_cell_receiver, transaction_sender);
             });
@@ -495,6 +512,15 @@ fn clap_app() -> App<'static, 'static> {
                         .help("input-output pairs of a transaction")
                         .validator(|s| s.parse::<u64>().map(|_| ()).map_err(|err| err.to_string())),
                 )
+                .arg(
+                    Arg::with_name("fee-rate")
+                        .long("fee-rate")
+                        .value_name("SHANNONS_PER_KB")
+                        .takes_value(true)
+                        .default_value("1000")
+                        .help("Fee rate of the bench transactions, in shannons per kilobyte")
+                        .validator(|s| s.parse::<u64>().map(|_| ()).map_err(|err| err.to_string())),
+                )
                 .arg(
                     Arg::with_name("tx-interval-ms")
                         .long("tx-interval-ms")
@@ -1,13 +1,12 @@
 use crate::utils::maybe_retry_send_transaction;
-use ckb_testkit::ckb_crypto::secp::{Message, Privkey};
+use ckb_testkit::ckb_crypto::secp::Privkey;
 use ckb_testkit::ckb_jsonrpc_types::Status;
 use ckb_testkit::ckb_types::{
-    bytes::Bytes,
-    core::{cell::CellMeta, Capacity, TransactionBuilder},
-    packed::{Byte32, CellInput, CellOutput, OutPoint, WitnessArgs},
+    core::cell::CellMeta,
+    packed::{Byte32, CellOutput, OutPoint},
     prelude::*,
 };
-use ckb_testkit::{Node, User};
+use ckb_testkit::{NoCollector, Node, TxBuilder, User};
 use std::cmp::min;
 use std::collections::{HashMap, VecDeque};
 use std::thread::sleep;
@@ -16,6 +15,7 @@ use std::time::{Duration, Instant};
 /// count of two-in-two-out txs a block should capable to package.
 pub const TWO_IN_TWO_OUT_COUNT: u64 = 1_000;
 pub const MAX_OUT_COUNT: u64 = TWO_IN_TWO_OUT_COUNT;
+/// estimated fee per output, used to check whether the owner's capacity is enough beforehand.
 pub const FEE_RATE_OF_OUTPUT: u64 = 1000;
 
 pub fn dispatch(
@@ -58,66 +58,24 @@ pub fn dispatch(
 
     let mut last_logging_time = Instant::now();
     let mut i_out = 0usize;
-    let mut inputs = Vec::new();
     let mut txs = Vec::new();
-    while let Some(input) = live_cells.pop_front() {
-        inputs.push(input);
-
-        let inputs_capacity: u64 = inputs.iter().map(|input| input.capacity().as_u64()).sum();
-        // TODO estimate tx fee
-        let fee = MAX_OUT_COUNT * FEE_RATE_OF_OUTPUT;
-        let outputs_capacity = inputs_capacity - fee;
-        let mut n_outs = min(MAX_OUT_COUNT, outputs_capacity / capacity_per_cell) as usize;
-        if n_outs == 0 {
-            continue;
-        }
-
-        if i_out + n_outs >= total_outs {
-            n_outs = total_outs - i_out;
-        }
-        let change_capacity = outputs_capacity - n_outs as u64 * capacity_per_cell;
-
-        let mut outputs = Vec::with_capacity(n_outs as usize + 1);
-        if change_capacity >= Capacity::bytes(67).unwrap().as_u64() {
-            let change_output = CellOutput::new_builder()
-                .capacity(change_capacity.pack())
-                .lock(owner.single_secp256k1_lock_script_via_data())
-                .build();
-            outputs.push(change_output);
-        }
-        for i in i_out..i_out + n_outs {
+    while i_out < total_outs {
+        let n_outs = min(MAX_OUT_COUNT as usize, total_outs - i_out);
+        let outputs = (i_out..i_out + n_outs).map(|i| {
             let user = &users[index_user(i)];
             let cell_output = CellOutput::new_builder()
                 .capacity(capacity_per_cell.pack())
                 .lock(user.single_secp256k1_lock_script_via_data())
                 .build();
-            outputs.push(cell_output);
-        }
-
-        let signed_tx = {
-            let unsigned_tx = TransactionBuilder::default()
-                .inputs(
-                    inputs
-                        .iter()
-                        .map(|input| CellInput::new(input.out_point.clone(), 0)),
-                )
-                .outputs_data(
-                    (0..outputs.len())
-                        .map(|_| Default::default())
-                        .collect::<Vec<_>>(),
-                )
-                .outputs(outputs)
-                .cell_dep(owner.single_secp256k1_cell_dep())
-                .build();
-            let witness = owner
-                .single_secp256k1_signed_witness(&unsigned_tx)
-                .as_bytes()
-                .pack();
-            unsigned_tx
-                .as_advanced_builder()
-                .set_witnesses(vec![witness])
-                .build()
-        };
+            (cell_output, Default::default())
+        });
+        let signed_tx = TxBuilder::new()
+            .outputs(outputs)
+            .cell_dep(owner.single_secp256k1_cell_dep())
+            .change_lock(owner.single_secp256k1_lock_script_via_data())
+            .signer(owner.clone())
+            .build(&mut live_cells)
+            .unwrap_or_else(|err| panic!("failed to build dispatch-transaction, error: {}", err));
 
         let result = maybe_retry_send_transaction(&nodes[0], &signed_tx);
         if last_logging_time.elapsed() > Duration::from_secs(30) {
@@ -131,20 +89,15 @@ pub fn dispatch(
             result.unwrap_err()
         );
 
-        // Reset `inputs`, it already been using.
-        inputs = Vec::new();
         txs.push(signed_tx.clone());
         i_out += n_outs;
-        if i_out == total_outs {
-            break;
-        }
 
         // Reuse the change output, we can construct chained transactions
         if signed_tx.outputs().len() > n_outs {
-            // the 1st output is a change cell, push it back into live_cells as it is a live cell
+            // the last output is a change cell, push it back into live_cells as it is a live cell
             let change_live_cell = {
-                let cell_output = signed_tx.output(0).expect("1st output exists");
-                let out_point = OutPoint::new(signed_tx.hash(), 0);
+                let cell_output = signed_tx.output(n_outs).expect("change output exists");
+                let out_point = OutPoint::new(signed_tx.hash(), n_outs as u32);
                 CellMeta {
                     cell_output,
                     out_point,
@@ -282,84 +235,27 @@ fn collect_inputs(
     inputs: &[CellMeta],
     users: &HashMap<Byte32, User>,
 ) {
-    let inputs_capacity: u64 = inputs.iter().map(|cell| cell.capacity().as_u64()).sum();
-    // TODO estimate tx fee
-    let fee = inputs.len() as u64 * 1000;
-    let output = CellOutput::new_builder()
-        .capacity((inputs_capacity - fee).pack())
-        .lock(owner.single_secp256k1_lock_script_via_data())
-        .build();
-    let unsigned_tx = TransactionBuilder::default()
-        .inputs(
-            inputs
-                .iter()
-                .map(|cell| CellInput::new(cell.out_point.clone(), 0)),
-        )
-        .output(output)
-        .output_data(Default::default())
-        .cell_dep(owner.single_secp256k1_cell_dep())
-        .build();
-
-    let sorted_script_groups = {
-        let mut script_groups = HashMap::new();
+    let signers = {
+        let mut signers: Vec<User> = Vec::new();
         for input in inputs.iter() {
-            let lock = input.cell_output.lock();
-            script_groups
-                .entry(lock)
-                .and_modify(|cnt| *cnt += 1)
-                .or_insert(1usize);
-        }
-
-        let mut sorted_script_groups = Vec::new();
-        for input in inputs.iter() {
-            if let Some(cnt) = script_groups.remove(&input.cell_output.lock()) {
-                sorted_script_groups.push((input.cell_output.lock(), cnt));
-            }
-        }
-        sorted_script_groups
-    };
-    let witnesses = {
-        let mut witnesses = Vec::new();
-        let tx_hash = unsigned_tx.hash();
-        for (script, cnt) in sorted_script_groups {
-            // 1. hash the tx-hash and first modified witness
-            let mut blake2b = ckb_hash::new_blake2b();
-            blake2b.update(&tx_hash.raw_data());
-
-            let placeholder = WitnessArgs::new_builder()
-                .lock(Some(Bytes::from(vec![0u8; 65])).pack())
-                .build();
-            blake2b.update(&(placeholder.as_bytes().len() as u64).to_le_bytes());
-            blake2b.update(&placeholder.as_bytes());
-
-            // 2. hash the rest witnesses.
-            for _ in 1..cnt {
-                blake2b.update(&(placeholder.as_bytes().len() as u64).to_le_bytes());
-                blake2b.update(&placeholder.as_bytes());
+            let user = users.get(&input.cell_output.calc_lock_hash()).unwrap();
+            if !signers
+                .iter()
+                .any(|signer| signer.single_secp256k1_address() == user.single_secp256k1_address())
+            {
+                signers.push(user.clone());
             }
-
-            // 3. sign the hashed message
-            let mut message = [0u8; 32];
-            blake2b.finalize(&mut message);
-            let sig = users
-                .get(&script.calc_script_hash())
-                .unwrap()
-                .sign_recoverable(&Message::from(message));
-            let first_witness = WitnessArgs::new_builder()
-                .lock(Some(Bytes::from(sig.serialize())).pack())
-                .build();
-
-            witnesses.push(first_witness.as_bytes().pack());
-            witnesses.extend((1..cnt).map(|_| placeholder.as_bytes().pack()));
         }
-
-        witnesses
+        signers
     };
-
-    let signed_tx = unsigned_tx
-        .as_advanced_builder()
-        .witnesses(witnesses)
-        .build();
+    // All the inputs are collected into the change output
+    let signed_tx = TxBuilder::new()
+        .inputs(inputs.iter().cloned())
+        .cell_dep(owner.single_secp256k1_cell_dep())
+        .change_lock(owner.single_secp256k1_lock_script_via_data())
+        .signers(signers)
+        .build(&mut NoCollector)
+        .unwrap_or_else(|err| panic!("failed to build collect-transaction, error: {}", err));
     let result = maybe_retry_send_transaction(&nodes[0], &signed_tx);
     assert!(
         result.is_ok(),
@@ -21,7 +21,7 @@ fn test_bench() {
     let nodes: Nodes = node_options()
         .into_iter()
         .map(|node_options| {
-            let mut node = Node::init("test_bench", node_options, true);
+            let mut node = Node::init("test_bench", node_options);
             node.start();
             node
         })
@@ -12,7 +12,7 @@ fn test_mine() {
     let nodes: Nodes = node_options()
         .into_iter()
         .map(|node_options| {
-            let mut node = Node::init("test_mine", node_options, true);
+            let mut node = Node::init("test_mine", node_options);
             node.start();
             node
         })
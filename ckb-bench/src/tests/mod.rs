@@ -1,29 +1,32 @@
-use ckb_testkit::NodeOptions;
+use ckb_testkit::{register_ckb_binary, NodeOptions};
 use std::path::PathBuf;
 
 pub mod bench;
 pub mod mine;
 pub mod prepare;
 
+// The nodes run the `ckb` found in `PATH`
 pub(self) fn node_options() -> Vec<NodeOptions> {
+    register_ckb_binary("ckb", PathBuf::from("ckb"))
+        .unwrap_or_else(|err| panic!("failed to register ckb binary, error: {}", err));
     vec![
         NodeOptions {
             node_name: String::from("node2021_1"),
-            ckb_binary: PathBuf::from("ckb"),
+            ckb_binary: String::from("ckb"),
             initial_database: "testdata/db/empty",
             chain_spec: "testdata/spec/ckb2021",
             app_config: "testdata/config/ckb2021",
         },
         // NodeOptions {
         //     node_name: String::from("node2021_2"),
-        //     ckb_binary: PathBuf::from("ckb"),
+        //     ckb_binary: String::from("ckb"),
         //     initial_database: "testdata/db/empty",
         //     chain_spec: "testdata/spec/ckb2021",
         //     app_config: "testdata/config/ckb2021",
         // },
         // NodeOptions {
         //     node_name: String::from("node2021_3"),
-        //     ckb_binary: PathBuf::from("ckb"),
+        //     ckb_binary: String::from("ckb"),
         //     initial_database: "testdata/db/empty",
         //     chain_spec: "testdata/spec/ckb2021",
         //     app_config: "testdata/config/ckb2021",
@@ -19,7 +19,7 @@ fn test_prepare() {
     let nodes: Nodes = node_options()
         .into_iter()
         .map(|node_options| {
-            let mut node = Node::init("test_prepare", node_options, true);
+            let mut node = Node::init("test_prepare", node_options);
             node.start();
             node
         })
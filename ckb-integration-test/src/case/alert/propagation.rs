@@ -0,0 +1,155 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_crypto::secp::Privkey;
+use ckb_testkit::ckb_hash::blake2b_256;
+use ckb_testkit::connector::{
+    message::{build_alert, build_raw_alert},
+    ConnectorBuilder, SharedState, SimpleServiceHandler,
+};
+use ckb_testkit::util::wait_until;
+use ckb_testkit::SupportProtocols;
+use std::sync::{Arc, RwLock};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// The nodes are configured to accept alerts signed by this key, see
+// `testdata/config/alert_signature/ckb.patch.toml`
+const NOTIFIER_SEED: &[u8] = b"alert notifier";
+const ATTACKER_SEED: &[u8] = b"alert attacker";
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    signer_seed: &'static [u8],
+    expected_noticed: bool,
+}
+
+/// ## Note
+///
+/// `node-a` and `node-b` are connected. A connector sends an alert to `node-a`, which should
+/// verify the signature, then notice the alert and relay it to `node-b`.
+///
+/// ```text
+/// ┌────┬───────────┬──────────────────────────────────┐
+/// │ id │ signed by │ noticed by node-a and node-b     │
+/// ├────┼───────────┼──────────────────────────────────┤
+/// │ 0  │ notifier  │ yes                              │
+/// ├────┼───────────┼──────────────────────────────────┤
+/// │ 1  │ attacker  │ no, rejected for bad signature   │
+/// └────┴───────────┴──────────────────────────────────┘
+/// ```
+pub struct AlertPropagation;
+
+impl ParameterizedCase for AlertPropagation {
+    type Params = CaseParams;
+    type Context = ();
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![
+                NodeOptions {
+                    node_name: String::from("node-a"),
+                    ckb_binary: CKB2021.to_string(),
+                    initial_database: "testdata/db/empty",
+                    chain_spec: "testdata/spec/ckb2021",
+                    app_config: "testdata/config/alert_signature",
+                },
+                NodeOptions {
+                    node_name: String::from("node-b"),
+                    ckb_binary: CKB2021.to_string(),
+                    initial_database: "testdata/db/empty",
+                    chain_spec: "testdata/spec/ckb2021",
+                    app_config: "testdata/config/alert_signature",
+                },
+            ],
+        }
+    }
+
+    fn prepare(&self, _nodes: &Nodes) {}
+
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                signer_seed: NOTIFIER_SEED,
+                expected_noticed: true,
+            },
+            CaseParams {
+                id: 1,
+                signer_seed: ATTACKER_SEED,
+                expected_noticed: false,
+            },
+        ]
+    }
+
+    fn run_one(&self, _context: &(), nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node_a = nodes.get_node("node-a");
+        let node_b = nodes.get_node("node-b");
+        // The row nodes are clones of the template nodes, connect them here
+        nodes.p2p_connect();
+
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut connector = ConnectorBuilder::new()
+            .simple_protocols(
+                &shared,
+                &[
+                    SupportProtocols::Sync,
+                    SupportProtocols::Identify,
+                    SupportProtocols::Alert,
+                    SupportProtocols::DisconnectMessage,
+                ],
+            )
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        connector.connect_and_identify(node_a)?;
+
+        let alert_id = case.id as u32 + 1;
+        let alert = {
+            let notice_until = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time")
+                .as_millis() as u64
+                + 60 * 60 * 1000;
+            let raw_alert = build_raw_alert(
+                alert_id,
+                0,
+                1,
+                notice_until,
+                &format!("case-{} alert", case.id),
+            );
+            let signer = Privkey::from_slice(&blake2b_256(case.signer_seed));
+            build_alert(raw_alert, &[&signer])
+        };
+        connector.send_alert(node_a, &alert)?;
+
+        let noticed = |node: &Node| {
+            node.rpc_client()
+                .get_blockchain_info()
+                .alerts
+                .iter()
+                .any(|alert| alert.id.value() == alert_id)
+        };
+        let (noticed_a, noticed_b) = if case.expected_noticed {
+            (
+                wait_until(10, || noticed(node_a)),
+                wait_until(10, || noticed(node_b)),
+            )
+        } else {
+            sleep(Duration::from_secs(5));
+            (noticed(node_a), noticed(node_b))
+        };
+        if let Ok(reason) = connector.wait_disconnect_message(node_a, Duration::from_secs(0)) {
+            ckb_testkit::info!("case.id: {}, node-a disconnected: {}", case.id, reason);
+        }
+        assert_eq!(
+            (case.expected_noticed, case.expected_noticed),
+            (noticed_a, noticed_b),
+            "case.id: {}, node-a.log: {}, node-b.log: {}",
+            case.id,
+            node_a.log_path().to_string_lossy(),
+            node_b.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,262 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_crypto::secp::Privkey;
+use ckb_testkit::ckb_hash::blake2b_256;
+use ckb_testkit::ckb_types::{
+    core::{BlockNumber, Capacity, EpochNumberWithFraction},
+    packed::CellOutput,
+    prelude::*,
+};
+use ckb_testkit::{always_success_collector, BulkMiningOptions, TxBuilder, User, DEFAULT_FEE_RATE};
+use std::collections::HashMap;
+
+const BULK_BLOCKS: u64 = 30;
+const TRANSACTIONS_PER_BLOCK: usize = 4;
+const FUNDED_CELLS: usize = 8;
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    transfer: Transfer,
+    verify: bool,
+    // Bulk mine on a chain whose `cellbase_maturity` is a fraction of an epoch, see
+    // `testdata/spec/cellbase_maturity_not_zero_2021`
+    fractional_maturity: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Transfer {
+    // always-success cells, without fee
+    AlwaysSuccess,
+    // single secp256k1 cells, paying `DEFAULT_FEE_RATE`
+    SingleSecp256k1,
+}
+
+/// ## Note
+///
+/// Bulk mine blocks carrying transfer transactions, then mine on top of them via block
+/// templates. With `verify: true` the node fully verifies the generated blocks, i.e. the
+/// signatures, the fees, the cellbase rewards and the dao fields computed by `bulk_mine`.
+///
+/// ```text
+/// ┌────┬─────────────────────────┬─────────┬─────────────────────┐
+/// │ id │ transfer                │ verify  │ cellbase maturity   │
+/// ├────┼─────────────────────────┼─────────┼─────────────────────┤
+/// │ 0  │ always-success, no fee  │ true    │ 0                   │
+/// ├────┼─────────────────────────┼─────────┼─────────────────────┤
+/// │ 1  │ secp256k1, with fee     │ true    │ 0                   │
+/// ├────┼─────────────────────────┼─────────┼─────────────────────┤
+/// │ 2  │ secp256k1, with fee     │ false   │ 0                   │
+/// ├────┼─────────────────────────┼─────────┼─────────────────────┤
+/// │ 3  │ always-success, no fee  │ false   │ (0, 10, 1800)       │
+/// └────┴─────────────────────────┴─────────┴─────────────────────┘
+/// ```
+///
+/// Case 3 starts its own node, whose recent cellbases are immature. The node does not verify
+/// the blocks, so the case checks that no transaction spends an immature cellbase itself.
+pub struct BasicBulkMining;
+
+impl ParameterizedCase for BasicBulkMining {
+    type Params = CaseParams;
+    type Context = User;
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    // Fund the user with single secp256k1 cells, then mine beyond the finalization delay, as
+    // `bulk_mine` computes the rewards of the blocks before it without fees
+    fn prepare(&self, nodes: &Nodes) -> User {
+        let node2021 = nodes.get_node("node2021");
+        node2021.mine(20);
+
+        let privkey = Privkey::from_slice(&blake2b_256(b"BasicBulkMining"));
+        let user = User::new(node2021.genesis_block().clone(), Some(privkey));
+        let output = CellOutput::new_builder()
+            .lock(user.single_secp256k1_lock_script_via_type())
+            .capacity(Capacity::bytes(1000).unwrap().pack())
+            .build();
+        let tx = TxBuilder::new()
+            .outputs(vec![(output, Default::default()); FUNDED_CELLS])
+            .cell_dep(node2021.always_success_cell_dep())
+            .change_lock(node2021.always_success_script())
+            .build(&mut always_success_collector(node2021))
+            .expect("fund the user");
+        node2021.submit_transaction(&tx);
+        while !node2021.is_transaction_committed(&tx) {
+            node2021.mine(1);
+        }
+        let finalization_delay_length =
+            node2021.consensus().tx_proposal_window.farthest.value() + 1;
+        node2021.mine(finalization_delay_length);
+        user
+    }
+
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                transfer: Transfer::AlwaysSuccess,
+                verify: true,
+                fractional_maturity: false,
+            },
+            CaseParams {
+                id: 1,
+                transfer: Transfer::SingleSecp256k1,
+                verify: true,
+                fractional_maturity: false,
+            },
+            CaseParams {
+                id: 2,
+                transfer: Transfer::SingleSecp256k1,
+                verify: false,
+                fractional_maturity: false,
+            },
+            CaseParams {
+                id: 3,
+                transfer: Transfer::AlwaysSuccess,
+                verify: false,
+                fractional_maturity: true,
+            },
+        ]
+    }
+
+    fn run_one(&self, user: &User, nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let maturity_node;
+        let node = if case.fractional_maturity {
+            maturity_node = self.setup_maturity_node(case);
+            &maturity_node
+        } else {
+            nodes.get_node("node2021")
+        };
+        let user_capacity = || -> u64 {
+            user.get_spendable_single_secp256k1_cells(node)
+                .iter()
+                .map(|cell| cell.capacity().as_u64())
+                .sum()
+        };
+        let capacity_before = user_capacity();
+        let tip_before = node.get_tip_block_number();
+        let options = BulkMiningOptions {
+            transactions_per_block: TRANSACTIONS_PER_BLOCK,
+            transfer_user: if case.transfer == Transfer::SingleSecp256k1 {
+                Some(user.clone())
+            } else {
+                None
+            },
+            fee_rate: DEFAULT_FEE_RATE,
+            verify: case.verify,
+            ..Default::default()
+        };
+        node.bulk_mine(BULK_BLOCKS, options);
+        if node.get_tip_block_number() != tip_before + BULK_BLOCKS {
+            return Err(format!(
+                "expected tip {} after bulk mining, got {}",
+                tip_before + BULK_BLOCKS,
+                node.get_tip_block_number()
+            ));
+        }
+
+        // Every transaction is proposed `closest` blocks before committed, and none is proposed
+        // in the last `closest` blocks
+        let closest = node.consensus().tx_proposal_window.closest.value();
+        let committed = (tip_before + 1..=tip_before + BULK_BLOCKS)
+            .map(|number| node.get_block_by_number(number).transactions().len() - 1)
+            .sum::<usize>();
+        let expected_committed = TRANSACTIONS_PER_BLOCK * (BULK_BLOCKS - closest) as usize;
+        if committed != expected_committed {
+            return Err(format!(
+                "expected {} transactions committed, got {}",
+                expected_committed, committed
+            ));
+        }
+        check_cellbase_maturity(node, tip_before + 1, tip_before + BULK_BLOCKS)?;
+        let capacity_after = user_capacity();
+        match case.transfer {
+            Transfer::AlwaysSuccess if capacity_after != capacity_before => {
+                return Err(format!(
+                    "the user's capacity changes from {} to {}",
+                    capacity_before, capacity_after
+                ));
+            }
+            Transfer::SingleSecp256k1 if capacity_after >= capacity_before => {
+                return Err(format!(
+                    "expected the user pays fees, capacity before: {}, after: {}",
+                    capacity_before, capacity_after
+                ));
+            }
+            _ => {}
+        }
+
+        // The node builds on top of the generated blocks, whose cellbases and dao fields it
+        // takes as given when `verify` is false
+        let farthest = node.consensus().tx_proposal_window.farthest.value();
+        node.mine(farthest + 2);
+        Ok(())
+    }
+}
+
+impl BasicBulkMining {
+    // Start a node whose cellbases mature `10 / 1800` epochs, i.e. 6 blocks, after committed,
+    // so that the cellbases of the last 6 blocks are immature
+    fn setup_maturity_node(&self, case: &CaseParams) -> Node {
+        let node_options = NodeOptions {
+            node_name: format!("case-{}-node2021", case.id),
+            ckb_binary: CKB2021.to_string(),
+            initial_database: "testdata/db/empty",
+            chain_spec: "testdata/spec/cellbase_maturity_not_zero_2021",
+            app_config: "testdata/config/ckb2021",
+        };
+        let mut node = Node::init(self.case_name(), node_options);
+        node.start();
+        node.mine(20);
+        node
+    }
+}
+
+// Return an error if a transaction committed in the blocks `from..=to` spends a cellbase which
+// is immature, i.e. `cellbase.block_epoch + cellbase_maturity > block_epoch`, like ckb checks
+fn check_cellbase_maturity(node: &Node, from: BlockNumber, to: BlockNumber) -> Result<(), String> {
+    let maturity =
+        EpochNumberWithFraction::from_full_value(node.consensus().cellbase_maturity.into())
+            .to_rational();
+    let mut cellbase_epochs = HashMap::new();
+    for number in 0..=to {
+        let block = node.get_block_by_number(number);
+        if let Some(cellbase) = block.transaction(0) {
+            cellbase_epochs.insert(cellbase.hash(), (number, block.epoch()));
+        }
+        if number < from {
+            continue;
+        }
+        for tx in block.transactions().iter().skip(1) {
+            for input in tx.inputs() {
+                let previous = input.previous_output();
+                if let Some((cellbase_number, cellbase_epoch)) =
+                    cellbase_epochs.get(&previous.tx_hash())
+                {
+                    if block.epoch().to_rational() < cellbase_epoch.to_rational() + maturity.clone()
+                    {
+                        return Err(format!(
+                            "block {} commits tx {:#x} spending the immature cellbase of block {}",
+                            number,
+                            tx.hash(),
+                            cellbase_number
+                        ));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,170 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_crypto::secp::Privkey;
+use ckb_testkit::ckb_hash::blake2b_256;
+use ckb_testkit::ckb_types::{
+    core::{Capacity, TransactionView},
+    packed::{CellDep, CellOutput, OutPoint, Script},
+    prelude::*,
+};
+use ckb_testkit::{
+    always_success_collector, single_secp256k1_collector, Deployer, NoCollector, Payer, TestScript,
+    TxBuilder, User,
+};
+
+const CODE: &str = "code";
+
+/// ## Note
+///
+/// Deploy a lock script with Type ID, paid by a single secp256k1 user, then upgrade it and
+/// check the deployment manifest.
+///
+/// 1. Deploy `always_success` with Type ID, locked by and paid by the user. A cell locked by
+///    the script referenced via type hash is spendable.
+///
+/// 2. The manifest loaded from `save_manifest` equals the deployer's.
+///
+/// 3. Upgrade the code to `another_always_success`, paid by the user, who also signs the
+///    upgraded cell. The type hash is kept and the data hash changes. The cell locked via type
+///    hash is still spendable, with the cell dep of the upgraded code.
+///
+/// 4. Loading the stale manifest fails, as the deployed cell is dead; loading the saved
+///    manifest of the upgraded deployer succeeds.
+pub struct BasicDeployment;
+
+impl Case for BasicDeployment {
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn run(&self, nodes: Nodes) {
+        let node = nodes.get_node("node2021");
+        node.mine(20);
+        let privkey = Privkey::from_slice(&blake2b_256(b"BasicDeployment"));
+        let user = User::new(node.genesis_block().clone(), Some(privkey));
+        fund(node, &user);
+
+        // 1. deploy with Type ID
+        let mut deployer = Deployer::new().commit_via_tx_pool(true);
+        {
+            let binary = TestScript::AlwaysSuccess.binary();
+            // Reserve the capacity of the Type ID script, 32 + 1 + 32 bytes
+            let output = CellOutput::new_builder()
+                .lock(user.single_secp256k1_lock_script_via_type())
+                .build_exact_capacity(Capacity::bytes(binary.len() + 65).unwrap())
+                .unwrap();
+            let mut collector = single_secp256k1_collector(node, &user);
+            let mut payer = Payer::single_secp256k1(&user, &mut collector);
+            deployer.deploy_with_type_id_paid_by(node, CODE, output, binary.pack(), &mut payer);
+        }
+        let type_hash = deployer
+            .get_cell(CODE)
+            .cell_output
+            .type_()
+            .to_opt()
+            .expect("deployed with Type ID")
+            .calc_script_hash();
+        let lock = deployer.script_via_type(CODE);
+        spend_locked_cell(node, &lock, deployer.cell_dep(CODE));
+
+        // 2. save and load the manifest
+        let manifest_path = node.working_dir().join("deployment.json");
+        deployer
+            .save_manifest(&manifest_path)
+            .expect("save manifest");
+        let loaded = Deployer::load_manifest(node, &manifest_path).expect("load manifest");
+        assert_eq!(loaded.manifest(), deployer.manifest());
+
+        // 3. upgrade
+        {
+            let mut collector = single_secp256k1_collector(node, &user);
+            let mut payer = Payer::single_secp256k1(&user, &mut collector);
+            deployer.upgrade_paid_by(
+                node,
+                CODE,
+                TestScript::AnotherAlwaysSuccess.binary().pack(),
+                &mut payer,
+            );
+        }
+        let upgraded = deployer.manifest()[CODE].clone();
+        assert_eq!(upgraded.type_hash, Some(type_hash));
+        assert_eq!(
+            upgraded.data_hash,
+            TestScript::AnotherAlwaysSuccess.data_hash()
+        );
+        spend_locked_cell(node, &lock, deployer.cell_dep(CODE));
+
+        // 4. the stale manifest refers to a dead cell
+        let result = Deployer::load_manifest(node, &manifest_path);
+        assert!(
+            result.is_err(),
+            "loading the stale manifest should fail, but got {:?}",
+            result.map(|deployer| deployer.manifest())
+        );
+        deployer
+            .save_manifest(&manifest_path)
+            .expect("save manifest");
+        let loaded = Deployer::load_manifest(node, &manifest_path).expect("load manifest");
+        assert_eq!(loaded.manifest(), deployer.manifest());
+    }
+}
+
+// Transfer some always-success capacity to `user`'s single secp256k1 lock
+fn fund(node: &Node, user: &User) {
+    let output = CellOutput::new_builder()
+        .lock(user.single_secp256k1_lock_script_via_type())
+        .capacity(Capacity::bytes(100_000).unwrap().pack())
+        .build();
+    let tx = TxBuilder::new()
+        .output(output, Default::default())
+        .cell_dep(node.always_success_cell_dep())
+        .change_lock(node.always_success_script())
+        .build(&mut always_success_collector(node))
+        .expect("fund the user");
+    commit(node, &tx);
+}
+
+// Create a cell locked by `lock`, then spend it with `code_cell_dep`
+fn spend_locked_cell(node: &Node, lock: &Script, code_cell_dep: CellDep) {
+    let output = CellOutput::new_builder()
+        .lock(lock.clone())
+        .capacity(Capacity::bytes(1000).unwrap().pack())
+        .build();
+    let create_tx = TxBuilder::new()
+        .output(output, Default::default())
+        .cell_dep(node.always_success_cell_dep())
+        .change_lock(node.always_success_script())
+        .build(&mut always_success_collector(node))
+        .expect("create the locked cell");
+    commit(node, &create_tx);
+
+    let locked = node
+        .get_cell_meta(OutPoint::new(create_tx.hash(), 0))
+        .expect("locked cell is live");
+    let spend_tx = TxBuilder::new()
+        .input(locked)
+        .cell_dep(code_cell_dep)
+        .change_lock(node.always_success_script())
+        .build(&mut NoCollector)
+        .expect("spend the locked cell");
+    commit(node, &spend_tx);
+}
+
+fn commit(node: &Node, tx: &TransactionView) {
+    node.rpc_client()
+        .send_transaction_result(tx.data().into())
+        .unwrap_or_else(|err| panic!("failed to send tx {:#x}, error: {}", tx.hash(), err));
+    while !node.is_transaction_committed(tx) {
+        node.mine(1);
+    }
+}
@@ -1 +1,4 @@
+pub(super) mod bulk_mining;
+pub(super) mod deployment;
 pub(super) mod networking;
+pub(super) mod test_scripts;
@@ -1,47 +1,77 @@
-use crate::case::{Case, CaseOptions};
-use crate::{CKB2019, CKB2021};
-use ckb_testkit::NodeOptions;
-use ckb_testkit::Nodes;
+use crate::prelude::*;
+use crate::util::mixed_pairs;
+use ckb_testkit::CkbBinary;
 
+/// Connect a node of every mixed pair of the registered ckb binaries, and check that they
+/// synchronize with each other before the fork2021. Registering more binaries via `--ckb` adds
+/// rows, e.g. `--ckb v0.43=... --ckb v0.100=... --ckb current=...`.
 pub struct BasicNetworking;
 
-impl Case for BasicNetworking {
+impl ParameterizedCase for BasicNetworking {
+    type Params = (CkbBinary, CkbBinary);
+    type Context = ();
+
     fn case_options(&self) -> CaseOptions {
-        CaseOptions {
-            make_all_nodes_connected: false,
-            make_all_nodes_synced: false,
-            make_all_nodes_connected_and_synced: false,
-            node_options: vec![
-                NodeOptions {
-                    node_name: String::from("node2019"),
-                    ckb_binary: CKB2019.read().unwrap().clone(),
-                    initial_database: "testdata/db/Height13TestData",
-                    chain_spec: "testdata/spec/ckb2019",
-                    app_config: "testdata/config/ckb2019",
-                },
-                NodeOptions {
-                    node_name: String::from("node2021"),
-                    ckb_binary: CKB2021.read().unwrap().clone(),
-                    initial_database: "testdata/db/Height13TestData",
-                    chain_spec: "testdata/spec/ckb2021",
-                    app_config: "testdata/config/ckb2021",
-                },
-            ]
-            .into_iter()
-            .collect(),
-        }
+        Default::default()
+    }
+
+    fn requires(&self) -> Requirements {
+        Requirements::new().testdata("Height13TestData")
+    }
+
+    // Every row sets up its own nodes, see `setup_node`
+    fn prepare(&self, _nodes: &Nodes) {}
+
+    fn params(&self) -> Vec<(CkbBinary, CkbBinary)> {
+        mixed_pairs()
     }
 
-    fn run(&self, nodes: Nodes) {
-        let node2019 = nodes.get_node("node2019");
-        let node2021 = nodes.get_node("node2021");
-        node2019.mine(10);
-        node2021.mine(10);
-        node2019.p2p_connect(node2021);
-
-        node2019.mine(10);
-        nodes.waiting_for_sync().expect("waiting for sync");
-        node2021.mine(10);
-        nodes.waiting_for_sync().expect("waiting for sync");
+    fn run_one(
+        &self,
+        _context: &(),
+        _nodes: &Nodes,
+        (binary_a, binary_b): &(CkbBinary, CkbBinary),
+    ) -> Result<(), String> {
+        let node_a = self.setup_node(binary_a);
+        let node_b = self.setup_node(binary_b);
+        let nodes: Nodes = vec![node_a, node_b].into();
+        let node_a = nodes.get_node(&node_name(binary_a));
+        let node_b = nodes.get_node(&node_name(binary_b));
+        node_a.mine(10);
+        node_b.mine(10);
+        node_a.p2p_connect(node_b);
+
+        node_a.mine(10);
+        nodes
+            .waiting_for_sync()
+            .map_err(|unsynced| format!("waiting for sync, tips: {:?}", unsynced))?;
+        node_b.mine(10);
+        nodes
+            .waiting_for_sync()
+            .map_err(|unsynced| format!("waiting for sync, tips: {:?}", unsynced))
+    }
+}
+
+impl BasicNetworking {
+    fn setup_node(&self, binary: &CkbBinary) -> Node {
+        let (chain_spec, app_config) = if binary.is_ckb2021() {
+            ("testdata/spec/ckb2021", "testdata/config/ckb2021")
+        } else {
+            ("testdata/spec/ckb2019", "testdata/config/ckb2019")
+        };
+        let node_options = NodeOptions {
+            node_name: node_name(binary),
+            ckb_binary: binary.name.clone(),
+            initial_database: "testdata/db/Height13TestData",
+            chain_spec,
+            app_config,
+        };
+        let mut node = Node::init(self.case_name(), node_options);
+        node.start();
+        node
     }
 }
+
+fn node_name(binary: &CkbBinary) -> String {
+    format!("node-{}", binary.name)
+}
@@ -0,0 +1,247 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_types::{
+    bytes::Bytes,
+    core::ScriptHashType,
+    packed::{Script, WitnessArgs},
+    prelude::*,
+};
+use ckb_testkit::{
+    assert_result_eq, Deployer, LoadSyscall, Source, SpawnParams, TestScript, TxBuilder,
+};
+
+const ERROR_CODE_1: &str = "error code 1 in the page";
+const ERROR_INVALID_ECALL: &str = "InvalidEcall";
+const BURNT_CYCLES: u64 = 100_000;
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    invocation: Invocation,
+    expected_result: Result<(), String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Invocation {
+    AlwaysFailure,
+    // Also checks that the dry-run cycles grow by at least N
+    CycleBurner(u64),
+    SyscallExerciser(LoadSyscall, u64, Source),
+    // Whether the witness matches the script args
+    ArgsAsserter(bool),
+    // Tries to spawn `always_success`, the first cell dep, and expects the spawn syscall to be
+    // rejected
+    SpawnRejected,
+}
+
+/// ## Note
+///
+/// Run every bundled test script written for ckb-testkit as a lock, referenced via data hash
+/// with `ScriptHashType::Data`. Every invocation spends its own locked cell, with the tip block
+/// as the header dep.
+///
+/// ```text
+/// ┌────┬───────────────────┬──────────────────────────────┬───────────────────┐
+/// │ id │ script            │ args                         │ result            │
+/// ├────┼───────────────────┼──────────────────────────────┼───────────────────┤
+/// │ 0  │ always_failure    │                              │ error code 1      │
+/// │ 1  │ cycle_burner      │ 100_000                      │ Ok                │
+/// │ 2  │ syscall_exerciser │ load_header, 0, HeaderDep    │ Ok                │
+/// │ 3  │ syscall_exerciser │ load_header, 1, HeaderDep    │ error code 1      │
+/// │ 4  │ syscall_exerciser │ load_witness, 0, GroupInput  │ Ok                │
+/// │ 5  │ syscall_exerciser │ load_cell, 1, Output         │ error code 1      │
+/// │ 6  │ syscall_exerciser │ load_cell_data, 0, Input     │ Ok                │
+/// │ 7  │ args_asserter     │ the witness                  │ Ok                │
+/// │ 8  │ args_asserter     │ not the witness              │ error code 1      │
+/// │ 9  │ spawn_caller      │ 0, CellDep, 0, 0             │ InvalidEcall      │
+/// └────┴───────────────────┴──────────────────────────────┴───────────────────┘
+/// ```
+///
+/// None of the ckb binaries this case runs on implements the spawn syscall, so case 9 only
+/// checks that `spawn_caller` is rejected with `InvalidEcall`. It does not exercise a successful
+/// spawn.
+pub struct BasicTestScripts;
+
+impl ParameterizedCase for BasicTestScripts {
+    type Params = CaseParams;
+    /// The deployed test scripts
+    type Context = Deployer;
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn prepare(&self, nodes: &Nodes) -> Deployer {
+        let node2021 = nodes.get_node("node2021");
+        node2021.mine(20);
+        let mut deployer = Deployer::new();
+        for script in &[
+            TestScript::AlwaysSuccess,
+            TestScript::AlwaysFailure,
+            TestScript::CycleBurner,
+            TestScript::SyscallExerciser,
+            TestScript::ArgsAsserter,
+            TestScript::SpawnCaller,
+        ] {
+            deployer.deploy_test_script(node2021, *script);
+        }
+        deployer
+    }
+
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                invocation: Invocation::AlwaysFailure,
+                expected_result: Err(ERROR_CODE_1.to_string()),
+            },
+            CaseParams {
+                id: 1,
+                invocation: Invocation::CycleBurner(BURNT_CYCLES),
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 2,
+                invocation: Invocation::SyscallExerciser(LoadSyscall::Header, 0, Source::HeaderDep),
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 3,
+                invocation: Invocation::SyscallExerciser(LoadSyscall::Header, 1, Source::HeaderDep),
+                expected_result: Err(ERROR_CODE_1.to_string()),
+            },
+            CaseParams {
+                id: 4,
+                invocation: Invocation::SyscallExerciser(
+                    LoadSyscall::Witness,
+                    0,
+                    Source::GroupInput,
+                ),
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 5,
+                invocation: Invocation::SyscallExerciser(LoadSyscall::Cell, 1, Source::Output),
+                expected_result: Err(ERROR_CODE_1.to_string()),
+            },
+            CaseParams {
+                id: 6,
+                invocation: Invocation::SyscallExerciser(LoadSyscall::CellData, 0, Source::Input),
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 7,
+                invocation: Invocation::ArgsAsserter(true),
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 8,
+                invocation: Invocation::ArgsAsserter(false),
+                expected_result: Err(ERROR_CODE_1.to_string()),
+            },
+            CaseParams {
+                id: 9,
+                invocation: Invocation::SpawnRejected,
+                expected_result: Err(ERROR_INVALID_ECALL.to_string()),
+            },
+        ]
+    }
+
+    fn run_one(&self, deployer: &Deployer, nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let witness = WitnessArgs::new_builder()
+            .input_type(Some(Bytes::from(vec![case.id as u8])).pack())
+            .build();
+        let builder = TxBuilder::new()
+            .cell_dep(deployer.cell_dep(TestScript::AlwaysSuccess.name()))
+            .header_dep(node.get_tip_block().hash());
+        let (script, args) = match case.invocation {
+            Invocation::AlwaysFailure => (TestScript::AlwaysFailure, Bytes::new()),
+            Invocation::CycleBurner(n) => {
+                (TestScript::CycleBurner, TestScript::cycle_burner_args(n))
+            }
+            Invocation::SyscallExerciser(syscall, index, source) => (
+                TestScript::SyscallExerciser,
+                TestScript::syscall_exerciser_args(syscall, index, source),
+            ),
+            Invocation::ArgsAsserter(true) => (TestScript::ArgsAsserter, witness.as_bytes()),
+            Invocation::ArgsAsserter(false) => {
+                (TestScript::ArgsAsserter, Bytes::from(vec![0u8; 4]))
+            }
+            Invocation::SpawnRejected => {
+                let params = SpawnParams {
+                    index: 0,
+                    source: Source::CellDep,
+                    place: 0,
+                    bounds: 0,
+                };
+                (TestScript::SpawnCaller, params.serialize())
+            }
+        };
+        let lock = deployer.test_script(script, ScriptHashType::Data, args);
+
+        if let Invocation::CycleBurner(n) = case.invocation {
+            let idle_lock = deployer.test_script(
+                script,
+                ScriptHashType::Data,
+                TestScript::cycle_burner_args(0),
+            );
+            let idle_cycles =
+                dry_run_cycles(node, deployer, script, idle_lock, &witness, &builder)?;
+            let burnt_cycles =
+                dry_run_cycles(node, deployer, script, lock.clone(), &witness, &builder)?;
+            if burnt_cycles < idle_cycles + n {
+                return Err(format!(
+                    "expected burning {} cycles at least, idle cycles: {}, burnt cycles: {}",
+                    n, idle_cycles, burnt_cycles
+                ));
+            }
+        }
+
+        let actual_result = deployer
+            .invoke_test_script(node, script, lock, witness, builder)
+            .map(|_| ());
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case: {}, expected: {:?}, actual: {:?}, node.log_path: {}",
+            case.id,
+            case.expected_result,
+            actual_result,
+            node.log_path().to_string_lossy(),
+        );
+        Ok(())
+    }
+}
+
+fn dry_run_cycles(
+    node: &Node,
+    deployer: &Deployer,
+    script: TestScript,
+    lock: Script,
+    witness: &WitnessArgs,
+    builder: &TxBuilder,
+) -> Result<u64, String> {
+    let tx = deployer.build_test_script_invocation(
+        node,
+        script,
+        lock,
+        witness.clone(),
+        builder.clone(),
+    )?;
+    Ok(node
+        .rpc_client()
+        .dry_run_transaction(tx.data().into())
+        .cycles
+        .value())
+}
@@ -0,0 +1,126 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_types::prelude::*;
+use ckb_testkit::connector::{
+    message::{build_sync_get_headers, network_identifier},
+    pretty_print, read_capture, ConnectorBuilder, Direction, Replayer, SharedState,
+    SimpleProtocolHandler, SimpleServiceHandler,
+};
+use ckb_testkit::SupportProtocols;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// ## Note
+///
+/// A connector captures its session to `node-origin`, in which it sends a truncated
+/// `GetHeaders` and gets dropped. Replaying the capture to `node-replay` should reproduce the
+/// same reaction, which is how a captured bug becomes a regression fixture.
+pub struct CaptureReplay;
+
+impl Case for CaptureReplay {
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![
+                NodeOptions {
+                    node_name: String::from("node-origin"),
+                    ckb_binary: CKB2021.to_string(),
+                    initial_database: "testdata/db/empty",
+                    chain_spec: "testdata/spec/ckb2021",
+                    app_config: "testdata/config/ckb2021",
+                },
+                NodeOptions {
+                    node_name: String::from("node-replay"),
+                    ckb_binary: CKB2021.to_string(),
+                    initial_database: "testdata/db/empty",
+                    chain_spec: "testdata/spec/ckb2021",
+                    app_config: "testdata/config/ckb2021",
+                },
+            ],
+        }
+    }
+
+    fn run(&self, nodes: Nodes) {
+        let node_origin = nodes.get_node("node-origin");
+        let node_replay = nodes.get_node("node-replay");
+        let capture_path = node_origin.working_dir().join("connector.cap");
+
+        // Capture
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut connector = ConnectorBuilder::new()
+            .capture_to(&capture_path)
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true)
+            })
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Identify)
+                    .build(false)
+            })
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        connector.connect(node_origin).expect("connect");
+        let observed_address = connector
+            .get_session(node_origin)
+            .expect("connected")
+            .address;
+        connector
+            .send_identify_message(
+                node_origin,
+                &network_identifier(node_origin),
+                &node_origin.rpc_client().local_node_info().version,
+                vec![],
+                observed_address,
+            )
+            .expect("send identify");
+        let truncated_get_headers = {
+            let genesis_hash = node_origin.genesis_block().hash();
+            let message = build_sync_get_headers(vec![genesis_hash], Default::default());
+            message.as_bytes().slice(0..message.as_slice().len() - 1)
+        };
+        connector
+            .send(node_origin, SupportProtocols::Sync, truncated_get_headers)
+            .expect("send truncated GetHeaders");
+        let origin_closed = connector
+            .wait_session_closed(node_origin, Duration::from_secs(10))
+            .unwrap_or_else(|err| {
+                panic!(
+                    "node-origin should drop the connector, error: {}, node-origin.log: {}",
+                    err,
+                    node_origin.log_path().to_string_lossy()
+                )
+            });
+        ckb_testkit::info!("node-origin dropped the connector, {:?}", origin_closed);
+
+        let frames = read_capture(&capture_path).expect("read capture");
+        let start_timestamp = frames.first().map(|frame| frame.timestamp).unwrap_or(0);
+        for frame in frames.iter() {
+            ckb_testkit::info!("{}", pretty_print(frame, start_timestamp));
+        }
+        let outbound_protocols = frames
+            .iter()
+            .filter(|frame| frame.direction == Direction::Outbound)
+            .map(|frame| frame.protocol_id)
+            .collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                SupportProtocols::Identify.protocol_id(),
+                SupportProtocols::Sync.protocol_id()
+            ],
+            outbound_protocols,
+        );
+
+        // Replay
+        let replay_connector = Replayer::from_capture(&capture_path)
+            .expect("read capture")
+            .replay(node_replay)
+            .expect("replay");
+        let replay_closed =
+            replay_connector.wait_session_closed(node_replay, Duration::from_secs(10));
+        assert!(
+            replay_closed.is_ok(),
+            "node-replay should drop the connector as node-origin, error: {:?}, node-replay.log: {}",
+            replay_closed,
+            node_replay.log_path().to_string_lossy()
+        );
+    }
+}
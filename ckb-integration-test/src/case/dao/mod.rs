@@ -0,0 +1,3 @@
+pub(super) mod withdraw;
+
+const ERROR_IMMATURE: &str = "Immature";
@@ -0,0 +1,217 @@
+use super::ERROR_IMMATURE;
+use crate::prelude::*;
+use ckb_testkit::ckb_types::{
+    core::{cell::CellMeta, BlockNumber, Capacity, EpochNumberWithFraction, TransactionView},
+    packed::{Byte32, OutPoint},
+};
+use ckb_testkit::{assert_result_eq, dao_minimal_unlock_epoch, BuildInstruction};
+
+const EPOCH_LENGTH: u64 = 10;
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    deposit_epoch: EpochNumberWithFraction,
+    withdraw_phase1_epoch: EpochNumberWithFraction,
+    expected_unlock_epoch: EpochNumberWithFraction,
+}
+
+/// ## Note
+///
+/// Deposit into Nervos DAO, then withdraw at the epoch boundaries around the lock period.
+///
+/// * All epochs length are `10`, see `testdata/spec/dao_short_epoch_2021`
+///
+/// * The lock period is 180 epochs, the passed epochs from deposit to phase-1 withdraw are
+///   rounded up to a multiple of it
+///
+/// * The phase-2 withdraw transaction's `since` is `abs(unlock epoch)`. It is `Immature` when
+///   committed at the block before the unlock epoch, and ok at the unlock epoch.
+///
+/// ```text
+/// ┌────┬─────────────────┬─────────────────┬─────────────────┐
+/// │ id │ deposit epoch   │ phase-1 epoch   │ unlock epoch    │
+/// ├────┼─────────────────┼─────────────────┼─────────────────┤
+/// │ 0  │ (1, 0, 10)      │ (2, 0, 10)      │ (181, 0, 10)    │
+/// ├────┼─────────────────┼─────────────────┼─────────────────┤
+/// │ 1  │ (1, 5, 10)      │ (2, 5, 10)      │ (181, 5, 10)    │
+/// ├────┼─────────────────┼─────────────────┼─────────────────┤
+/// │ 2  │ (1, 5, 10)      │ (2, 6, 10)      │ (181, 5, 10)    │
+/// ├────┼─────────────────┼─────────────────┼─────────────────┤
+/// │ 3  │ (1, 5, 10)      │ (181, 6, 10)    │ (361, 5, 10)    │
+/// └────┴─────────────────┴─────────────────┴─────────────────┘
+/// ```
+pub struct DaoWithdraw;
+
+impl ParameterizedCase for DaoWithdraw {
+    type Params = CaseParams;
+    type Context = ();
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/dao_short_epoch_2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn prepare(&self, nodes: &Nodes) {
+        nodes.get_node("node2021").mine(5);
+    }
+
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                deposit_epoch: EpochNumberWithFraction::new(1, 0, EPOCH_LENGTH),
+                withdraw_phase1_epoch: EpochNumberWithFraction::new(2, 0, EPOCH_LENGTH),
+                expected_unlock_epoch: EpochNumberWithFraction::new(181, 0, EPOCH_LENGTH),
+            },
+            CaseParams {
+                id: 1,
+                deposit_epoch: EpochNumberWithFraction::new(1, 5, EPOCH_LENGTH),
+                withdraw_phase1_epoch: EpochNumberWithFraction::new(2, 5, EPOCH_LENGTH),
+                expected_unlock_epoch: EpochNumberWithFraction::new(181, 5, EPOCH_LENGTH),
+            },
+            CaseParams {
+                id: 2,
+                deposit_epoch: EpochNumberWithFraction::new(1, 5, EPOCH_LENGTH),
+                withdraw_phase1_epoch: EpochNumberWithFraction::new(2, 6, EPOCH_LENGTH),
+                expected_unlock_epoch: EpochNumberWithFraction::new(181, 5, EPOCH_LENGTH),
+            },
+            CaseParams {
+                id: 3,
+                deposit_epoch: EpochNumberWithFraction::new(1, 5, EPOCH_LENGTH),
+                withdraw_phase1_epoch: EpochNumberWithFraction::new(181, 6, EPOCH_LENGTH),
+                expected_unlock_epoch: EpochNumberWithFraction::new(361, 5, EPOCH_LENGTH),
+            },
+        ]
+    }
+
+    fn run_one(&self, _context: &(), nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let deposit_capacity = Capacity::bytes(1000).unwrap().as_u64();
+        let deposit_tx = node.dao_deposit(deposit_capacity);
+        commit_at(node, &deposit_tx, epoch_to_height(case.deposit_epoch));
+        let deposited = get_cell_meta(node, OutPoint::new(deposit_tx.hash(), 0));
+
+        let phase1_tx = node.dao_withdraw_phase1(&deposited);
+        commit_at(
+            node,
+            &phase1_tx,
+            epoch_to_height(case.withdraw_phase1_epoch),
+        );
+        let withdrawing = get_cell_meta(node, OutPoint::new(phase1_tx.hash(), 0));
+
+        let deposit_header = node.get_block(cell_block_hash(&deposited)).header();
+        let withdrawing_header = node.get_block(cell_block_hash(&withdrawing)).header();
+        assert_eq!(
+            deposit_header.epoch(),
+            case.deposit_epoch,
+            "case.id: {}",
+            case.id
+        );
+        assert_eq!(
+            withdrawing_header.epoch(),
+            case.withdraw_phase1_epoch,
+            "case.id: {}",
+            case.id
+        );
+        assert_eq!(
+            case.expected_unlock_epoch,
+            dao_minimal_unlock_epoch(&deposit_header, &withdrawing_header),
+            "case.id: {}",
+            case.id
+        );
+
+        let phase2_tx = node.dao_withdraw_phase2(&withdrawing);
+        let mature_height = epoch_to_height(case.expected_unlock_epoch);
+        let immature_height = mature_height - 1;
+        let immature_result = node.build_according_to_instructions(
+            immature_height,
+            vec![
+                BuildInstruction::Propose {
+                    proposal_short_id: phase2_tx.proposal_short_id(),
+                    template_number: immature_height - 2,
+                },
+                BuildInstruction::Commit {
+                    transaction: phase2_tx.clone(),
+                    template_number: immature_height,
+                },
+            ],
+        );
+        assert_result_eq!(
+            Result::<(), &str>::Err(ERROR_IMMATURE),
+            immature_result,
+            "case.id: {}, node.log: {}",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+
+        let mature_result = node.build_according_to_instructions(
+            mature_height,
+            vec![BuildInstruction::Commit {
+                transaction: phase2_tx,
+                template_number: mature_height,
+            }],
+        );
+        assert_eq!(
+            Result::<(), String>::Ok(()),
+            mature_result,
+            "case.id: {}, node.log: {}",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+fn epoch_to_height(epoch: EpochNumberWithFraction) -> BlockNumber {
+    assert_eq!(epoch.length(), EPOCH_LENGTH);
+    epoch.number() * epoch.length() + epoch.index()
+}
+
+// Propose `tx` at `height - 2` and commit it at `height`.
+fn commit_at(node: &Node, tx: &TransactionView, height: BlockNumber) {
+    let result = node.build_according_to_instructions(
+        height,
+        vec![
+            BuildInstruction::Propose {
+                proposal_short_id: tx.proposal_short_id(),
+                template_number: height - 2,
+            },
+            BuildInstruction::Commit {
+                transaction: tx.clone(),
+                template_number: height,
+            },
+        ],
+    );
+    assert_eq!(
+        Result::<(), String>::Ok(()),
+        result,
+        "node.log: {}",
+        node.log_path().to_string_lossy()
+    );
+}
+
+fn get_cell_meta(node: &Node, out_point: OutPoint) -> CellMeta {
+    // Make sure the indexer has indexed the newly committed cells
+    let _ = node.indexer();
+    node.get_cell_meta(out_point)
+        .expect("committed cell is live")
+}
+
+fn cell_block_hash(cell: &CellMeta) -> Byte32 {
+    cell.transaction_info
+        .as_ref()
+        .expect("committed cell")
+        .block_hash
+        .clone()
+}
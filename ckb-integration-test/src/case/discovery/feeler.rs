@@ -0,0 +1,94 @@
+use crate::prelude::*;
+use ckb_testkit::connector::{ConnectorBuilder, SharedState, SimpleServiceHandler};
+use ckb_testkit::p2p::multiaddr::{Multiaddr, Protocol};
+use ckb_testkit::util::find_available_port;
+use ckb_testkit::SupportProtocols;
+use std::borrow::Cow;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// ## Note
+///
+/// The node feels the addresses it learns, i.e. dials them, opens the Feeler protocol to check
+/// their reachability, then disconnects.
+///
+/// An announcer connects to the node and announces the address of a listening target via
+/// `DiscoveryMessage::Nodes`. The node, which runs outbound tasks every second, should feel
+/// the target.
+pub struct DiscoveryFeeler;
+
+impl Case for DiscoveryFeeler {
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/connect_outbound_interval_secs",
+            }],
+        }
+    }
+
+    fn run(&self, nodes: Nodes) {
+        let node2021 = nodes.get_node("node2021");
+
+        let target_shared = Arc::new(RwLock::new(SharedState::new()));
+        let listening_address: Multiaddr = format!("/ip4/127.0.0.1/tcp/{}", find_available_port())
+            .parse()
+            .unwrap();
+        let target = ConnectorBuilder::new()
+            .listening_addresses(vec![listening_address.clone()])
+            .simple_protocols(
+                &target_shared,
+                &[
+                    SupportProtocols::Sync,
+                    SupportProtocols::Identify,
+                    SupportProtocols::Feeler,
+                ],
+            )
+            .build(
+                SimpleServiceHandler::new(Arc::clone(&target_shared)),
+                target_shared,
+            );
+
+        let announcer_shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut announcer = ConnectorBuilder::new()
+            .simple_protocols(
+                &announcer_shared,
+                &[
+                    SupportProtocols::Sync,
+                    SupportProtocols::Identify,
+                    SupportProtocols::Discovery,
+                ],
+            )
+            .build(
+                SimpleServiceHandler::new(Arc::clone(&announcer_shared)),
+                announcer_shared,
+            );
+        announcer
+            .connect_and_identify(node2021)
+            .expect("announcer connects");
+
+        // The node keeps only the addresses carrying peer ids
+        let mut announced_address = listening_address;
+        announced_address.push(Protocol::P2P(Cow::Owned(
+            target.peer_id().as_bytes().to_vec(),
+        )));
+        announcer
+            .send_discovery_nodes(node2021, true, vec![announced_address])
+            .expect("announce the target");
+
+        let feeler = target.wait_feeler(node2021, Duration::from_secs(30));
+        assert!(
+            feeler.is_ok(),
+            "error: {:?}, events: {:?}, node.log: {}",
+            feeler,
+            target.events(),
+            node2021.log_path().to_string_lossy()
+        );
+    }
+}
@@ -9,13 +9,17 @@ use ckb_testkit::{
     SupportProtocols,
 };
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 /// Send `DiscoveryMessage` frequently, to set up a flood attack on discovery protocol.
 ///
 /// CKB full node should resist the attack by disconnecting or banning attacking peers.
 pub struct DiscoveryFloodAttack;
 
-impl Case for DiscoveryFloodAttack {
+impl ParameterizedCase for DiscoveryFloodAttack {
+    type Params = CaseParams;
+    type Context = ();
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -23,7 +27,7 @@ impl Case for DiscoveryFloodAttack {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
@@ -31,61 +35,11 @@ impl Case for DiscoveryFloodAttack {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
-        let template_node = nodes.get_node("node2021");
-        template_node.mine(1);
-        for case in self.cases_params() {
-            let node = template_node.clone_node(&format!("{}-case-{}", self.case_name(), case.id));
-            let actual_result = self.run_case(&node, &case);
-            assert_result_eq!(
-                case.expected_result,
-                actual_result,
-                "case.id={}, node.log=\"{}\"",
-                case.id,
-                node.log_path().to_string_lossy()
-            );
-        }
-    }
-}
-
-impl DiscoveryFloodAttack {
-    fn run_case(&self, node: &Node, case: &CaseParams) -> Result<(), String> {
-        let shared = Arc::new(RwLock::new(SharedState::new()));
-        let mut connector = ConnectorBuilder::new()
-            .protocol_meta({
-                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Discovery)
-                    .build(false)
-            })
-            .protocol_meta({
-                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true)
-            })
-            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
-        connector.connect(&node)?;
-
-        let mut final_result = Ok(());
-        for _ in 0..case.times {
-            if let Err(err) =
-                connector.send(&node, SupportProtocols::Discovery, case.message.as_bytes())
-            {
-                final_result = Err(err);
-                break;
-            }
-        }
-
-        let banned = wait_until(5, || !node.rpc_client().get_banned_addresses().is_empty());
-        if banned {
-            return Err("banned".to_string());
-        }
-
-        let disconnected = wait_until(5, || node.rpc_client().get_peers().is_empty());
-        if disconnected {
-            return Err("disconnected".to_string());
-        }
-
-        final_result
+    fn prepare(&self, nodes: &Nodes) {
+        nodes.get_node("node2021").mine(1);
     }
 
-    fn cases_params(&self) -> Vec<CaseParams> {
+    fn params(&self) -> Vec<CaseParams> {
         let get_nodes_message = {
             let discovery_get_node = packed::GetNodes::new_builder()
                 .listen_port(packed::PortOpt::default())
@@ -158,9 +112,60 @@ impl DiscoveryFloodAttack {
             },
         ]
     }
+
+    fn run_one(&self, _context: &(), nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let actual_result = self.run_case(node, case);
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id={}, node.log=\"{}\"",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+impl DiscoveryFloodAttack {
+    fn run_case(&self, node: &Node, case: &CaseParams) -> Result<(), String> {
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut connector = ConnectorBuilder::new()
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Discovery)
+                    .build(false)
+            })
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true)
+            })
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        connector.connect(&node)?;
+
+        let mut final_result = Ok(());
+        for _ in 0..case.times {
+            if let Err(err) =
+                connector.send(&node, SupportProtocols::Discovery, case.message.as_bytes())
+            {
+                final_result = Err(err);
+                break;
+            }
+        }
+
+        if node.wait_banned(&connector, Duration::from_secs(5)).is_ok() {
+            return Err("banned".to_string());
+        }
+
+        let disconnected = wait_until(5, || node.rpc_client().get_peers().is_empty());
+        if disconnected {
+            return Err("disconnected".to_string());
+        }
+
+        final_result
+    }
 }
 
-struct CaseParams {
+#[derive(Debug)]
+pub struct CaseParams {
     id: usize,
     times: usize,
     message: packed::DiscoveryMessage,
@@ -9,6 +9,7 @@ use ckb_testkit::{
     SupportProtocols,
 };
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 /// Send empty or oversize DiscoveryMessage
 pub struct ManipulatedAddresses;
@@ -21,7 +22,7 @@ impl Case for ManipulatedAddresses {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
@@ -61,8 +62,7 @@ impl ManipulatedAddresses {
         connector.connect(&node)?;
         connector.send(&node, SupportProtocols::Discovery, case.message.as_bytes())?;
 
-        let banned = wait_until(5, || !node.rpc_client().get_banned_addresses().is_empty());
-        if banned {
+        if node.wait_banned(&connector, Duration::from_secs(5)).is_ok() {
             return Err("banned".to_string());
         }
 
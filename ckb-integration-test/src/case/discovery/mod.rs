@@ -1,3 +1,4 @@
+pub(super) mod feeler;
 pub(super) mod flood_attack;
 pub(super) mod manipulated_addresses;
 
@@ -0,0 +1,66 @@
+use crate::prelude::*;
+use ckb_testkit::{P2PFuzzer, SupportProtocols};
+use std::env;
+
+// Override via environment variable `CKB_FUZZ_SEED` to explore, or to reproduce a failure
+const DEFAULT_SEED: u64 = 20211018;
+const ITERATIONS: u64 = 10;
+
+/// ## Note
+///
+/// Fuzz the node under each protocol with mutated frames from fresh connectors. The node
+/// should stay alive, drop the session or ban the peer for malformed frames, and never panic.
+///
+/// The failure message contains the seed and the iteration, reproduce it via
+/// `P2PFuzzer::new(protocol, seed).replay(node, iteration)`.
+pub struct P2PFuzz;
+
+impl Case for P2PFuzz {
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn run(&self, nodes: Nodes) {
+        let node2021 = nodes.get_node("node2021");
+        let seed = env::var("CKB_FUZZ_SEED")
+            .map(|seed| seed.parse().expect("CKB_FUZZ_SEED should be u64"))
+            .unwrap_or(DEFAULT_SEED);
+        ckb_testkit::info!("fuzzing with seed {}", seed);
+        let protocols = vec![
+            SupportProtocols::Sync,
+            SupportProtocols::RelayV2,
+            SupportProtocols::Discovery,
+            SupportProtocols::Identify,
+            SupportProtocols::Ping,
+            SupportProtocols::Time,
+            SupportProtocols::Alert,
+        ];
+        for protocol in protocols {
+            let report = P2PFuzzer::new(protocol.clone(), seed)
+                .iterations(ITERATIONS)
+                .run(node2021)
+                .unwrap_or_else(|err| panic!("fuzzing failed, {}", err));
+            for case in report.cases {
+                ckb_testkit::debug!(
+                    "protocol: \"{}\", iteration: {}, mutation: {:?}, frame_len: {}, outcome: {:?}",
+                    protocol.name(),
+                    case.iteration,
+                    case.mutation,
+                    case.frame_len,
+                    case.outcome
+                );
+            }
+        }
+    }
+}
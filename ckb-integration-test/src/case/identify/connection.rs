@@ -2,7 +2,6 @@ use crate::prelude::*;
 use crate::util::{v0_100, v0_43};
 use ckb_testkit::{
     assert_result_eq,
-    ckb_types::{packed, prelude::*},
     connector::{ConnectorBuilder, SharedState, SimpleProtocolHandler, SimpleServiceHandler},
     SupportProtocols,
 };
@@ -25,7 +24,7 @@ impl Case for IdentifyConnection {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
@@ -78,17 +77,8 @@ impl IdentifyConnection {
                 session.address,
             )?;
 
-            if let Ok(shared) = connector.shared().read() {
-                if let Some(receiver) = shared
-                    .get_protocol_receiver(&session.id, &SupportProtocols::Identify.protocol_id())
-                {
-                    let data = receiver
-                        .recv_timeout(Duration::from_secs(10))
-                        .expect("receive IdentifyMessage");
-                    let _ = packed::IdentifyMessage::from_slice(data.as_ref())
-                        .map_err(|err| format!("{:?}", err))?;
-                }
-            }
+            connector.recv_identify(Duration::from_secs(10), &node)?;
+            node.assert_not_banned(&connector);
         }
         Ok(())
     }
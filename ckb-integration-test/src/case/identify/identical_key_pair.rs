@@ -12,7 +12,10 @@ use std::sync::{Arc, RwLock};
 /// In this test case, two connectors share the same key pair.
 pub struct IdentifyIdenticalKeyPair;
 
-impl Case for IdentifyIdenticalKeyPair {
+impl ParameterizedCase for IdentifyIdenticalKeyPair {
+    type Params = CaseParams;
+    type Context = ();
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -20,7 +23,7 @@ impl Case for IdentifyIdenticalKeyPair {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
@@ -28,20 +31,26 @@ impl Case for IdentifyIdenticalKeyPair {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn prepare(&self, nodes: &Nodes) {
         let template_node = nodes.get_node("node2021");
         template_node.mine(1);
-        for case in self.cases_params() {
-            let node = template_node.clone_node(&format!("{}-case-{}", self.case_name(), case.id));
-            let actual_result = self.run_case(&node, &case);
-            assert_result_eq!(
-                case.expected_result,
-                actual_result,
-                "case.id={}, node.log=\"{}\"",
-                case.id,
-                node.log_path().to_string_lossy()
-            );
-        }
+    }
+
+    fn params(&self) -> Vec<CaseParams> {
+        self.cases_params()
+    }
+
+    fn run_one(&self, _context: &(), nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let actual_result = self.run_case(node, case);
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id={}, node.log=\"{}\"",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
     }
 }
 
@@ -89,7 +98,8 @@ impl IdentifyIdenticalKeyPair {
     }
 }
 
-struct CaseParams {
+#[derive(Debug)]
+pub struct CaseParams {
     id: usize,
     expected_result: Result<(), String>,
 }
@@ -0,0 +1,66 @@
+use crate::prelude::*;
+use ckb_testkit::{
+    connector::{ConnectorBuilder, SharedState, SimpleProtocolHandler, SimpleServiceHandler},
+    util::find_available_port,
+    SupportProtocols,
+};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// The connector listens and makes the node dial it. The node should establish an outbound
+/// session, which is inbound from the connector's view, and send `IdentifyMessage` over it.
+pub struct IdentifyInboundConnection;
+
+impl Case for IdentifyInboundConnection {
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn run(&self, nodes: Nodes) {
+        let node2021 = nodes.get_node("node2021");
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let listening_address = format!("/ip4/127.0.0.1/tcp/{}", find_available_port())
+            .parse()
+            .unwrap();
+        let mut connector = ConnectorBuilder::new()
+            .listening_addresses(vec![listening_address])
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true)
+            })
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Identify)
+                    .build(false)
+            })
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+
+        connector.accept_from(node2021).unwrap_or_else(|err| {
+            panic!(
+                "node should dial the connector, error: {}, node.log: {}",
+                err,
+                node2021.log_path().to_string_lossy()
+            )
+        });
+        let session = connector.get_session(node2021).expect("inbound session");
+        assert!(session.ty.is_inbound());
+        connector
+            .recv_identify(Duration::from_secs(10), node2021)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "node should send IdentifyMessage, error: {}, node.log: {}",
+                    err,
+                    node2021.log_path().to_string_lossy()
+                )
+            });
+    }
+}
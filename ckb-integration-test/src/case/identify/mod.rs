@@ -1,2 +1,3 @@
 pub(super) mod connection;
 pub(super) mod identical_key_pair;
+pub(super) mod inbound_connection;
@@ -1,7 +1,16 @@
+mod alert;
 mod basic;
+mod capture;
 mod case_options;
+mod dao;
 mod discovery;
+mod fuzz;
 mod identify;
+mod multisig;
+mod parameterized;
+mod ping;
+mod relay;
+mod requirements;
 mod rfc0028;
 mod rfc0029;
 mod rfc0030;
@@ -10,43 +19,236 @@ mod rfc0032;
 mod rfc0034;
 mod rfc0035;
 mod rfc0036;
+mod sync;
+mod time;
+mod upgrade;
 
+use crate::report::{CaseOutcome, CaseResult};
 pub use case_options::CaseOptions;
 use ckb_testkit::{Node, Nodes};
+pub use parameterized::{row_name, Parameterized, ParameterizedCase, RowResult, RowRunner};
+pub use requirements::{BinaryRequirement, Requirements};
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 pub fn all_cases() -> Vec<Box<dyn Case>> {
     vec![
-        Box::new(basic::networking::BasicNetworking),
+        Box::new(Parameterized(basic::networking::BasicNetworking)),
+        Box::new(Parameterized(basic::bulk_mining::BasicBulkMining)),
+        Box::new(basic::deployment::BasicDeployment),
+        Box::new(Parameterized(basic::test_scripts::BasicTestScripts)),
         Box::new(rfc0028::chained::RFC0028Chained),
-        Box::new(rfc0028::rfc0028::RFC0028),
-        Box::new(rfc0029::rfc0029::RFC0029),
-        Box::new(rfc0030::rfc0030::RFC0030),
+        Box::new(Parameterized(rfc0028::rfc0028::RFC0028)),
+        Box::new(Parameterized(rfc0029::rfc0029::RFC0029)),
+        Box::new(Parameterized(rfc0030::rfc0030::RFC0030)),
         Box::new(rfc0031::rfc0031::RFC0031),
         Box::new(rfc0036::rfc0036::RFC0036),
-        Box::new(rfc0032::rfc0032::RFC0032),
-        Box::new(rfc0034::rfc0034::RFC0034),
+        Box::new(Parameterized(rfc0032::rfc0032::RFC0032)),
+        Box::new(Parameterized(rfc0034::rfc0034::RFC0034)),
         Box::new(rfc0035::v2019::RFC0035V2019),
-        Box::new(rfc0035::relay_transaction::RFC0035RelayTransaction),
+        Box::new(Parameterized(
+            rfc0035::relay_transaction::RFC0035RelayTransaction,
+        )),
         Box::new(rfc0035::connection::RFC0035V2021Connection),
         Box::new(rfc0035::discovery::RFC0035V2021Discovery),
+        Box::new(Parameterized(upgrade::fork2021::UpgradeAcrossFork2021)),
+        Box::new(Parameterized(relay::compact_block::RelayCompactBlock)),
         Box::new(identify::connection::IdentifyConnection),
-        Box::new(identify::identical_key_pair::IdentifyIdenticalKeyPair),
-        Box::new(discovery::flood_attack::DiscoveryFloodAttack),
+        Box::new(Parameterized(
+            identify::identical_key_pair::IdentifyIdenticalKeyPair,
+        )),
+        Box::new(identify::inbound_connection::IdentifyInboundConnection),
+        Box::new(discovery::feeler::DiscoveryFeeler),
+        Box::new(Parameterized(discovery::flood_attack::DiscoveryFloodAttack)),
         Box::new(discovery::manipulated_addresses::ManipulatedAddresses),
+        Box::new(Parameterized(multisig::transfer::MultisigTransfer)),
+        Box::new(Parameterized(dao::withdraw::DaoWithdraw)),
+        Box::new(sync::fake_peer::SyncFromFakePeer),
+        Box::new(Parameterized(sync::compression::SyncCompression)),
+        Box::new(Parameterized(sync::attack::SyncAttacks)),
+        Box::new(Parameterized(ping::timeout::PingTimeout)),
+        Box::new(Parameterized(time::skew::TimeSkew)),
+        Box::new(Parameterized(alert::propagation::AlertPropagation)),
+        Box::new(fuzz::p2p::P2PFuzz),
+        Box::new(capture::replay::CaptureReplay),
     ]
 }
 
-pub fn run_case(case: Box<dyn Case>) {
+/// Run the case in a dedicated thread, catching its panic. If it does not finish within
+/// `timeout`, its nodes are killed and the case is reported as timed out. The case is skipped
+/// if its ckb binaries or features are not met, or it calls `skip`, and fails if its testdata
+/// is missing or stale.
+///
+/// A table-driven case, see `ParameterizedCase`, runs the rows of `rows`, or all rows if it is
+/// `None`, up to `row_jobs` at a time, and reports every row as its own result.
+pub fn run_case(
+    case: Box<dyn Case>,
+    rows: Option<Vec<usize>>,
+    timeout: Duration,
+    row_jobs: usize,
+) -> Vec<CaseResult> {
+    let case_name = case.case_name().to_string();
+    let start_time = Instant::now();
+    let requirements = case.requires();
+    let unmet = match requirements.check() {
+        Err(reason) => Some((CaseOutcome::Skipped, reason)),
+        Ok(()) => requirements
+            .check_testdata()
+            .err()
+            .map(|reason| (CaseOutcome::Failed, reason)),
+    };
+    if let Some((outcome, reason)) = unmet {
+        return vec![CaseResult {
+            case_name,
+            outcome,
+            duration: start_time.elapsed(),
+            message: Some(reason),
+            log_paths: Vec::new(),
+        }];
+    }
+
+    let row_count = case.row_count();
+    let selected_rows = rows.unwrap_or_else(|| (0..row_count).collect());
+    let runner = Arc::new(RowRunner::new(selected_rows.clone(), row_jobs));
+    let (sender, receiver) = mpsc::channel();
+    {
+        let runner = Arc::clone(&runner);
+        thread::Builder::new()
+            .name(case_name.clone())
+            .spawn(move || {
+                let result =
+                    panic::catch_unwind(AssertUnwindSafe(|| run_case_in_place(case, &runner)));
+                let _ = sender.send(result.map_err(|payload| panic_outcome(&*payload)));
+            })
+            .unwrap_or_else(|err| panic!("failed to spawn thread for case, error: {}", err));
+    }
+
+    let (outcome, message) = match receiver.recv_timeout(timeout) {
+        Ok(Ok(())) => (CaseOutcome::Passed, None),
+        Ok(Err((outcome, message))) => (outcome, Some(message)),
+        Err(RecvTimeoutError::Timeout) => {
+            ckb_testkit::kill_nodes_of(&case_name);
+            for index in selected_rows.iter() {
+                ckb_testkit::kill_nodes_of(&row_name(&case_name, *index));
+            }
+            // Give the case thread a chance to unwind, so that its logs do not interleave
+            // with the next case
+            let _ = receiver.recv_timeout(Duration::from_secs(10));
+            (
+                CaseOutcome::TimedOut,
+                Some(format!("exceeded the timeout of {}s", timeout.as_secs())),
+            )
+        }
+        Err(RecvTimeoutError::Disconnected) => (
+            CaseOutcome::Failed,
+            Some(String::from("the case thread exited unexpectedly")),
+        ),
+    };
+    if row_count == 0 {
+        return vec![CaseResult {
+            log_paths: ckb_testkit::node_log_paths_of(&case_name),
+            case_name,
+            outcome,
+            duration: start_time.elapsed(),
+            message,
+        }];
+    }
+
+    // The rows which did not finish take the outcome of the case, e.g. when the template
+    // nodes fail to prepare or the case times out
+    let row_results = runner.results();
+    selected_rows
+        .iter()
+        .map(|index| {
+            let name = row_name(&case_name, *index);
+            let log_paths = ckb_testkit::node_log_paths_of(&name);
+            match row_results.iter().find(|row| row.index == *index) {
+                Some(row) => CaseResult {
+                    case_name: name,
+                    outcome: row.outcome,
+                    duration: row.duration,
+                    message: row.message.clone(),
+                    log_paths,
+                },
+                None => CaseResult {
+                    case_name: name,
+                    outcome: if outcome == CaseOutcome::Passed {
+                        CaseOutcome::Failed
+                    } else {
+                        outcome
+                    },
+                    duration: Duration::default(),
+                    message: Some(format!(
+                        "not finished: {}",
+                        message.as_deref().unwrap_or("unknown")
+                    )),
+                    log_paths: if log_paths.is_empty() {
+                        ckb_testkit::node_log_paths_of(&case_name)
+                    } else {
+                        log_paths
+                    },
+                },
+            }
+        })
+        .collect()
+}
+
+fn run_case_in_place(case: Box<dyn Case>, runner: &RowRunner) {
     ckb_testkit::LOG_TARGET.with(|c| {
         *c.borrow_mut() = case.case_name().to_string();
     });
 
     ckb_testkit::info!("********** START **********");
-    let nodes = case.before_run();
-    case.run(nodes);
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        case.setup();
+        let nodes = case.before_run();
+        if case.row_count() == 0 {
+            case.run(nodes);
+        } else {
+            case.run_rows(nodes, runner);
+        }
+    }));
+    case.teardown();
+    if let Err(payload) = result {
+        panic::resume_unwind(payload);
+    }
     ckb_testkit::info!("********** END **********");
 }
 
+/// The panic payload of `skip`.
+#[derive(Debug, Clone)]
+pub struct Skip(pub String);
+
+/// Skip the running case, or the running row of a table-driven case, and report `reason`. Used
+/// when a case finds out at runtime that it does not apply, e.g. the node lacks an optional
+/// RPC module. The requirements known in advance belong to `Case::requires`.
+pub fn skip<S: ToString>(reason: S) -> ! {
+    // Unlike `panic!`, it does not invoke the panic hook, which would print a backtrace
+    panic::resume_unwind(Box::new(Skip(reason.to_string())))
+}
+
+// The outcome of a case or row which panicked with `payload`
+fn panic_outcome(payload: &(dyn Any + Send)) -> (CaseOutcome, String) {
+    match payload.downcast_ref::<Skip>() {
+        Some(Skip(reason)) => (CaseOutcome::Skipped, reason.clone()),
+        None => (CaseOutcome::Failed, panic_message(payload)),
+    }
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::from("panicked with a non-string payload")
+    }
+}
+
 pub trait Case: Send {
     fn case_name(&self) -> &str {
         case_name(self)
@@ -54,17 +256,38 @@ pub trait Case: Send {
 
     fn case_options(&self) -> CaseOptions;
 
+    /// What this case requires to run, see `Requirements`. By default they are the ckb
+    /// binaries and the initial databases of its nodes. The runner skips the case if any of
+    /// them is not met.
+    fn requires(&self) -> Requirements {
+        Requirements::from_case_options(&self.case_options())
+    }
+
+    /// Called before starting the nodes.
+    fn setup(&self) {}
+
+    /// Called after the case finishes, whether it passes or panics.
+    fn teardown(&self) {}
+
+    /// Number of rows of a table-driven case, see `ParameterizedCase`. Zero for a plain case.
+    fn row_count(&self) -> usize {
+        0
+    }
+
+    /// Run the rows selected by `runner`, which collects their results. Only table-driven
+    /// cases implement it.
+    fn run_rows(&self, nodes: Nodes, runner: &RowRunner) {
+        let _ = runner;
+        self.run(nodes);
+    }
+
     fn before_run(&self) -> Nodes {
         let case_name = self.case_name();
         let case_options = self.case_options();
         let mut nodes = ::std::collections::HashMap::new();
         let mut first_node_name = None;
         for node_options in case_options.node_options.iter() {
-            let mut node = Node::init(
-                case_name,
-                node_options.clone(),
-                node_options.ckb_binary == *crate::CKB2021.read().unwrap(),
-            );
+            let mut node = Node::init(case_name, node_options.clone());
             let node_name = node.node_name().to_string();
             node.start();
             nodes.insert(node_name.clone(), node);
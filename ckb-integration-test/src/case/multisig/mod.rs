@@ -0,0 +1,4 @@
+pub(super) mod transfer;
+
+const ERROR_IMMATURE: &str = "Immature";
+const ERROR_VALIDATION_FAILURE: &str = "ValidationFailure";
@@ -0,0 +1,274 @@
+use super::{ERROR_IMMATURE, ERROR_VALIDATION_FAILURE};
+use crate::prelude::*;
+use ckb_testkit::assert_result_eq;
+use ckb_testkit::ckb_crypto::secp::Privkey;
+use ckb_testkit::ckb_hash::blake2b_256;
+use ckb_testkit::ckb_types::{
+    core::{BlockNumber, Capacity, TransactionBuilder, TransactionView},
+    packed::{CellInput, CellOutput, OutPoint},
+    prelude::*,
+};
+use ckb_testkit::util::since_from_absolute_block_number;
+use ckb_testkit::{MultisigConfig, User};
+
+const FEE: u64 = 100_000;
+const LOCKED_BLOCKS: u64 = 10;
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    since_locked: bool,
+    signers: Vec<usize>,
+    input_since: InputSince,
+    expected_result: Result<(), &'static str>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum InputSince {
+    Zero,
+    // the since value written in lock args
+    LockArgs,
+    // the since value written in lock args, and the tip has reached it
+    LockArgsAndMature,
+}
+
+/// ## Note
+///
+/// Spend cells locked by `secp256k1_blake160_multisig_all`, with
+/// `MultisigConfig { require_first_n: 1, threshold: 2, pubkeys: [A, B, C] }`.
+///
+/// * "plain" is the lock without since, `args = blake160(multisig_script)`
+/// * "locked" is the since-locked lock, `args = blake160(multisig_script) | abs_block(L)`
+///
+/// ```text
+/// ┌────┬─────────┬─────────┬──────────────────────┬──────────────────────────┐
+/// │ id │ lock    │ signers │ input.since          │ result                   │
+/// ├────┼─────────┼─────────┼──────────────────────┼──────────────────────────┤
+/// │ 0  │ plain   │ A, B    │ 0                    │ Ok                       │
+/// ├────┼─────────┼─────────┼──────────────────────┼──────────────────────────┤
+/// │ 1  │ plain   │ A, C    │ 0                    │ Ok                       │
+/// ├────┼─────────┼─────────┼──────────────────────┼──────────────────────────┤
+/// │ 2  │ plain   │ B, C    │ 0                    │ Err(ValidationFailure)   │
+/// ├────┼─────────┼─────────┼──────────────────────┼──────────────────────────┤
+/// │ 3  │ locked  │ A, B    │ 0                    │ Err(ValidationFailure)   │
+/// ├────┼─────────┼─────────┼──────────────────────┼──────────────────────────┤
+/// │ 4  │ locked  │ A, B    │ abs_block(L)         │ Err(Immature)            │
+/// ├────┼─────────┼─────────┼──────────────────────┼──────────────────────────┤
+/// │ 5  │ locked  │ A, B    │ abs_block(L), mature │ Ok                       │
+/// └────┴─────────┴─────────┴──────────────────────┴──────────────────────────┘
+/// ```
+pub struct MultisigTransfer;
+
+/// The multisig cells funded on the template node and their owner
+pub struct Context {
+    signers: Vec<User>,
+    owner: User,
+    lock_until: BlockNumber,
+    since: u64,
+    plain_out_point: OutPoint,
+    locked_out_point: OutPoint,
+}
+
+impl ParameterizedCase for MultisigTransfer {
+    type Params = CaseParams;
+    type Context = Context;
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn prepare(&self, nodes: &Nodes) -> Context {
+        let node2021 = nodes.get_node("node2021");
+        node2021.mine(20);
+
+        let genesis_block = node2021.get_block_by_number(0);
+        let signers = (0..3u8)
+            .map(|i| {
+                let privkey = Privkey::from_slice(&blake2b_256(&[i]));
+                User::new(genesis_block.clone(), Some(privkey))
+            })
+            .collect::<Vec<_>>();
+        let config = MultisigConfig::new(
+            1,
+            2,
+            signers
+                .iter()
+                .map(|signer| signer.single_secp256k1_pubkey())
+                .collect(),
+        )
+        .expect("valid multisig config");
+        let owner = User::new(genesis_block, None).with_multisig_config(config);
+
+        let lock_until = node2021.get_tip_block_number() + LOCKED_BLOCKS;
+        let since = since_from_absolute_block_number(lock_until);
+        let plain_out_point = fund(node2021, &owner, None);
+        let locked_out_point = fund(node2021, &owner, Some(since));
+
+        Context {
+            signers,
+            owner,
+            lock_until,
+            since,
+            plain_out_point,
+            locked_out_point,
+        }
+    }
+
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                since_locked: false,
+                signers: vec![0, 1],
+                input_since: InputSince::Zero,
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 1,
+                since_locked: false,
+                signers: vec![0, 2],
+                input_since: InputSince::Zero,
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 2,
+                since_locked: false,
+                signers: vec![1, 2],
+                input_since: InputSince::Zero,
+                expected_result: Err(ERROR_VALIDATION_FAILURE),
+            },
+            CaseParams {
+                id: 3,
+                since_locked: true,
+                signers: vec![0, 1],
+                input_since: InputSince::Zero,
+                expected_result: Err(ERROR_VALIDATION_FAILURE),
+            },
+            CaseParams {
+                id: 4,
+                since_locked: true,
+                signers: vec![0, 1],
+                input_since: InputSince::LockArgs,
+                expected_result: Err(ERROR_IMMATURE),
+            },
+            CaseParams {
+                id: 5,
+                since_locked: true,
+                signers: vec![0, 1],
+                input_since: InputSince::LockArgsAndMature,
+                expected_result: Ok(()),
+            },
+        ]
+    }
+
+    fn run_one(&self, context: &Context, nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let input_since = match case.input_since {
+            InputSince::Zero => 0,
+            InputSince::LockArgs => context.since,
+            InputSince::LockArgsAndMature => {
+                node.mine_to(context.lock_until);
+                context.since
+            }
+        };
+        let out_point = if case.since_locked {
+            &context.locked_out_point
+        } else {
+            &context.plain_out_point
+        };
+        let tx = {
+            let unsigned_tx = spend(node, &context.owner, out_point, input_since);
+            let case_signers = case
+                .signers
+                .iter()
+                .map(|&i| &context.signers[i])
+                .collect::<Vec<_>>();
+            let witness = context
+                .owner
+                .multisig_signed_witness(&unsigned_tx, &case_signers);
+            unsigned_tx
+                .as_advanced_builder()
+                .witness(witness.as_bytes().pack())
+                .build()
+        };
+        let actual_result = node
+            .rpc_client()
+            .send_transaction_result(tx.data().into())
+            .map(|_| ());
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id: {}, node.log: {}",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+// Transfer an always-success cell to `owner`'s multisig lock, return the out-point of the
+// multisig cell after committed.
+fn fund(node: &Node, owner: &User, since: Option<u64>) -> OutPoint {
+    let input = node
+        .get_spendable_always_success_cells()
+        .into_iter()
+        .next()
+        .expect("spendable always-success cell");
+    let lock = match since {
+        Some(since) => owner.multisig_lock_script_with_since(since),
+        None => owner.multisig_lock_script_via_type(),
+    };
+    let multisig_capacity = Capacity::bytes(1000).unwrap().as_u64();
+    let change_capacity = input.capacity().as_u64() - multisig_capacity - FEE;
+    let tx = TransactionBuilder::default()
+        .input(CellInput::new(input.out_point.clone(), 0))
+        .output(
+            CellOutput::new_builder()
+                .lock(lock)
+                .capacity(multisig_capacity.pack())
+                .build(),
+        )
+        .output(
+            CellOutput::new_builder()
+                .lock(node.always_success_script())
+                .capacity(change_capacity.pack())
+                .build(),
+        )
+        .outputs_data(vec![Default::default(); 2])
+        .cell_dep(node.always_success_cell_dep())
+        .build();
+    node.submit_transaction(&tx);
+    while !node.is_transaction_committed(&tx) {
+        node.mine(1);
+    }
+    OutPoint::new(tx.hash(), 0)
+}
+
+fn spend(node: &Node, owner: &User, out_point: &OutPoint, since: u64) -> TransactionView {
+    let input = node
+        .get_cell_meta(out_point.clone())
+        .expect("multisig cell is live");
+    TransactionBuilder::default()
+        .input(CellInput::new(out_point.clone(), since))
+        .output(
+            CellOutput::new_builder()
+                .lock(node.always_success_script())
+                .capacity((input.capacity().as_u64() - FEE).pack())
+                .build(),
+        )
+        .output_data(Default::default())
+        .cell_dep(owner.multisig_cell_dep())
+        .cell_dep(node.always_success_cell_dep())
+        .build()
+}
@@ -0,0 +1,213 @@
+use super::{case_name, panic_outcome, Case, CaseOptions, Requirements};
+use crate::report::CaseOutcome;
+use ckb_testkit::Nodes;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A table-driven case. The runner prepares the template nodes once, then runs every row of
+/// `params` against fresh clones of them, catching its panic. Every row is reported as its own
+/// sub-test named `<case_name>[<row index>]`, which `--cases` accepts to run the selected rows
+/// only.
+///
+/// Register it in `all_cases` wrapped in `Parameterized`. A row may skip itself via `skip`.
+///
+/// ```ignore
+/// impl ParameterizedCase for RFC0029 {
+///     type Params = CaseParams;
+///     type Context = Deployer;
+///
+///     fn prepare(&self, nodes: &Nodes) -> Deployer { .. }
+///     fn params(&self) -> Vec<CaseParams> { .. }
+///     fn run_one(&self, deployer: &Deployer, nodes: &Nodes, params: &CaseParams) -> Result<(), String> { .. }
+/// }
+/// ```
+pub trait ParameterizedCase: Send + Sync {
+    type Params: fmt::Debug + Send + Sync;
+    /// What `prepare` builds on the template nodes and the rows share
+    type Context: Send + Sync;
+
+    fn case_name(&self) -> &str {
+        case_name(self)
+    }
+
+    fn case_options(&self) -> CaseOptions;
+
+    fn requires(&self) -> Requirements {
+        Requirements::from_case_options(&self.case_options())
+    }
+
+    /// Called before starting the template nodes, see `Case::setup`.
+    fn setup(&self) {}
+
+    /// Called after all rows finish, whether they pass or not, see `Case::teardown`.
+    fn teardown(&self) {}
+
+    /// Prepare the template nodes started according to `case_options`.
+    fn prepare(&self, nodes: &Nodes) -> Self::Context;
+
+    fn params(&self) -> Vec<Self::Params>;
+
+    /// Run a row. `nodes` are clones of the template nodes, bearing the same names. The clones
+    /// are not connected to each other.
+    fn run_one(
+        &self,
+        context: &Self::Context,
+        nodes: &Nodes,
+        params: &Self::Params,
+    ) -> Result<(), String>;
+}
+
+/// Adapt a `ParameterizedCase` to `Case`.
+pub struct Parameterized<T>(pub T);
+
+impl<T: ParameterizedCase> Case for Parameterized<T> {
+    fn case_name(&self) -> &str {
+        self.0.case_name()
+    }
+
+    fn case_options(&self) -> CaseOptions {
+        self.0.case_options()
+    }
+
+    fn requires(&self) -> Requirements {
+        self.0.requires()
+    }
+
+    fn setup(&self) {
+        self.0.setup()
+    }
+
+    fn teardown(&self) {
+        self.0.teardown()
+    }
+
+    fn row_count(&self) -> usize {
+        self.0.params().len()
+    }
+
+    fn run(&self, nodes: Nodes) {
+        let runner = RowRunner::new((0..self.row_count()).collect(), 1);
+        self.run_rows(nodes, &runner);
+        let failures = runner
+            .results()
+            .into_iter()
+            .filter(|row| row.outcome == CaseOutcome::Failed)
+            .map(|row| {
+                format!(
+                    "{}: {}",
+                    row_name(self.case_name(), row.index),
+                    row.message.unwrap_or_default()
+                )
+            })
+            .collect::<Vec<_>>();
+        assert!(failures.is_empty(), "{}", failures.join("\n"));
+    }
+
+    fn run_rows(&self, nodes: Nodes, runner: &RowRunner) {
+        let context = self.0.prepare(&nodes);
+        let params = self.0.params();
+        runner.run(self.case_name(), |index| {
+            let row_nodes: Nodes = nodes
+                .nodes()
+                .map(|node| node.clone_node(node.node_name()))
+                .collect::<Vec<_>>()
+                .into();
+            let params = &params[index];
+            self.0
+                .run_one(&context, &row_nodes, params)
+                .map_err(|err| format!("{}, params: {:?}", err, params))
+        });
+    }
+}
+
+/// The name of a row reported by the runner, like "RFC0029[15]".
+pub fn row_name(case_name: &str, index: usize) -> String {
+    format!("{}[{}]", case_name, index)
+}
+
+#[derive(Debug, Clone)]
+pub struct RowResult {
+    pub index: usize,
+    /// `Passed`, `Failed`, or `Skipped` via `skip`
+    pub outcome: CaseOutcome,
+    /// The error or the reason of skipping
+    pub message: Option<String>,
+    pub duration: Duration,
+}
+
+/// Run the selected rows of a case, up to `jobs` rows at a time, collecting their results.
+pub struct RowRunner {
+    selected: Vec<usize>,
+    jobs: usize,
+    results: Mutex<Vec<RowResult>>,
+}
+
+impl RowRunner {
+    pub fn new(selected: Vec<usize>, jobs: usize) -> Self {
+        Self {
+            selected,
+            jobs,
+            results: Default::default(),
+        }
+    }
+
+    pub fn selected(&self) -> &[usize] {
+        &self.selected
+    }
+
+    /// Results of the finished rows, in order of completion.
+    pub fn results(&self) -> Vec<RowResult> {
+        self.results.lock().unwrap().clone()
+    }
+
+    /// Call `run_row` with the index of every selected row. Each row runs under its own
+    /// `LOG_TARGET`, see `row_name`, so that the nodes it starts are attributed to it.
+    pub fn run<F>(&self, case_name: &str, run_row: F)
+    where
+        F: Fn(usize) -> Result<(), String> + Sync,
+    {
+        let next = AtomicUsize::new(0);
+        let worker = || {
+            while let Some(&index) = self.selected.get(next.fetch_add(1, Ordering::SeqCst)) {
+                ckb_testkit::LOG_TARGET.with(|c| {
+                    *c.borrow_mut() = row_name(case_name, index);
+                });
+                ckb_testkit::info!("********** START **********");
+                let start_time = Instant::now();
+                let (outcome, message) =
+                    match panic::catch_unwind(AssertUnwindSafe(|| run_row(index))) {
+                        Ok(Ok(())) => (CaseOutcome::Passed, None),
+                        Ok(Err(err)) => (CaseOutcome::Failed, Some(err)),
+                        Err(payload) => {
+                            let (outcome, message) = panic_outcome(&*payload);
+                            (outcome, Some(message))
+                        }
+                    };
+                ckb_testkit::info!("********** END, {:?} {:?} **********", outcome, message);
+                self.results.lock().unwrap().push(RowResult {
+                    index,
+                    outcome,
+                    message,
+                    duration: start_time.elapsed(),
+                });
+            }
+        };
+
+        if self.jobs <= 1 {
+            worker();
+            ckb_testkit::LOG_TARGET.with(|c| {
+                *c.borrow_mut() = case_name.to_string();
+            });
+        } else {
+            crossbeam::thread::scope(|scope| {
+                for _ in 0..self.jobs.min(self.selected.len()) {
+                    scope.spawn(|_| worker());
+                }
+            })
+            .expect("rows catch their panics");
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_types::{packed::PingPayloadUnion, prelude::*};
+use ckb_testkit::connector::{ConnectorBuilder, SharedState, SimpleServiceHandler};
+use ckb_testkit::SupportProtocols;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+
+// The node is configured with `ping_interval_secs = 1` and `ping_timeout_secs = 3`
+const PING_TIMEOUT_SECS: u64 = 3;
+const OBSERVING_SECS: u64 = 10;
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    reply_pong: bool,
+    expected_connected: bool,
+}
+
+/// ## Note
+///
+/// A connector opens the Ping protocol with the node, then replies the node's pings or not.
+/// The node should evict the connector when it does not reply within `ping_timeout_secs`.
+///
+/// ```text
+/// ┌────┬────────────┬─────────────────────────────────────┐
+/// │ id │ reply pong │ connected after 10 seconds          │
+/// ├────┼────────────┼─────────────────────────────────────┤
+/// │ 0  │ yes        │ yes                                 │
+/// ├────┼────────────┼─────────────────────────────────────┤
+/// │ 1  │ no         │ no, evicted for ping timeout        │
+/// └────┴────────────┴─────────────────────────────────────┘
+/// ```
+pub struct PingTimeout;
+
+impl ParameterizedCase for PingTimeout {
+    type Params = CaseParams;
+    type Context = ();
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/short_ping_timeout",
+            }],
+        }
+    }
+
+    fn prepare(&self, _nodes: &Nodes) {}
+
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                reply_pong: true,
+                expected_connected: true,
+            },
+            CaseParams {
+                id: 1,
+                reply_pong: false,
+                expected_connected: false,
+            },
+        ]
+    }
+
+    fn run_one(&self, _context: &(), nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node2021 = nodes.get_node("node2021");
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut connector = ConnectorBuilder::new()
+            .simple_protocols(
+                &shared,
+                &[
+                    SupportProtocols::Sync,
+                    SupportProtocols::Identify,
+                    SupportProtocols::Ping,
+                ],
+            )
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        connector.connect_and_identify(node2021)?;
+
+        let start_time = Instant::now();
+        while start_time.elapsed() <= Duration::from_secs(OBSERVING_SECS) {
+            match connector.recv_ping(Duration::from_millis(500), node2021) {
+                Ok(PingPayloadUnion::Ping(ping)) if case.reply_pong => {
+                    let nonce: u32 = ping.nonce().unpack();
+                    let _ = connector.send_pong(node2021, nonce);
+                }
+                Ok(_) => {}
+                // Timeout, or the session was closed
+                Err(_) => {
+                    if connector.get_session(node2021).is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+
+        if case.expected_connected {
+            assert!(
+                connector.get_session(node2021).is_some(),
+                "case.id: {}, events: {:?}, node.log: {}",
+                case.id,
+                connector.events(),
+                node2021.log_path().to_string_lossy()
+            );
+        } else {
+            let closed = connector
+                .wait_session_closed(node2021, Duration::from_secs(OBSERVING_SECS))
+                .unwrap_or_else(|err| {
+                    panic!(
+                        "case.id: {}, error: {}, node.log: {}",
+                        case.id,
+                        err,
+                        node2021.log_path().to_string_lossy()
+                    )
+                });
+            // Evicted only after the ping timed out
+            assert!(
+                closed.closed_at.duration_since(closed.opened_at)
+                    >= Duration::from_secs(PING_TIMEOUT_SECS),
+                "case.id: {}, closed: {:?}, node.log: {}",
+                case.id,
+                closed,
+                node2021.log_path().to_string_lossy()
+            );
+        }
+        Ok(())
+    }
+}
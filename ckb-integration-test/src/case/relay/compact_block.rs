@@ -0,0 +1,234 @@
+use crate::case::rfc0035::{HARDFORK_DELAY_WINDOW, RFC0035_BLOCK_NUMBER};
+use crate::prelude::*;
+use ckb_testkit::ckb_types::core::{BlockView, TransactionView};
+use ckb_testkit::util::wait_until;
+use ckb_testkit::{assert_result_eq, FakeRelayPeerBuilder, SupportProtocols};
+use std::collections::HashSet;
+
+/// Relay the compact blocks of a short chain, of which the last block commits a transaction,
+/// from a `FakeRelayPeer` to the node. The node should reconstruct every block, fetching the
+/// missing transaction via `GetBlockTransactions`, or falling back to `GetBlocks` under the
+/// Sync protocol if the peer does not answer.
+///
+/// The chain extends a node of height below `2999` under Relay, and above `3011` under RelayV2,
+/// as RFC0035 switches the relay protocol.
+///
+/// ## Cases
+///
+/// ```text
+/// ┌───┬──────────┬───────────┬────────────┬───────────────────────┬──────────────────────┐
+/// │id │ network  │ prefilled │ tx in pool │ peer                  │ result               │
+/// │   │ protocol │           │            │                       │                      │
+/// ├───┼──────────┼───────────┼────────────┼───────────────────────┼──────────────────────┤
+/// │0  │ relay    │ all       │ false      │ -                     │ Ok(Reconstructed)    │
+/// │1  │ relay    │ cellbase  │ true       │ -                     │ Ok(Reconstructed)    │
+/// │2  │ relay    │ cellbase  │ false      │ -                     │ Ok(FetchedMissing)   │
+/// │3  │ relay    │ cellbase  │ false      │ withhold transactions │ Ok(FellBackToSync)   │
+/// │4  │ relay_v2 │ all       │ false      │ -                     │ Ok(Reconstructed)    │
+/// │5  │ relay_v2 │ cellbase  │ true       │ -                     │ Ok(Reconstructed)    │
+/// │6  │ relay_v2 │ cellbase  │ false      │ -                     │ Ok(FetchedMissing)   │
+/// │7  │ relay_v2 │ cellbase  │ false      │ withhold transactions │ Ok(FellBackToSync)   │
+/// └───┴──────────┴───────────┴────────────┴───────────────────────┴──────────────────────┘
+/// ```
+pub struct RelayCompactBlock;
+
+/// The template node above the RFC0035 switch and the chains relayed by the rows
+pub struct Context {
+    node2021_v2: Node,
+    relay_chain: (TransactionView, Vec<BlockView>),
+    relay_v2_chain: (TransactionView, Vec<BlockView>),
+}
+
+impl ParameterizedCase for RelayCompactBlock {
+    type Params = CaseParams;
+    type Context = Context;
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/Epoch2V2TestData",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    // RelayV2 is introduced in v0.100.0
+    fn requires(&self) -> Requirements {
+        Requirements::from_case_options(&self.case_options()).ckb_binary(
+            CKB2021,
+            Some("0.100.0"),
+            None,
+        )
+    }
+
+    fn prepare(&self, nodes: &Nodes) -> Context {
+        let node2021 = nodes.get_node("node2021");
+        // Mine a recent block so that the nodes are out of IBD
+        node2021.mine(1);
+        assert!(node2021.get_tip_block_number() < RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW);
+        let node2021_v2 = node2021.clone_node("node2021-relay-v2");
+        node2021_v2.mine_to(RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1);
+
+        let relay_chain = self.build_chain(node2021, "source-relay");
+        let relay_v2_chain = self.build_chain(&node2021_v2, "source-relay-v2");
+        Context {
+            node2021_v2,
+            relay_chain,
+            relay_v2_chain,
+        }
+    }
+
+    fn params(&self) -> Vec<CaseParams> {
+        let mut cases = Vec::new();
+        for protocol in [SupportProtocols::Relay, SupportProtocols::RelayV2].iter() {
+            let rows = vec![
+                (true, false, false, Reconstruction::Reconstructed),
+                (false, true, false, Reconstruction::Reconstructed),
+                (false, false, false, Reconstruction::FetchedMissing),
+                (false, false, true, Reconstruction::FellBackToSync),
+            ];
+            for (prefill_all, tx_in_pool, withhold_block_transactions, expected) in rows {
+                cases.push(CaseParams {
+                    id: cases.len(),
+                    protocol: protocol.clone(),
+                    prefill_all,
+                    tx_in_pool,
+                    withhold_block_transactions,
+                    expected_result: Ok(expected),
+                });
+            }
+        }
+        cases
+    }
+
+    fn run_one(&self, context: &Context, nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let is_relay = case.protocol.protocol_id() == SupportProtocols::Relay.protocol_id();
+        let node2021_v2;
+        let (node, (transaction, blocks)) = if is_relay {
+            (nodes.get_node("node2021"), &context.relay_chain)
+        } else {
+            let node_name = format!("{}-case-{}", self.case_name(), case.id);
+            node2021_v2 = context.node2021_v2.clone_node(&node_name);
+            (&node2021_v2, &context.relay_v2_chain)
+        };
+        let actual_result = self.run_case(node, case, transaction, blocks);
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id={}, node.log=\"{}\"",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reconstruction {
+    /// Reconstructed from the prefilled transactions and the transaction pool
+    Reconstructed,
+    /// Reconstructed after fetching the missing transactions via `GetBlockTransactions`
+    FetchedMissing,
+    /// Downloaded via `GetBlocks` under the Sync protocol
+    FellBackToSync,
+}
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    protocol: SupportProtocols,
+    prefill_all: bool,
+    tx_in_pool: bool,
+    withhold_block_transactions: bool,
+    expected_result: Result<Reconstruction, String>,
+}
+
+impl RelayCompactBlock {
+    fn run_case(
+        &self,
+        node: &Node,
+        case: &CaseParams,
+        transaction: &TransactionView,
+        blocks: &[BlockView],
+    ) -> Result<Reconstruction, String> {
+        if case.tx_in_pool {
+            node.submit_transaction(transaction);
+        }
+        let mut builder = FakeRelayPeerBuilder::new(case.protocol.clone())
+            .transactions(vec![transaction.clone()]);
+        if case.withhold_block_transactions {
+            builder = builder.withhold_block_transactions();
+        }
+        let mut peer = builder.build();
+        peer.connect(node)?;
+
+        for block in blocks {
+            let prefilled = if case.prefill_all {
+                (0..block.transactions().len()).collect()
+            } else {
+                HashSet::new()
+            };
+            peer.relay_compact_block(node, block, &prefilled)?;
+            let reached = wait_until(30, || node.get_tip_block_number() >= block.number());
+            if !reached {
+                return Err(format!(
+                    "stuck at {}, fake peer stats: {:?}",
+                    node.get_tip_block_number(),
+                    peer.stats()
+                ));
+            }
+        }
+
+        let stats = peer.stats();
+        // The node may request the block under Sync as well while fetching the missing
+        // transactions, so `GetBlockTransactions` being served takes precedence
+        if !stats.served_block_transactions.is_empty() {
+            Ok(Reconstruction::FetchedMissing)
+        } else if !stats.served_sync_blocks.is_empty() {
+            Ok(Reconstruction::FellBackToSync)
+        } else {
+            Ok(Reconstruction::Reconstructed)
+        }
+    }
+
+    // Build the blocks extending `base_node`, of which the last one commits a transaction
+    fn build_chain(
+        &self,
+        base_node: &Node,
+        source_name: &str,
+    ) -> (TransactionView, Vec<BlockView>) {
+        let source = base_node.clone_node(source_name);
+        let input = source
+            .get_spendable_always_success_cells()
+            .into_iter()
+            .next()
+            .expect("spendable always-success cell");
+        let transaction = source.always_success_transaction(&input);
+        source.submit_transaction(&transaction);
+        // The transaction is committed after the proposal window
+        let mut committed = false;
+        for _ in 0..20 {
+            source.mine(1);
+            committed = source
+                .get_tip_block()
+                .transactions()
+                .iter()
+                .any(|committed| committed.hash() == transaction.hash());
+            if committed {
+                break;
+            }
+        }
+        assert!(committed, "transaction should be committed in source node");
+
+        let blocks = (base_node.get_tip_block_number() + 1..=source.get_tip_block_number())
+            .map(|number| source.get_block_by_number(number))
+            .collect();
+        (transaction, blocks)
+    }
+}
@@ -0,0 +1 @@
+pub(super) mod compact_block;
@@ -0,0 +1,183 @@
+use super::CaseOptions;
+use std::cmp::Ordering;
+
+/// What a case requires to run, see `Case::requires`. The runner skips the case and reports the
+/// reason if a ckb binary or a feature is not met, which are deliberate gates, and fails the
+/// case if the testdata is missing or stale, which is a broken environment.
+///
+/// ```ignore
+/// Requirements::from_case_options(&self.case_options())
+///     .ckb_binary(CKB2019, None, Some("0.44.0"))
+///     .ckb_binary(CKB2021, Some("0.100.0"), None)
+///     .testdata("Epoch2V2TestData")
+///     .feature("with_subscribe")
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Requirements {
+    pub ckb_binaries: Vec<BinaryRequirement>,
+    /// Names of the testdata located in `testdata/db/`
+    pub testdata: Vec<String>,
+    /// Cargo features of ckb-testkit, see `ckb_testkit::enabled_features`
+    pub features: Vec<&'static str>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BinaryRequirement {
+    /// Name of the ckb binary, see `ckb_testkit::register_ckb_binary`
+    pub ckb_binary: String,
+    /// Inclusive
+    pub min_version: Option<&'static str>,
+    /// Exclusive
+    pub max_version: Option<&'static str>,
+}
+
+impl Requirements {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Require the ckb binary named `ckb_binary` to be registered, and its version to be within
+    /// `[min_version, max_version)`. Requiring a binary more than once merges the bounds.
+    pub fn ckb_binary<S: ToString>(
+        mut self,
+        ckb_binary: S,
+        min_version: Option<&'static str>,
+        max_version: Option<&'static str>,
+    ) -> Self {
+        let ckb_binary = ckb_binary.to_string();
+        if let Some(existing) = self
+            .ckb_binaries
+            .iter_mut()
+            .find(|existing| existing.ckb_binary == ckb_binary)
+        {
+            existing.min_version = min_version.or(existing.min_version);
+            existing.max_version = max_version.or(existing.max_version);
+        } else {
+            self.ckb_binaries.push(BinaryRequirement {
+                ckb_binary,
+                min_version,
+                max_version,
+            });
+        }
+        self
+    }
+
+    /// The ckb binaries of the nodes, without version bounds, and their initial databases
+    /// located in `testdata/db/`, except the empty one.
+    pub fn from_case_options(case_options: &CaseOptions) -> Self {
+        let mut requirements = Self::new();
+        for node_options in case_options.node_options.iter() {
+            requirements = requirements.ckb_binary(&node_options.ckb_binary, None, None);
+            if let Some(name) = node_options
+                .initial_database
+                .strip_prefix("testdata/db/")
+                .filter(|name| *name != "empty")
+            {
+                requirements = requirements.testdata(name);
+            }
+        }
+        requirements
+    }
+
+    pub fn testdata<S: ToString>(mut self, testdata: S) -> Self {
+        let testdata = testdata.to_string();
+        if !self.testdata.contains(&testdata) {
+            self.testdata.push(testdata);
+        }
+        self
+    }
+
+    pub fn feature(mut self, feature: &'static str) -> Self {
+        if !self.features.contains(&feature) {
+            self.features.push(feature);
+        }
+        self
+    }
+
+    /// Return the reason of the first unmet ckb binary or feature.
+    pub fn check(&self) -> Result<(), String> {
+        for feature in self.features.iter() {
+            if !ckb_testkit::enabled_features().contains(feature) {
+                return Err(format!(
+                    "requires ckb-testkit feature \"{}\", which is not enabled",
+                    feature
+                ));
+            }
+        }
+        for requirement in self.ckb_binaries.iter() {
+            requirement.check()?;
+        }
+        Ok(())
+    }
+
+    /// Return the reason if any of the testdata is missing or stale.
+    pub fn check_testdata(&self) -> Result<(), String> {
+        crate::testdata::check_testdata(&self.testdata)
+    }
+}
+
+impl BinaryRequirement {
+    fn check(&self) -> Result<(), String> {
+        let binary = ckb_testkit::ckb_binary(&self.ckb_binary).ok_or_else(|| {
+            format!(
+                "requires ckb binary \"{}\", which is not registered",
+                self.ckb_binary
+            )
+        })?;
+        if self.min_version.is_none() && self.max_version.is_none() {
+            return Ok(());
+        }
+        let actual = parse_version(&binary.version).ok_or_else(|| {
+            format!(
+                "failed to parse the version of \"{}\": \"{}\"",
+                self.ckb_binary, binary.version
+            )
+        })?;
+        if let Some(min_version) = self.min_version {
+            if compare_versions(&actual, &parse_required_version(min_version)) == Ordering::Less {
+                return Err(format!(
+                    "requires \"{}\" >= {}, got \"{}\"",
+                    self.ckb_binary, min_version, binary.version
+                ));
+            }
+        }
+        if let Some(max_version) = self.max_version {
+            if compare_versions(&actual, &parse_required_version(max_version)) != Ordering::Less {
+                return Err(format!(
+                    "requires \"{}\" < {}, got \"{}\"",
+                    self.ckb_binary, max_version, binary.version
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+// Parse "0.100.0" out of `ckb --version`, like "ckb 0.100.0 (7ac2d4c 2021-10-25)". The
+// pre-release suffix, like "-rc1", is ignored.
+fn parse_version(version: &str) -> Option<Vec<u64>> {
+    version
+        .split_whitespace()
+        .find(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+        .and_then(|token| token.split('-').next())
+        .and_then(|token| {
+            token
+                .split('.')
+                .map(|part| part.parse::<u64>().ok())
+                .collect()
+        })
+}
+
+fn parse_required_version(version: &str) -> Vec<u64> {
+    parse_version(version).unwrap_or_else(|| panic!("invalid required version \"{}\"", version))
+}
+
+fn compare_versions(a: &[u64], b: &[u64]) -> Ordering {
+    let len = a.len().max(b.len());
+    let pad = |v: &[u64]| {
+        let mut v = v.to_vec();
+        v.resize(len, 0);
+        v
+    };
+    pad(a).cmp(&pad(b))
+}
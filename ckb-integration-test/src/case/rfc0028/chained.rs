@@ -23,7 +23,7 @@ impl Case for RFC0028Chained {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/Epoch2V2TestData",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
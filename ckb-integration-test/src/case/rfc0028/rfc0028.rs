@@ -2,7 +2,7 @@ use super::{ERROR_IMMATURE, RFC0028_EPOCH_NUMBER};
 use crate::prelude::*;
 use crate::util::estimate_start_number_of_epoch;
 use ckb_testkit::ckb_types::{
-    core::TransactionView,
+    core::{BlockNumber, TransactionView},
     packed::{CellInput, OutPoint},
 };
 use ckb_testkit::util::since_from_relative_timestamp;
@@ -58,7 +58,27 @@ use ckb_testkit::{assert_result_eq, BuildInstruction};
 /// ```
 pub struct RFC0028;
 
-impl Case for RFC0028 {
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    input_tx_committed_number: BlockNumber,
+    /// Commit the transaction at the fork switch height, otherwise at the one before it
+    after_switch: bool,
+    expected_result: Result<(), &'static str>,
+}
+
+/// The chain and the input transaction shared by the rows
+pub struct Context {
+    fork_switch_height: BlockNumber,
+    construct_chain_instructions: Vec<BuildInstruction>,
+    input_tx: TransactionView,
+    since: u64,
+}
+
+impl ParameterizedCase for RFC0028 {
+    type Params = CaseParams;
+    type Context = Context;
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -66,7 +86,7 @@ impl Case for RFC0028 {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
@@ -74,7 +94,7 @@ impl Case for RFC0028 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn prepare(&self, nodes: &Nodes) -> Context {
         let node2021 = nodes.get_node("node2021");
         let fork_switch_height = estimate_start_number_of_epoch(node2021, RFC0028_EPOCH_NUMBER);
 
@@ -96,105 +116,78 @@ impl Case for RFC0028 {
             let input = node2021.get_spendable_always_success_cells()[0].to_owned();
             node2021.always_success_transaction(&input)
         };
-
-        // Run cases 0, 1, 2.
-        //
-        // The current tip number is 2999, that has not activate fork.
-        // Please read struct documentation for detail.
-        //
-        // [(case_id, input_tx_committed_number, tip_number)]
-        let cases_before_switch = vec![
-            (0, 1998, Ok(())),
-            (1, 1999, Ok(())),
-            (2, 2000, Err(ERROR_IMMATURE)),
-        ];
-        for (case_id, input_tx_committed_number, expected_result_before_switch) in
-            cases_before_switch
-        {
-            let node = node2021.clone_node(&format!("case-{}-before-switch", case_id));
-
-            let mut ins = construct_chain_instructions.clone();
-            ins.extend(vec![
-                BuildInstruction::Propose {
-                    template_number: input_tx_committed_number - 2,
-                    proposal_short_id: input_tx.proposal_short_id(),
-                },
-                BuildInstruction::Commit {
-                    template_number: input_tx_committed_number,
-                    transaction: input_tx.clone(),
-                },
-            ]);
-
-            let tx = build_transaction(&node, &input_tx, since);
-            ins.extend(vec![
-                BuildInstruction::Propose {
-                    template_number: fork_switch_height - 1 - 2,
-                    proposal_short_id: tx.proposal_short_id(),
-                },
-                BuildInstruction::Commit {
-                    template_number: fork_switch_height - 1,
-                    transaction: tx,
-                },
-            ]);
-            let actual_result_before_switch =
-                node.build_according_to_instructions(fork_switch_height, ins);
-
-            assert_result_eq!(
-                expected_result_before_switch,
-                actual_result_before_switch,
-                "case-{}",
-                case_id,
-            );
+        Context {
+            fork_switch_height,
+            construct_chain_instructions,
+            input_tx,
+            since,
         }
+    }
 
-        // Run cases 4, 5, 6.
-        //
-        // The current tip number is 2999, that has not activate fork.
-        // Please read struct documentation for detail.
-        //
-        // [(case_id, input_tx_committed_number, tip_number)]
-        let cases_after_switch = vec![
-            (3, 1980, Ok(())),
-            (4, 1981, Ok(())),
-            (5, 1982, Err(ERROR_IMMATURE)),
-        ];
-        for (case_id, input_tx_committed_number, expected_result_after_switch) in cases_after_switch
-        {
-            let node = node2021.clone_node(&format!("case-{}-after-switch", case_id));
+    // The tip number is 2999 before the transaction is committed, that has not activated
+    // fork. Please read struct documentation for detail.
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            (0, 1998, false, Ok(())),
+            (1, 1999, false, Ok(())),
+            (2, 2000, false, Err(ERROR_IMMATURE)),
+            (3, 1980, true, Ok(())),
+            (4, 1981, true, Ok(())),
+            (5, 1982, true, Err(ERROR_IMMATURE)),
+        ]
+        .into_iter()
+        .map(
+            |(id, input_tx_committed_number, after_switch, expected_result)| CaseParams {
+                id,
+                input_tx_committed_number,
+                after_switch,
+                expected_result,
+            },
+        )
+        .collect()
+    }
 
-            let mut ins = construct_chain_instructions.clone();
-            ins.extend(vec![
-                BuildInstruction::Propose {
-                    template_number: input_tx_committed_number - 2,
-                    proposal_short_id: input_tx.proposal_short_id(),
-                },
-                BuildInstruction::Commit {
-                    template_number: input_tx_committed_number,
-                    transaction: input_tx.clone(),
-                },
-            ]);
+    fn run_one(&self, context: &Context, nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let input_tx = &context.input_tx;
+        let mut ins = context.construct_chain_instructions.clone();
+        ins.extend(vec![
+            BuildInstruction::Propose {
+                template_number: case.input_tx_committed_number - 2,
+                proposal_short_id: input_tx.proposal_short_id(),
+            },
+            BuildInstruction::Commit {
+                template_number: case.input_tx_committed_number,
+                transaction: input_tx.clone(),
+            },
+        ]);
 
-            let tx = build_transaction(&node, &input_tx, since);
-            ins.extend(vec![
-                BuildInstruction::Propose {
-                    template_number: fork_switch_height - 2,
-                    proposal_short_id: tx.proposal_short_id(),
-                },
-                BuildInstruction::Commit {
-                    template_number: fork_switch_height,
-                    transaction: tx,
-                },
-            ]);
-            let actual_result_after_switch =
-                node.build_according_to_instructions(fork_switch_height, ins);
+        let tx = build_transaction(node, input_tx, context.since);
+        let committed_number = if case.after_switch {
+            context.fork_switch_height
+        } else {
+            context.fork_switch_height - 1
+        };
+        ins.extend(vec![
+            BuildInstruction::Propose {
+                template_number: committed_number - 2,
+                proposal_short_id: tx.proposal_short_id(),
+            },
+            BuildInstruction::Commit {
+                template_number: committed_number,
+                transaction: tx,
+            },
+        ]);
+        let actual_result = node.build_according_to_instructions(context.fork_switch_height, ins);
 
-            assert_result_eq!(
-                expected_result_after_switch,
-                actual_result_after_switch,
-                "case-{}",
-                case_id,
-            );
-        }
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id: {}, node.log: {}",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
     }
 }
 
@@ -2,19 +2,18 @@ use super::{
     ERROR_DUPLICATE_CELL_DEPS, ERROR_MULTIPLE_MATCHES, RFC0029_BLOCK_NUMBER, RFC0029_EPOCH_NUMBER,
 };
 use crate::prelude::*;
-use crate::util::deployer::Deployer;
 use ckb_testkit::ckb_types::{
     core::{
         cell::CellMeta, BlockNumber, Capacity, DepType, ScriptHashType, TransactionBuilder,
         TransactionView,
     },
-    packed::{Byte32, CellDep, CellInput, CellOutput, OutPointVec, Script},
+    packed::{Byte32, CellDep, CellInput, CellOutput, Script},
     prelude::*,
 };
-use ckb_testkit::{assert_result_eq, BuildInstruction};
+use ckb_testkit::{assert_result_eq, BuildInstruction, Deployer};
 
 #[derive(Debug)]
-struct CaseParams {
+pub struct CaseParams {
     id: usize,
     height: BlockNumber,
     script_hash_type: ScriptHashType,
@@ -98,7 +97,18 @@ struct CaseParams {
 /// ```
 pub struct RFC0029;
 
-impl Case for RFC0029 {
+/// The deployed cells shared by the rows
+pub struct Context {
+    deployer: Deployer,
+    code_hash_via_data_hash: Byte32,
+    code_hash_via_type_hash: Byte32,
+    input: CellMeta,
+}
+
+impl ParameterizedCase for RFC0029 {
+    type Params = CaseParams;
+    type Context = Context;
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -106,7 +116,7 @@ impl Case for RFC0029 {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/Epoch2V2TestData",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
@@ -114,7 +124,7 @@ impl Case for RFC0029 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn prepare(&self, nodes: &Nodes) -> Context {
         let node2021 = nodes.get_node("node2021");
 
         // We use this as type script of our deployed cells,
@@ -162,92 +172,17 @@ impl Case for RFC0029 {
             deployer.deploy(node2021, "b1", output, output_data)
         }
         // deploy Group("a1"), naming "group_a1"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![deployer.get_out_point("a1")])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a1", output, output_data)
-        }
+        deployer.deploy_dep_group(node2021, "group_a1", &["a1"]);
         // deploy Group("a2"), naming "group_a2"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![deployer.get_out_point("a2")])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a2", output, output_data)
-        }
+        deployer.deploy_dep_group(node2021, "group_a2", &["a2"]);
         // deploy Group("b1"), naming "group_b1"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![deployer.get_out_point("b1")])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_b1", output, output_data)
-        }
+        deployer.deploy_dep_group(node2021, "group_b1", &["b1"]);
         // deploy Group("a1", "a1"), naming "group_a1_a1"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![
-                    deployer.get_out_point("a1"),
-                    deployer.get_out_point("a1"),
-                ])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a1_a1", output, output_data)
-        }
+        deployer.deploy_dep_group(node2021, "group_a1_a1", &["a1", "a1"]);
         // deploy Group("a1", "a2"), naming "group_a1_a2"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![
-                    deployer.get_out_point("a1"),
-                    deployer.get_out_point("a2"),
-                ])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a1_a2", output, output_data)
-        }
+        deployer.deploy_dep_group(node2021, "group_a1_a2", &["a1", "a2"]);
         // deploy Group("a1", "b1"), naming "group_a1_b1"
-        {
-            let output_data = OutPointVec::new_builder()
-                .set(vec![
-                    deployer.get_out_point("a1"),
-                    deployer.get_out_point("b1"),
-                ])
-                .build()
-                .as_bytes()
-                .pack();
-            let output = CellOutput::new_builder()
-                .lock(node2021.always_success_script())
-                .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
-                .unwrap();
-            deployer.deploy(node2021, "group_a1_b1", output, output_data)
-        }
+        deployer.deploy_dep_group(node2021, "group_a1_b1", &["a1", "b1"]);
 
         let code_hash_via_data_hash = {
             let out_point = deployer.get_out_point("a1");
@@ -261,47 +196,49 @@ impl Case for RFC0029 {
         assert!(node2021.get_tip_block().epoch().number() < RFC0029_EPOCH_NUMBER);
 
         let input = node2021.get_spendable_always_success_cells()[0].to_owned();
-        for case in self.cases_params() {
-            let node = {
-                let node = node2021.clone_node(&format!("case-{}-node", case.id));
-                node.pull_node(node2021).unwrap();
-                node
-            };
-            let tx = self.build_transaction(
-                &code_hash_via_data_hash,
-                &code_hash_via_type_hash,
-                node2021,
-                &deployer,
-                &input,
-                case.script_hash_type,
-                case.cell_deps,
-            );
-            let actual_result = node.build_according_to_instructions(
-                case.height,
-                vec![
-                    BuildInstruction::Propose {
-                        template_number: case.height - 2,
-                        proposal_short_id: tx.proposal_short_id(),
-                    },
-                    BuildInstruction::Commit {
-                        template_number: case.height,
-                        transaction: tx,
-                    },
-                ],
-            );
-            assert_result_eq!(
-                case.expected_result,
-                actual_result,
-                "case.id: {}, node.log: {}",
-                case.id,
-                node.log_path().to_string_lossy()
-            );
+        Context {
+            deployer,
+            code_hash_via_data_hash,
+            code_hash_via_type_hash,
+            input,
         }
     }
-}
 
-impl RFC0029 {
-    fn cases_params(&self) -> Vec<CaseParams> {
+    fn run_one(&self, context: &Context, nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let tx = self.build_transaction(
+            &context.code_hash_via_data_hash,
+            &context.code_hash_via_type_hash,
+            node,
+            &context.deployer,
+            &context.input,
+            case.script_hash_type,
+            case.cell_deps.clone(),
+        );
+        let actual_result = node.build_according_to_instructions(
+            case.height,
+            vec![
+                BuildInstruction::Propose {
+                    template_number: case.height - 2,
+                    proposal_short_id: tx.proposal_short_id(),
+                },
+                BuildInstruction::Commit {
+                    template_number: case.height,
+                    transaction: tx,
+                },
+            ],
+        );
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id: {}, node.log: {}",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
+
+    fn params(&self) -> Vec<CaseParams> {
         vec![
             CaseParams {
                 id: 0,
@@ -669,7 +606,9 @@ impl RFC0029 {
             },
         ]
     }
+}
 
+impl RFC0029 {
     fn build_transaction(
         &self,
         code_hash_via_data_hash: &Byte32,
@@ -6,15 +6,13 @@ use ckb_testkit::ckb_types::{
     packed::{CellInput, CellOutput, OutPoint},
     prelude::*,
 };
-use ckb_testkit::util::{
-    since_from_absolute_epoch_number_with_fraction, since_from_relative_epoch_number_with_fraction,
-};
+use ckb_testkit::util::Since;
 use ckb_testkit::{assert_result_eq, BuildInstruction};
 
 #[derive(Debug)]
-struct CaseParams {
+pub struct CaseParams {
     id: usize,
-    since: u64,
+    since: Since,
     expected_result: Result<EpochNumberWithFraction, &'static str>,
 }
 
@@ -114,7 +112,10 @@ struct CaseParams {
 /// ```
 pub struct RFC0030;
 
-impl Case for RFC0030 {
+impl ParameterizedCase for RFC0030 {
+    type Params = CaseParams;
+    type Context = OutPoint;
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -122,7 +123,7 @@ impl Case for RFC0030 {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
@@ -132,117 +133,56 @@ impl Case for RFC0030 {
 
     // NOTE: This test make a strong assumption that
     // `input.tx_info.block.epoch == EpochNumberWithFraction(1, 0, 1000)`
-    fn run(&self, nodes: Nodes) {
+    //
+    // Construct input out point which
+    // `input.tx_info.block.epoch == EpochNumberWithFraction(1, 0, 1000)
+    fn prepare(&self, nodes: &Nodes) -> OutPoint {
         let node2021 = nodes.get_node("node2021");
-
-        // Construct input out point which
-        // `input.tx_info.block.epoch == EpochNumberWithFraction(1, 0, 1000)
         assert!(node2021.get_tip_block().epoch() <= EpochNumberWithFraction::new(1, 0, 1000));
-        let input_out_point = {
-            let height = estimate_start_number_of_epoch(node2021, 1);
-            assert!(node2021.get_tip_block_number() <= height);
-            node2021.mine_to(height);
-
-            let tip_block = node2021.get_tip_block();
-            assert_eq!(tip_block.epoch(), EpochNumberWithFraction::new(1, 0, 1000));
-            assert_eq!(EpochNumberWithFraction::new(1, 0, 1000), tip_block.epoch(),);
+        let height = estimate_start_number_of_epoch(node2021, 1);
+        assert!(node2021.get_tip_block_number() <= height);
+        node2021.mine_to(height);
 
-            let tip_cellbase_hash = tip_block
-                .transaction(0)
-                .expect("cellbase transaction")
-                .hash();
-            OutPoint::new(tip_cellbase_hash, 0)
-        };
-
-        for case in self.cases_params() {
-            let tx = build_transaction(node2021, &input_out_point, case.since);
-            if let Ok(expected_mature_epoch) = case.expected_result {
-                let node = node2021.clone_node(&format!("case-{}-node2021", case.id));
-                let mature_height = epoch_to_height(&node, expected_mature_epoch);
-                let immature_height = mature_height - 1;
-                let immature_result = node.build_according_to_instructions(
-                    immature_height,
-                    vec![
-                        BuildInstruction::Propose {
-                            proposal_short_id: tx.proposal_short_id(),
-                            template_number: immature_height - 2,
-                        },
-                        BuildInstruction::Commit {
-                            transaction: tx.clone(),
-                            template_number: immature_height,
-                        },
-                    ],
-                );
-                assert_result_eq!(
-                    Result::<(), &str>::Err(ERROR_IMMATURE),
-                    immature_result,
-                    "case.id: {}, node.log: {}",
-                    case.id,
-                    node.log_path().to_string_lossy()
-                );
+        let tip_block = node2021.get_tip_block();
+        assert_eq!(tip_block.epoch(), EpochNumberWithFraction::new(1, 0, 1000));
 
-                let mature_result = node.build_according_to_instructions(
-                    mature_height,
-                    vec![BuildInstruction::Commit {
-                        transaction: tx.clone(),
-                        template_number: mature_height,
-                    }],
-                );
-                assert_eq!(
-                    Result::<(), String>::Ok(()),
-                    mature_result,
-                    "case.id: {}, node.log: {}",
-                    case.id,
-                    node.log_path().to_string_lossy()
-                );
-            }
-        }
+        let tip_cellbase_hash = tip_block
+            .transaction(0)
+            .expect("cellbase transaction")
+            .hash();
+        OutPoint::new(tip_cellbase_hash, 0)
     }
-}
 
-impl RFC0030 {
-    fn cases_params(&self) -> Vec<CaseParams> {
+    fn params(&self) -> Vec<CaseParams> {
         vec![
             CaseParams {
                 id: 0,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 0),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 0)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 1,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1, 0),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 1, 0)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 2,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 3,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 1, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(1, 1, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 4,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 2, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(1, 2, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 5,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1, 2),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 1, 2)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(
                     2,
                     1000 * 1 / 2,
@@ -251,184 +191,132 @@ impl RFC0030 {
             },
             CaseParams {
                 id: 6,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 0, 0),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(1, 0, 0)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 7,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 1, 0),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(1, 1, 0)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 8,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 0, 1),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(1, 0, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 9,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(0, 1, 1),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(0, 1, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 10,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(0, 5, 4),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(0, 5, 4)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 250, 1000)),
             },
             CaseParams {
                 id: 11,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(0, 1, 2),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(0, 1, 2)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(1, 500, 1000)),
             },
             CaseParams {
                 id: 12,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 999, 1000),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 999, 1000)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 999, 1000)),
             },
             CaseParams {
                 id: 13,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 0, 1000),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 0, 1000)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 14,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 0, 0),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 0, 0)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 15,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 0, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 0, 1)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 16,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 1, 0),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 1, 0)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 17,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 1, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 1, 1)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 18,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 1001, 1000),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 1001, 1000)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 19,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 999, 1000),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(1, 999, 1000)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 999, 1000)),
             },
             CaseParams {
                 id: 20,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 1000),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 21,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 0),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 0)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 22,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 1),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 1)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 23,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1, 0),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 1, 0)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 24,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1, 1),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 1, 1)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 25,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1001, 1000),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 1001, 1000)),
                 expected_result: Err(ERROR_IMMATURE),
             },
             CaseParams {
                 id: 0,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 0),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 0)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 1,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1, 0),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 1, 0)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 2,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 3,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 1, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(1, 1, 1)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 4,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 2, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(1, 2, 1)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 5,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1, 2),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 1, 2)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(
                     2,
                     1000 * 1 / 2,
@@ -437,151 +325,177 @@ impl RFC0030 {
             },
             CaseParams {
                 id: 6,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 0, 0),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(1, 0, 0)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 7,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 1, 0),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(1, 1, 0)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 8,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 0, 1),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(1, 0, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
             },
             CaseParams {
                 id: 9,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(0, 1, 1),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(0, 1, 1)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 10,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(0, 5, 4),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(0, 5, 4)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 11,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(0, 1, 2),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(0, 1, 2)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(1, 500, 1000)),
             },
             CaseParams {
                 id: 12,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 999, 1000),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(2, 999, 1000)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 999, 1000)),
             },
             CaseParams {
                 id: 13,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 0, 1000),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 0, 1000)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(3, 0, 1000)),
             },
             CaseParams {
                 id: 14,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 0, 0),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 0, 0)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(3, 0, 1000)),
             },
             CaseParams {
                 id: 15,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 0, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 0, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(3, 0, 1000)),
             },
             CaseParams {
                 id: 16,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 1, 0),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 1, 0)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 17,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 1, 1),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 1, 1)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 18,
-                since: since_from_absolute_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(3, 1001, 1000),
-                ),
+                since: Since::absolute_epoch(EpochNumberWithFraction::new_unchecked(3, 1001, 1000)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 19,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(1, 999, 1000),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(1, 999, 1000)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(2, 999, 1000)),
             },
             CaseParams {
                 id: 20,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 1000),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 1000)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(3, 0, 1000)),
             },
             CaseParams {
                 id: 21,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 0),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 0)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(3, 0, 1000)),
             },
             CaseParams {
                 id: 22,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 0, 1),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 0, 1)),
                 expected_result: Ok(EpochNumberWithFraction::new_unchecked(3, 0, 1000)),
             },
             CaseParams {
                 id: 23,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1, 0),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 1, 0)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 24,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1, 1),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 1, 1)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
             CaseParams {
                 id: 25,
-                since: since_from_relative_epoch_number_with_fraction(
-                    EpochNumberWithFraction::new_unchecked(2, 1001, 1000),
-                ),
+                since: Since::relative_epoch(EpochNumberWithFraction::new_unchecked(2, 1001, 1000)),
                 expected_result: Err(ERROR_INVALID_SINCE),
             },
         ]
     }
+
+    fn run_one(
+        &self,
+        input_out_point: &OutPoint,
+        nodes: &Nodes,
+        case: &CaseParams,
+    ) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+
+        // The table agrees with the RFC0030 rules of `Since::validate`
+        let validation = case.since.validate(true);
+        assert_eq!(
+            validation.is_err(),
+            case.expected_result == Err(ERROR_INVALID_SINCE),
+            "case.id: {}, since: {}, validate: {:?}",
+            case.id,
+            case.since,
+            validation,
+        );
+
+        let expected_mature_epoch = match case.expected_result {
+            Ok(expected_mature_epoch) => expected_mature_epoch,
+            Err(_) => return Ok(()),
+        };
+        let tx = build_transaction(node, input_out_point, case.since);
+        let mature_height = epoch_to_height(node, expected_mature_epoch);
+        let immature_height = mature_height - 1;
+        let immature_result = node.build_according_to_instructions(
+            immature_height,
+            vec![
+                BuildInstruction::Propose {
+                    proposal_short_id: tx.proposal_short_id(),
+                    template_number: immature_height - 2,
+                },
+                BuildInstruction::Commit {
+                    transaction: tx.clone(),
+                    template_number: immature_height,
+                },
+            ],
+        );
+        assert_result_eq!(
+            Result::<(), &str>::Err(ERROR_IMMATURE),
+            immature_result,
+            "case.id: {}, since: {}, node.log: {}",
+            case.id,
+            case.since,
+            node.log_path().to_string_lossy()
+        );
+
+        let mature_result = node.build_according_to_instructions(
+            mature_height,
+            vec![BuildInstruction::Commit {
+                transaction: tx,
+                template_number: mature_height,
+            }],
+        );
+        assert_eq!(
+            Result::<(), String>::Ok(()),
+            mature_result,
+            "case.id: {}, since: {}, node.log: {}",
+            case.id,
+            case.since,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
 }
 
-fn build_transaction(node: &Node, input_out_point: &OutPoint, since: u64) -> TransactionView {
+fn build_transaction(node: &Node, input_out_point: &OutPoint, since: Since) -> TransactionView {
     TransactionBuilder::default()
-        .input(CellInput::new(input_out_point.clone(), since))
+        .input(CellInput::new(input_out_point.clone(), since.raw()))
         .output(
             CellOutput::new_builder()
                 .lock(node.always_success_script())
@@ -14,7 +14,7 @@ use ckb_testkit::{assert_result_eq, Node, NodeOptions, Nodes, SYSTEM_CELL_ALWAYS
 ///
 /// * We determine the VM selection via checking the transaction cycles.
 /// * We want the input transaction is VM-determined, so in this case,
-/// we config node with `app_config: "testdata/config/ckb2021_block_assembler_hash_type_is_data/ckb.toml"`
+/// we config node with `app_config: "testdata/config/ckb2021_block_assembler_hash_type_is_data"`
 ///
 /// ## Cases
 ///
@@ -44,30 +44,39 @@ const RFC0032_BLOCK_NUMBER: BlockNumber = 3000;
 const HARDFORK_DELAY_WINDOW: u64 = 10;
 const ERROR_INVALID_VM_VERSION: &str = "Invalid VM Version";
 
-impl Case for RFC0032 {
+impl ParameterizedCase for RFC0032 {
+    type Params = CaseParams;
+    type Context = ();
+
     fn case_options(&self) -> CaseOptions {
         Default::default()
     }
 
-    fn run(&self, _nodes: Nodes) {
-        for case in self.cases_params() {
-            let node = self.setup_node(&case);
-            let tx = self.build_transaction(&node, &case);
-            let actual_result = self.run_case(&node, &tx);
-            assert_result_eq!(
-                case.expected_result,
-                actual_result,
-                "case.id: {}, node.log_path: {}, tx: {:#x}",
-                case.id,
-                node.log_path().to_string_lossy(),
-                tx.hash(),
-            );
-        }
+    // Every row sets up its own node, see `setup_node`
+    fn prepare(&self, _nodes: &Nodes) {}
+
+    fn params(&self) -> Vec<CaseParams> {
+        self.cases_params()
+    }
+
+    fn run_one(&self, _context: &(), _nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = self.setup_node(case);
+        let tx = self.build_transaction(&node, case);
+        let actual_result = self.run_case(&node, &tx);
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id: {}, node.log_path: {}, tx: {:#x}",
+            case.id,
+            node.log_path().to_string_lossy(),
+            tx.hash(),
+        );
+        Ok(())
     }
 }
 
 #[derive(Debug)]
-struct CaseParams {
+pub struct CaseParams {
     id: usize,
     type_script_hash_type: ScriptHashType,
     height: BlockNumber,
@@ -88,13 +97,13 @@ impl RFC0032 {
     fn setup_node(&self, case: &CaseParams) -> Node {
         let node_options = NodeOptions {
             node_name: format!("{}-case-{}", self.case_name(), case.id),
-            ckb_binary: CKB2021.read().unwrap().clone(),
+            ckb_binary: CKB2021.to_string(),
             initial_database: "testdata/db/Epoch2V2TestData",
             chain_spec: "testdata/spec/ckb2021",
             // We want the input transaction is VM-determined
             app_config: "testdata/config/ckb2021_block_assembler_hash_type_is_data",
         };
-        let mut node = Node::init(self.case_name(), node_options, true);
+        let mut node = Node::init(self.case_name(), node_options);
         node.start();
 
         node.mine_to(case.height);
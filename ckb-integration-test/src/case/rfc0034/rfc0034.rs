@@ -1,14 +1,12 @@
 use super::{ERROR_INVALID_ECALL, RFC0034_EPOCH_NUMBER};
 use crate::prelude::*;
-use crate::util::deployer::Deployer;
 use crate::util::estimate_start_number_of_epoch;
-use ckb_exec_params::ExecParams;
-use ckb_testkit::assert_result_eq;
 use ckb_testkit::ckb_types::{
     core::{Capacity, ScriptHashType, TransactionBuilder, TransactionView},
     packed::{Bytes, CellDep, CellInput, CellOutput, OutPoint, Script},
     prelude::*,
 };
+use ckb_testkit::{assert_result_eq, Deployer, ExecParams, TestScript};
 
 /// * `output.type_.code_hash` points to `exec_caller`
 /// * `exec`'s parameter `bounds` is always be `0`
@@ -73,7 +71,11 @@ use ckb_testkit::ckb_types::{
 /// ```
 pub struct RFC0034;
 
-impl Case for RFC0034 {
+impl ParameterizedCase for RFC0034 {
+    type Params = CaseParams;
+    /// The deployed `exec_callee` and `exec_caller`
+    type Context = Deployer;
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -81,7 +83,7 @@ impl Case for RFC0034 {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/Epoch2V2TestData",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
@@ -89,7 +91,7 @@ impl Case for RFC0034 {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn prepare(&self, nodes: &Nodes) -> Deployer {
         let node2021 = nodes.get_node("node2021");
 
         // Make sure the VM1 is activated
@@ -98,42 +100,29 @@ impl Case for RFC0034 {
 
         // Deploy contract cells
         let mut deployer = Deployer::new();
-        {
-            let scripts_data = vec![
-                (
-                    "exec_callee",
-                    include_bytes!("../../../testdata/script/exec_callee").pack(),
-                ),
-                (
-                    "exec_caller",
-                    include_bytes!("../../../testdata/script/exec_caller").pack(),
-                ),
-            ];
-            for (script_name, script_data) in scripts_data {
-                let output = CellOutput::new_builder()
-                    .lock(node2021.always_success_script())
-                    .build_exact_capacity(Capacity::bytes(script_data.len()).unwrap())
-                    .unwrap();
-                deployer.deploy(node2021, script_name, output, script_data);
-            }
-        }
+        deployer.deploy_test_script(node2021, TestScript::ExecCallee);
+        deployer.deploy_test_script(node2021, TestScript::ExecCaller);
+        deployer
+    }
 
-        for case in self.cases_params() {
-            let txs = self.build_transactions(node2021, &deployer, &case);
-            let node = node2021.clone_node(&format!("case-{}", case.id));
-            node.pull_node(node2021).unwrap();
+    fn params(&self) -> Vec<CaseParams> {
+        self.cases_params()
+    }
 
-            let actual_result = self.run_case(&node, &txs);
-            assert_result_eq!(
-                case.expected_result,
-                actual_result,
-                "case: {}, expected: {:?}, actual: {:?}, node.log_path: {}",
-                case.id,
-                case.expected_result,
-                actual_result,
-                node.log_path().to_string_lossy(),
-            );
-        }
+    fn run_one(&self, deployer: &Deployer, nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let txs = self.build_transactions(node, deployer, case);
+        let actual_result = self.run_case(node, &txs);
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case: {}, expected: {:?}, actual: {:?}, node.log_path: {}",
+            case.id,
+            case.expected_result,
+            actual_result,
+            node.log_path().to_string_lossy(),
+        );
+        Ok(())
     }
 }
 
@@ -153,7 +142,7 @@ enum ExecPlace {
 }
 
 #[derive(Debug)]
-struct CaseParams {
+pub struct CaseParams {
     id: usize,
     exec_source: ExecSource,
     exec_place: ExecPlace,
@@ -192,16 +181,12 @@ impl RFC0034 {
             .out_point(deployer.get_cell("exec_caller").out_point.clone())
             .build();
         let exec_caller_output = {
-            let exec_params = ExecParams::new_builder()
-                .source(ckb_exec_params::ckb_types::prelude::Pack::pack(
-                    &(case.exec_source as u32),
-                ))
-                .place(ckb_exec_params::ckb_types::prelude::Pack::pack(
-                    &(case.exec_place as u32),
-                ))
-                .index(ckb_exec_params::ckb_types::prelude::Pack::pack(&0u32))
-                .bounds(ckb_exec_params::ckb_types::prelude::Pack::pack(&0u64))
-                .build();
+            let exec_params = ExecParams {
+                source: case.exec_source as u32,
+                place: case.exec_place as u32,
+                index: 0,
+                bounds: 0,
+            };
             let exec_caller_data_hash = {
                 let exec_caller_out_point = deployer.get_out_point("exec_caller");
                 let cell_with_status = node
@@ -221,8 +206,7 @@ impl RFC0034 {
                         Script::new_builder()
                             .hash_type(case.script_hash_type.into())
                             .code_hash(exec_caller_data_hash)
-                            // `exec_params.as_slice().pack()` or `exec_params.as_bytes().pack()`?
-                            .args(exec_params.as_slice().pack())
+                            .args(exec_params.serialize().pack())
                             .build()
                     })
                     .pack(),
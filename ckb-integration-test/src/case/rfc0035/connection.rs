@@ -4,7 +4,7 @@
 //
 // Check the connections via RPC `get_peers`
 
-use crate::case::{Case, CaseOptions};
+use crate::case::{Case, CaseOptions, Requirements};
 use crate::{CKB2019, CKB2021};
 use ckb_testkit::ckb_types::core::BlockNumber;
 use ckb_testkit::util::wait_until;
@@ -24,28 +24,28 @@ impl Case for RFC0035V2021Connection {
             node_options: vec![
                 NodeOptions {
                     node_name: String::from("node2019"),
-                    ckb_binary: CKB2019.read().unwrap().clone(),
+                    ckb_binary: CKB2019.to_string(),
                     initial_database: "testdata/db/Epoch2V1TestData",
                     chain_spec: "testdata/spec/ckb2019",
                     app_config: "testdata/config/ckb2019",
                 },
                 NodeOptions {
                     node_name: String::from("node2019_2"),
-                    ckb_binary: CKB2019.read().unwrap().clone(),
+                    ckb_binary: CKB2019.to_string(),
                     initial_database: "testdata/db/Epoch2V1TestData",
                     chain_spec: "testdata/spec/ckb2019",
                     app_config: "testdata/config/ckb2019",
                 },
                 NodeOptions {
                     node_name: String::from("node2021"),
-                    ckb_binary: CKB2021.read().unwrap().clone(),
+                    ckb_binary: CKB2021.to_string(),
                     initial_database: "testdata/db/Epoch2V2TestData",
                     chain_spec: "testdata/spec/ckb2021",
                     app_config: "testdata/config/ckb2021",
                 },
                 NodeOptions {
                     node_name: String::from("node2021_2"),
-                    ckb_binary: CKB2021.read().unwrap().clone(),
+                    ckb_binary: CKB2021.to_string(),
                     initial_database: "testdata/db/Epoch2V2TestData",
                     chain_spec: "testdata/spec/ckb2021",
                     app_config: "testdata/config/ckb2021",
@@ -56,6 +56,10 @@ impl Case for RFC0035V2021Connection {
         }
     }
 
+    fn requires(&self) -> Requirements {
+        super::requirements(&self.case_options())
+    }
+
     fn run(&self, nodes: Nodes) {
         let rfc0035_activated_number = RFC0035_BLOCK_NUMBER - 1;
         let rfc0035_non_activated_number = rfc0035_activated_number - 1;
@@ -94,12 +98,12 @@ impl Case for RFC0035V2021Connection {
         let mut fresh_node2021 = {
             let node_options = NodeOptions {
                 node_name: String::from("fresh_node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
             };
-            Node::init(self.case_name(), node_options, true)
+            Node::init(self.case_name(), node_options)
         };
         fresh_node2021.start();
         fresh_node2021.p2p_connect(node2021);
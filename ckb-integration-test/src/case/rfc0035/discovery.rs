@@ -22,21 +22,21 @@ impl Case for RFC0035V2021Discovery {
             node_options: vec![
                 NodeOptions {
                     node_name: String::from("node2021_1"),
-                    ckb_binary: CKB2021.read().unwrap().clone(),
+                    ckb_binary: CKB2021.to_string(),
                     initial_database: "testdata/db/Epoch2V2TestData",
                     chain_spec: "testdata/spec/ckb2021",
                     app_config: "testdata/config/connect_outbound_interval_secs",
                 },
                 NodeOptions {
                     node_name: String::from("node2021_2"),
-                    ckb_binary: CKB2021.read().unwrap().clone(),
+                    ckb_binary: CKB2021.to_string(),
                     initial_database: "testdata/db/Epoch2V2TestData",
                     chain_spec: "testdata/spec/ckb2021",
                     app_config: "testdata/config/connect_outbound_interval_secs",
                 },
                 NodeOptions {
                     node_name: String::from("node2021_3"),
-                    ckb_binary: CKB2021.read().unwrap().clone(),
+                    ckb_binary: CKB2021.to_string(),
                     initial_database: "testdata/db/Epoch2V2TestData",
                     chain_spec: "testdata/spec/ckb2021",
                     app_config: "testdata/config/connect_outbound_interval_secs",
@@ -3,5 +3,16 @@ pub(super) mod discovery;
 pub(super) mod relay_transaction;
 pub(super) mod v2019;
 
-const HARDFORK_DELAY_WINDOW: u64 = 10;
-const RFC0035_BLOCK_NUMBER: ckb_testkit::ckb_types::core::BlockNumber = 3000;
+use crate::case::{CaseOptions, Requirements};
+use crate::{CKB2019, CKB2021};
+
+pub(super) const HARDFORK_DELAY_WINDOW: u64 = 10;
+pub(super) const RFC0035_BLOCK_NUMBER: ckb_testkit::ckb_types::core::BlockNumber = 3000;
+
+/// The cases run ckb2019 nodes, which predate the fork2021, against ckb2021 nodes, which
+/// activate it at `RFC0035_BLOCK_NUMBER`.
+pub(super) fn requirements(case_options: &CaseOptions) -> Requirements {
+    Requirements::from_case_options(case_options)
+        .ckb_binary(CKB2019, None, Some("0.44.0"))
+        .ckb_binary(CKB2021, Some("0.100.0"), None)
+}
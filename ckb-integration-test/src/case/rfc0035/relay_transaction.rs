@@ -7,7 +7,7 @@ use ckb_testkit::ckb_types::{
     core::{
         cell::CellMeta, BlockNumber, Cycle, ScriptHashType, TransactionBuilder, TransactionView,
     },
-    packed::{CellInput, CellOutput, OutPoint, RelayMessageReader, Script},
+    packed::{CellInput, CellOutput, OutPoint, RelayMessageUnion, Script},
     prelude::*,
 };
 use ckb_testkit::connector::{
@@ -114,7 +114,19 @@ use std::time::Duration;
 
 pub struct RFC0035RelayTransaction;
 
-impl Case for RFC0035RelayTransaction {
+/// The transactions relayed by the rows and their cycles, see `prepare`
+pub struct Context {
+    data0_tx: TransactionView,
+    data1_tx: TransactionView,
+    type_tx: TransactionView,
+    vm0_cycles: Cycle,
+    vm1_cycles: Cycle,
+}
+
+impl ParameterizedCase for RFC0035RelayTransaction {
+    type Params = CaseParams;
+    type Context = Context;
+
     fn case_options(&self) -> CaseOptions {
         CaseOptions {
             make_all_nodes_connected: false,
@@ -122,7 +134,7 @@ impl Case for RFC0035RelayTransaction {
             make_all_nodes_connected_and_synced: false,
             node_options: vec![NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/Epoch2V2TestData",
                 chain_spec: "testdata/spec/ckb2021",
                 // Note: `input.script.hash_type == "data"` ensures the input's scripts are
@@ -132,7 +144,11 @@ impl Case for RFC0035RelayTransaction {
         }
     }
 
-    fn run(&self, nodes: Nodes) {
+    fn requires(&self) -> Requirements {
+        super::requirements(&self.case_options())
+    }
+
+    fn prepare(&self, nodes: &Nodes) -> Context {
         let node2021 = nodes.get_node("node2021");
         node2021.mine(13);
 
@@ -174,218 +190,29 @@ impl Case for RFC0035RelayTransaction {
         // Node versions:
         //   - v0_43, `$CKB2019 --version`
         //   - v0_100, `$CKB2021 --version`
-        let data0_tx;
-        let data1_tx;
-        let type_tx;
-        let vm0_cycles;
-        let vm1_cycles;
-        {
-            let node_used_to_dry_run_txs = {
-                let node = node2021.clone_node("used_to_dry_run_txs");
-                // Let `node_used_to_dry_run_txs` activates fork2021, so that
-                // it allows data1-transactions
-                node.pull_node(node2021).unwrap();
-                node.mine_to(RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1);
-                node
-            };
-
-            data0_tx = Self::build_transaction(node2021, &input, ScriptHashType::Data);
-            data1_tx = Self::build_transaction(node2021, &input, ScriptHashType::Data1);
-            type_tx = Self::build_transaction(node2021, &input, ScriptHashType::Type);
-            vm0_cycles = node_used_to_dry_run_txs.get_transaction_cycles(&data0_tx);
-            vm1_cycles = node_used_to_dry_run_txs.get_transaction_cycles(&data1_tx);
-        };
-
-        for case in self.cases_params() {
-            let tx = match case.tx_script_hash_type {
-                ScriptHashType::Data => data0_tx.clone(),
-                ScriptHashType::Type => type_tx.clone(),
-                ScriptHashType::Data1 => data1_tx.clone(),
-            };
-            let relayed_cycles = match case.relayed_cycles {
-                ScriptHashType::Data => vm0_cycles,
-                ScriptHashType::Type => unreachable!(),
-                ScriptHashType::Data1 => vm1_cycles,
-            };
-            let node = self.setup_node(&case, node2021);
-            let mut connector = self.setup_connector(&case);
-            let actual_result = self.run(&case, &mut connector, &node, &tx, relayed_cycles);
-            assert_eq!(
-                case.expected_result,
-                actual_result,
-                "case.id: {}, node.log_path: {}, tx.hash: {:#x}",
-                case.id,
-                node.log_path().to_string_lossy(),
-                tx.hash(),
-            );
-        }
-    }
-}
-
-impl RFC0035RelayTransaction {
-    // Start a fresh node according to configurations spefified by `case`, and
-    // synchronize its chain from `base_chain_node`
-    fn setup_node(&self, case: &CaseParams, base_chain_node: &Node) -> Node {
-        // We only test v0.100
-        let is_ckb2021 = true;
-        let node_options = NodeOptions {
-            node_name: format!("case-{}", case.id),
-            ckb_binary: {
-                if is_ckb2021 {
-                    CKB2021.read().unwrap().clone()
-                } else {
-                    CKB2019.read().unwrap().clone()
-                }
-            },
-            chain_spec: {
-                if is_ckb2021 {
-                    "testdata/spec/ckb2021"
-                } else {
-                    "testdata/spec/ckb2019"
-                }
-            },
-            app_config: {
-                if is_ckb2021 {
-                    "testdata/config/ckb2021"
-                } else {
-                    "testdata/config/ckb2019"
-                }
-            },
-            initial_database: "testdata/db/empty",
-        };
-        let node = {
-            let mut node = Node::init(self.case_name(), node_options, is_ckb2021);
-            node.start();
+        let node_used_to_dry_run_txs = {
+            let node = node2021.clone_node("used_to_dry_run_txs");
+            // Let `node_used_to_dry_run_txs` activates fork2021, so that
+            // it allows data1-transactions
+            node.pull_node(node2021).unwrap();
+            node.mine_to(RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1);
             node
         };
-
-        node.pull_node(base_chain_node).unwrap();
-        node.mine_to(case.height);
-        // TODO FIXME node may need time to switch to fork2021
-        ::std::thread::sleep(Duration::from_secs(2));
-        node
-    }
-
-    // Start a connector
-    fn setup_connector(&self, case: &CaseParams) -> Connector {
-        let shared = Arc::new(RwLock::new(SharedState::new()));
-        ConnectorBuilder::new()
-            .protocol_meta({
-                SimpleProtocolHandler::new(Arc::clone(&shared), case.protocol.clone()).build(true)
-            })
-            .protocol_meta({
-                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true)
-            })
-            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared)
-    }
-
-    // Run case.
-    //
-    // 1. Connect the target node via p2p connection
-    // 2. Setup specified protocol
-    // 3. Relay specified transaction
-    // 4. Wait the result
-    fn run(
-        &self,
-        case: &CaseParams,
-        connector: &mut Connector,
-        node: &Node,
-        transaction: &TransactionView,
-        relayed_cycles: Cycle,
-    ) -> Result<(), Error> {
-        let _ = connector
-            .connect(&node)
-            .map_err(|_| Error::ConnectionTimeout)?;
-        let _ = connector
-            .send_relay_transaction_hash(&node, case.protocol.clone(), vec![transaction.hash()])
-            .unwrap();
-
-        let received_get_relay_txs = wait_until(20, || {
-            if let Ok(data) = connector.recv_timeout(Duration::from_secs(20), node, &case.protocol)
-            {
-                return RelayMessageReader::from_compatible_slice(&data)
-                    .unwrap()
-                    .to_enum()
-                    .item_name()
-                    == "GetRelayTransactions";
-            }
-            false
-        });
-        if !received_get_relay_txs {
-            return Err(Error::RelayTransactionHashFailed);
-        }
-
-        connector
-            .send_relay_transaction(&node, case.protocol.clone(), &transaction, relayed_cycles)
-            .unwrap();
-
-        let tx_relayed = wait_until(5, || node.is_transaction_pending(transaction));
-        let banned = wait_until(5, || {
-            let banned_addresses = node.rpc_client().get_banned_addresses();
-            !banned_addresses.is_empty()
-        });
-
-        match (tx_relayed, banned) {
-            (true, false) => Ok(()),
-            _ => Err(Error::RelayTransactionFailed),
-        }
-    }
-
-    fn build_transaction(
-        node: &Node,
-        input: &CellMeta,
-        type_script_hash_type: ScriptHashType,
-    ) -> TransactionView {
-        assert!(input.cell_output.lock().hash_type() == ScriptHashType::Data.into());
-
-        let type_ = Self::build_always_success_script(node, type_script_hash_type);
-        let output = CellOutput::new_builder()
-            .lock(input.cell_output.lock())
-            .type_(Some(type_).pack())
-            .capacity(input.capacity().pack())
-            .build();
-        TransactionBuilder::default()
-            .input(CellInput::new(input.out_point.clone(), 0))
-            .output(output)
-            .output_data(Default::default())
-            .cell_dep(node.always_success_cell_dep())
-            .build()
-    }
-
-    fn build_always_success_script(node: &Node, script_hash_type: ScriptHashType) -> Script {
-        let always_script_data_hash = {
-            let genesis_cellbase_hash = node.genesis_cellbase_hash();
-            let always_success_out_point =
-                OutPoint::new(genesis_cellbase_hash, SYSTEM_CELL_ALWAYS_SUCCESS_INDEX);
-            let cell = node
-                .rpc_client()
-                .get_live_cell(always_success_out_point.into(), true);
-            let cell_info = cell.cell.expect("genesis always cell must be live");
-            let cell_data_hash = cell_info.data.unwrap().hash;
-            cell_data_hash.pack()
-        };
-        let always_script_type_hash = {
-            let script = node.always_success_script();
-            assert!(script.hash_type() == ScriptHashType::Type.into());
-            script.code_hash()
-        };
-        match script_hash_type {
-            ScriptHashType::Data => Script::new_builder()
-                .code_hash(always_script_data_hash)
-                .hash_type(ScriptHashType::Data.into())
-                .build(),
-            ScriptHashType::Type => Script::new_builder()
-                .code_hash(always_script_type_hash)
-                .hash_type(ScriptHashType::Type.into())
-                .build(),
-            ScriptHashType::Data1 => Script::new_builder()
-                .code_hash(always_script_data_hash)
-                .hash_type(ScriptHashType::Data1.into())
-                .build(),
+        let data0_tx = Self::build_transaction(node2021, &input, ScriptHashType::Data);
+        let data1_tx = Self::build_transaction(node2021, &input, ScriptHashType::Data1);
+        let type_tx = Self::build_transaction(node2021, &input, ScriptHashType::Type);
+        let vm0_cycles = node_used_to_dry_run_txs.get_transaction_cycles(&data0_tx);
+        let vm1_cycles = node_used_to_dry_run_txs.get_transaction_cycles(&data1_tx);
+        Context {
+            data0_tx,
+            data1_tx,
+            type_tx,
+            vm0_cycles,
+            vm1_cycles,
         }
     }
 
-    fn cases_params(&self) -> Vec<CaseParams> {
+    fn params(&self) -> Vec<CaseParams> {
         vec![
             CaseParams {
                 id: 1,
@@ -632,10 +459,198 @@ impl RFC0035RelayTransaction {
             },
         ]
     }
+
+    fn run_one(&self, context: &Context, nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node2021 = nodes.get_node("node2021");
+        let tx = match case.tx_script_hash_type {
+            ScriptHashType::Data => &context.data0_tx,
+            ScriptHashType::Type => &context.type_tx,
+            ScriptHashType::Data1 => &context.data1_tx,
+        };
+        let relayed_cycles = match case.relayed_cycles {
+            ScriptHashType::Data => context.vm0_cycles,
+            ScriptHashType::Type => unreachable!(),
+            ScriptHashType::Data1 => context.vm1_cycles,
+        };
+        let node = self.setup_node(case, node2021);
+        let mut connector = self.setup_connector(case);
+        let actual_result = self.run(case, &mut connector, &node, tx, relayed_cycles);
+        assert_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id: {}, node.log_path: {}, tx.hash: {:#x}",
+            case.id,
+            node.log_path().to_string_lossy(),
+            tx.hash(),
+        );
+        Ok(())
+    }
+}
+
+impl RFC0035RelayTransaction {
+    // Start a fresh node according to configurations spefified by `case`, and
+    // synchronize its chain from `base_chain_node`
+    fn setup_node(&self, case: &CaseParams, base_chain_node: &Node) -> Node {
+        // We only test v0.100
+        let is_ckb2021 = true;
+        let node_options = NodeOptions {
+            node_name: format!("case-{}", case.id),
+            ckb_binary: {
+                if is_ckb2021 {
+                    CKB2021.to_string()
+                } else {
+                    CKB2019.to_string()
+                }
+            },
+            chain_spec: {
+                if is_ckb2021 {
+                    "testdata/spec/ckb2021"
+                } else {
+                    "testdata/spec/ckb2019"
+                }
+            },
+            app_config: {
+                if is_ckb2021 {
+                    "testdata/config/ckb2021"
+                } else {
+                    "testdata/config/ckb2019"
+                }
+            },
+            initial_database: "testdata/db/empty",
+        };
+        let node = {
+            let mut node = Node::init(self.case_name(), node_options);
+            node.start();
+            node
+        };
+
+        node.pull_node(base_chain_node).unwrap();
+        node.mine_to(case.height);
+        // TODO FIXME node may need time to switch to fork2021
+        ::std::thread::sleep(Duration::from_secs(2));
+        node
+    }
+
+    // Start a connector
+    fn setup_connector(&self, case: &CaseParams) -> Connector {
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        ConnectorBuilder::new()
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), case.protocol.clone()).build(true)
+            })
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true)
+            })
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared)
+    }
+
+    // Run case.
+    //
+    // 1. Connect the target node via p2p connection
+    // 2. Setup specified protocol
+    // 3. Relay specified transaction
+    // 4. Wait the result
+    fn run(
+        &self,
+        case: &CaseParams,
+        connector: &mut Connector,
+        node: &Node,
+        transaction: &TransactionView,
+        relayed_cycles: Cycle,
+    ) -> Result<(), Error> {
+        let _ = connector
+            .connect(&node)
+            .map_err(|_| Error::ConnectionTimeout)?;
+        let _ = connector
+            .send_relay_transaction_hash(&node, case.protocol.clone(), vec![transaction.hash()])
+            .unwrap();
+
+        let received_get_relay_txs = connector
+            .expect_message(
+                node,
+                &case.protocol,
+                Duration::from_secs(20),
+                |message: &RelayMessageUnion| {
+                    matches!(message, RelayMessageUnion::GetRelayTransactions(_))
+                },
+            )
+            .is_ok();
+        if !received_get_relay_txs {
+            return Err(Error::RelayTransactionHashFailed);
+        }
+
+        connector
+            .send_relay_transaction(&node, case.protocol.clone(), &transaction, relayed_cycles)
+            .unwrap();
+
+        let tx_relayed = wait_until(5, || node.is_transaction_pending(transaction));
+        let banned = node
+            .wait_banned(&*connector, Duration::from_secs(5))
+            .is_ok();
+
+        match (tx_relayed, banned) {
+            (true, false) => Ok(()),
+            _ => Err(Error::RelayTransactionFailed),
+        }
+    }
+
+    fn build_transaction(
+        node: &Node,
+        input: &CellMeta,
+        type_script_hash_type: ScriptHashType,
+    ) -> TransactionView {
+        assert!(input.cell_output.lock().hash_type() == ScriptHashType::Data.into());
+
+        let type_ = Self::build_always_success_script(node, type_script_hash_type);
+        let output = CellOutput::new_builder()
+            .lock(input.cell_output.lock())
+            .type_(Some(type_).pack())
+            .capacity(input.capacity().pack())
+            .build();
+        TransactionBuilder::default()
+            .input(CellInput::new(input.out_point.clone(), 0))
+            .output(output)
+            .output_data(Default::default())
+            .cell_dep(node.always_success_cell_dep())
+            .build()
+    }
+
+    fn build_always_success_script(node: &Node, script_hash_type: ScriptHashType) -> Script {
+        let always_script_data_hash = {
+            let genesis_cellbase_hash = node.genesis_cellbase_hash();
+            let always_success_out_point =
+                OutPoint::new(genesis_cellbase_hash, SYSTEM_CELL_ALWAYS_SUCCESS_INDEX);
+            let cell = node
+                .rpc_client()
+                .get_live_cell(always_success_out_point.into(), true);
+            let cell_info = cell.cell.expect("genesis always cell must be live");
+            let cell_data_hash = cell_info.data.unwrap().hash;
+            cell_data_hash.pack()
+        };
+        let always_script_type_hash = {
+            let script = node.always_success_script();
+            assert!(script.hash_type() == ScriptHashType::Type.into());
+            script.code_hash()
+        };
+        match script_hash_type {
+            ScriptHashType::Data => Script::new_builder()
+                .code_hash(always_script_data_hash)
+                .hash_type(ScriptHashType::Data.into())
+                .build(),
+            ScriptHashType::Type => Script::new_builder()
+                .code_hash(always_script_type_hash)
+                .hash_type(ScriptHashType::Type.into())
+                .build(),
+            ScriptHashType::Data1 => Script::new_builder()
+                .code_hash(always_script_data_hash)
+                .hash_type(ScriptHashType::Data1.into())
+                .build(),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
-struct CaseParams {
+pub struct CaseParams {
     id: usize,
 
     // The target node's tip number.
@@ -662,7 +677,7 @@ struct CaseParams {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-enum Error {
+pub enum Error {
     ConnectionTimeout,
     RelayTransactionHashFailed,
     RelayTransactionFailed,
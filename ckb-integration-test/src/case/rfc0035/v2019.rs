@@ -1,4 +1,4 @@
-use crate::case::{Case, CaseOptions};
+use crate::case::{Case, CaseOptions, Requirements};
 use crate::{CKB2019, CKB2021};
 use ckb_testkit::ckb_types::core::EpochNumber;
 use ckb_testkit::Nodes;
@@ -17,28 +17,28 @@ impl Case for RFC0035V2019 {
             node_options: vec![
                 NodeOptions {
                     node_name: String::from("node2019"),
-                    ckb_binary: CKB2019.read().unwrap().clone(),
+                    ckb_binary: CKB2019.to_string(),
                     initial_database: "testdata/db/Epoch2V1TestData",
                     chain_spec: "testdata/spec/ckb2019",
                     app_config: "testdata/config/ckb2019",
                 },
                 NodeOptions {
                     node_name: String::from("node2019_2"),
-                    ckb_binary: CKB2019.read().unwrap().clone(),
+                    ckb_binary: CKB2019.to_string(),
                     initial_database: "testdata/db/Epoch2V1TestData",
                     chain_spec: "testdata/spec/ckb2019",
                     app_config: "testdata/config/ckb2019",
                 },
                 NodeOptions {
                     node_name: String::from("node2021"),
-                    ckb_binary: CKB2021.read().unwrap().clone(),
+                    ckb_binary: CKB2021.to_string(),
                     initial_database: "testdata/db/Epoch2V2TestData",
                     chain_spec: "testdata/spec/ckb2021",
                     app_config: "testdata/config/ckb2021",
                 },
                 NodeOptions {
                     node_name: String::from("node2021_2"),
-                    ckb_binary: CKB2021.read().unwrap().clone(),
+                    ckb_binary: CKB2021.to_string(),
                     initial_database: "testdata/db/Epoch2V2TestData",
                     chain_spec: "testdata/spec/ckb2021",
                     app_config: "testdata/config/ckb2021",
@@ -49,6 +49,10 @@ impl Case for RFC0035V2019 {
         }
     }
 
+    fn requires(&self) -> Requirements {
+        super::requirements(&self.case_options())
+    }
+
     fn run(&self, nodes: Nodes) {
         let node2019 = nodes.get_node("node2019");
         let node2021 = nodes.get_node("node2021");
@@ -0,0 +1,165 @@
+use crate::prelude::*;
+use crate::util::distinct_binaries;
+use ckb_testkit::ckb_types::core::BlockNumber;
+use ckb_testkit::connector::{
+    message::network_identifier, observe_reaction, ConnectorBuilder, SharedState,
+    SimpleProtocolHandler, SimpleServiceHandler, SyncAttack, SyncReaction,
+};
+use ckb_testkit::{CkbBinary, SupportProtocols};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+const CHAIN_LENGTH: BlockNumber = 30;
+
+/// Play the catalogue of adversarial Sync scenarios, see `SyncAttack`, against a node of every
+/// registered ckb binary, from an inbound connector.
+///
+/// ## Cases
+///
+/// Every registered binary of a distinct version runs the rows below, in order.
+///
+/// ```text
+/// ┌───┬──────────────────────┬───────────┬────────┬────────────┐
+/// │id │ attack               │ PoW       │ height │ reaction   │
+/// ├───┼──────────────────────┼───────────┼────────┼────────────┤
+/// │0  │ InvalidCompactTarget │ Dummy     │ 30     │ Ban        │
+/// ├───┼──────────────────────┼───────────┼────────┼────────────┤
+/// │1  │ SkipEpochTransition  │ Dummy     │ 30     │ Ban        │
+/// ├───┼──────────────────────┼───────────┼────────┼────────────┤
+/// │2  │ LowWorkFork(1, 20)   │ Dummy     │ 30     │ Ignore     │
+/// ├───┼──────────────────────┼───────────┼────────┼────────────┤
+/// │3  │ UnconnectedHeaders   │ Dummy     │ 30     │ Ban        │
+/// ├───┼──────────────────────┼───────────┼────────┼────────────┤
+/// │4  │ DiscontinuousHeaders │ Dummy     │ 30     │ Ban        │
+/// ├───┼──────────────────────┼───────────┼────────┼────────────┤
+/// │5  │ EndlessInIbd(10)     │ Dummy     │ 30     │ Disconnect │
+/// ├───┼──────────────────────┼───────────┼────────┼────────────┤
+/// │6  │ InvalidPow           │ Eaglesong │ 0      │ Ban        │
+/// └───┴──────────────────────┴───────────┴────────┴────────────┘
+/// ```
+///
+/// ## Note
+///
+/// `Dummy` PoW accepts any nonce, so `InvalidPow` runs on a chain spec using `Eaglesong`, whose
+/// genesis target is hard enough that no nonce is valid by chance. The node cannot mine under
+/// `Eaglesong`, so the header extends the genesis. In every scenario the node's tip should stay
+/// unchanged.
+pub struct SyncAttacks;
+
+#[derive(Debug)]
+pub struct CaseParams {
+    binary: CkbBinary,
+    attack: SyncAttack,
+}
+
+impl ParameterizedCase for SyncAttacks {
+    type Params = CaseParams;
+    type Context = ();
+
+    fn case_options(&self) -> CaseOptions {
+        Default::default()
+    }
+
+    // Every row sets up its own node, see `setup_node`
+    fn prepare(&self, _nodes: &Nodes) {}
+
+    fn params(&self) -> Vec<CaseParams> {
+        let attacks = vec![
+            SyncAttack::InvalidCompactTarget,
+            SyncAttack::SkipEpochTransition,
+            SyncAttack::LowWorkFork {
+                fork_point: 1,
+                length: 20,
+            },
+            SyncAttack::UnconnectedHeaders,
+            SyncAttack::DiscontinuousHeaders,
+            SyncAttack::EndlessInIbd { times: 10 },
+            SyncAttack::InvalidPow,
+        ];
+        distinct_binaries()
+            .into_iter()
+            .flat_map(|binary| {
+                attacks.iter().map(move |attack| CaseParams {
+                    binary: binary.clone(),
+                    attack: attack.clone(),
+                })
+            })
+            .collect()
+    }
+
+    fn run_one(&self, _context: &(), _nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = self.setup_node(case);
+        let tip_hash = node.get_tip_block().hash();
+        let actual_reaction = self.launch(&node, case)?;
+        if actual_reaction != case.attack.expected_reaction() {
+            return Err(format!(
+                "expect {:?}, got {:?}, node.log: {}",
+                case.attack.expected_reaction(),
+                actual_reaction,
+                node.log_path().to_string_lossy()
+            ));
+        }
+        if tip_hash != node.get_tip_block().hash() {
+            return Err(format!(
+                "node's tip should stay unchanged, node.log: {}",
+                node.log_path().to_string_lossy()
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl SyncAttacks {
+    fn setup_node(&self, case: &CaseParams) -> Node {
+        let real_pow = case.attack == SyncAttack::InvalidPow;
+        let (chain_spec, app_config) = match (case.binary.is_ckb2021(), real_pow) {
+            (true, false) => ("testdata/spec/ckb2021", "testdata/config/ckb2021"),
+            (true, true) => ("testdata/spec/eaglesong_2021", "testdata/config/ckb2021"),
+            (false, false) => ("testdata/spec/ckb2019", "testdata/config/ckb2019"),
+            (false, true) => ("testdata/spec/eaglesong_2019", "testdata/config/ckb2019"),
+        };
+        let node_options = NodeOptions {
+            node_name: format!("node-{}", case.binary.name),
+            ckb_binary: case.binary.name.clone(),
+            initial_database: "testdata/db/empty",
+            chain_spec,
+            app_config,
+        };
+        let mut node = Node::init(self.case_name(), node_options);
+        node.start();
+        if !real_pow {
+            node.mine_to(CHAIN_LENGTH);
+        }
+        node
+    }
+
+    fn launch(&self, node: &Node, case: &CaseParams) -> Result<SyncReaction, String> {
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut connector = ConnectorBuilder::new()
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true)
+            })
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Identify)
+                    .build(false)
+            })
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        connector.connect(node)?;
+        let observed_address = connector
+            .get_session(node)
+            .ok_or_else(|| "disconnected before the attack".to_string())?
+            .address;
+        connector.send_identify_message(
+            node,
+            &network_identifier(node),
+            &node.rpc_client().local_node_info().version,
+            vec![],
+            observed_address,
+        )?;
+
+        case.attack
+            .launch(&connector, node)
+            .map_err(|err| format!("launch error: {}", err))?;
+        Ok(observe_reaction(&connector, node, Duration::from_secs(10)))
+    }
+}
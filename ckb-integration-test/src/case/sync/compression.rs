@@ -0,0 +1,194 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_types::{packed, prelude::*};
+use ckb_testkit::connector::{
+    build_decompression_bomb, compress_with, message::build_sync_get_headers,
+    message::network_identifier, Compression, ConnectorBuilder, SharedState, SimpleProtocolHandler,
+    SimpleServiceHandler, MAX_UNCOMPRESSED_LEN,
+};
+use ckb_testkit::p2p::bytes::Bytes;
+use ckb_testkit::{assert_result_eq, util::wait_until, SupportProtocols};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+/// Send a `GetHeaders` frame with edge-case compression under Sync protocol.
+///
+/// CKB full node should answer the well-formed frames, compressed or not, and drop or ban the
+/// peer sending malformed ones, without allocating more than `MAX_UNCOMPRESSED_LEN`.
+///
+/// ## Cases
+///
+/// ```text
+/// ┌───┬──────────────────────────────────────────────┬─────────────────┐
+/// │id │ frame                                        │ expected        │
+/// ├───┼──────────────────────────────────────────────┼─────────────────┤
+/// │0  │ uncompressed flag, GetHeaders                │ Ok              │
+/// ├───┼──────────────────────────────────────────────┼─────────────────┤
+/// │1  │ compressed GetHeaders, below the threshold   │ Ok              │
+/// ├───┼──────────────────────────────────────────────┼─────────────────┤
+/// │2  │ compress flag over uncompressed GetHeaders   │ Err(dropped)    │
+/// ├───┼──────────────────────────────────────────────┼─────────────────┤
+/// │3  │ corrupted snappy body of GetHeaders          │ Err(dropped)    │
+/// ├───┼──────────────────────────────────────────────┼─────────────────┤
+/// │4  │ snappy zeros, MAX_UNCOMPRESSED_LEN + 1 bytes │ Err(dropped)    │
+/// ├───┼──────────────────────────────────────────────┼─────────────────┤
+/// │5  │ snappy zeros, MAX_UNCOMPRESSED_LEN bytes     │ Err(banned)     │
+/// └───┴──────────────────────────────────────────────┴─────────────────┘
+/// ```
+///
+/// ## Note
+///
+/// Case 5 decompresses successfully, but the zeros are a malformed `SyncMessage`.
+pub struct SyncCompression;
+
+impl ParameterizedCase for SyncCompression {
+    type Params = CaseParams;
+    type Context = ();
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn prepare(&self, nodes: &Nodes) {
+        nodes.get_node("node2021").mine(1);
+    }
+
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                frame: Frame::GetHeaders(Compression::Never),
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 1,
+                frame: Frame::GetHeaders(Compression::Always),
+                expected_result: Ok(()),
+            },
+            CaseParams {
+                id: 2,
+                frame: Frame::GetHeaders(Compression::FlagOnly),
+                expected_result: Err("dropped".to_string()),
+            },
+            CaseParams {
+                id: 3,
+                frame: Frame::GetHeaders(Compression::Corrupted),
+                expected_result: Err("dropped".to_string()),
+            },
+            CaseParams {
+                id: 4,
+                frame: Frame::DecompressionBomb(MAX_UNCOMPRESSED_LEN + 1),
+                expected_result: Err("dropped".to_string()),
+            },
+            CaseParams {
+                id: 5,
+                frame: Frame::DecompressionBomb(MAX_UNCOMPRESSED_LEN),
+                expected_result: Err("banned".to_string()),
+            },
+        ]
+    }
+
+    fn run_one(&self, _context: &(), nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let frame = case.frame.build(node);
+        let actual_result = self.run_case(node, &frame);
+        assert_result_eq!(
+            case.expected_result,
+            actual_result,
+            "case.id={}, frame_len={}, node.log=\"{}\"",
+            case.id,
+            frame.len(),
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    frame: Frame,
+    expected_result: Result<(), String>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Frame {
+    /// `GetHeaders` from the genesis, compressed as specified
+    GetHeaders(Compression),
+    /// Snappy-compressed zeros of the specified uncompressed length
+    DecompressionBomb(usize),
+}
+
+impl Frame {
+    fn build(&self, node: &Node) -> Bytes {
+        match *self {
+            Frame::GetHeaders(compression) => {
+                let get_headers =
+                    build_sync_get_headers(vec![node.genesis_block().hash()], Default::default())
+                        .as_bytes();
+                compress_with(get_headers, compression)
+            }
+            Frame::DecompressionBomb(len) => build_decompression_bomb(len),
+        }
+    }
+}
+
+impl SyncCompression {
+    fn run_case(&self, node: &Node, frame: &Bytes) -> Result<(), String> {
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut connector = ConnectorBuilder::new()
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build_raw()
+            })
+            .protocol_meta({
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Identify)
+                    .build(false)
+            })
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        connector.connect(node)?;
+        let observed_address = connector
+            .get_session(node)
+            .ok_or_else(|| "disconnected before sending".to_string())?
+            .address;
+        connector.send_identify_message(
+            node,
+            &network_identifier(node),
+            &node.rpc_client().local_node_info().version,
+            vec![],
+            observed_address,
+        )?;
+        connector.send_frame(node, SupportProtocols::Sync, frame.clone())?;
+
+        // The node answers `GetHeaders` with `SendHeaders`, or `InIBD` in IBD
+        let answered = connector.expect_message(
+            node,
+            &SupportProtocols::Sync,
+            Duration::from_secs(5),
+            |message: &packed::SyncMessageUnion| {
+                matches!(
+                    message,
+                    packed::SyncMessageUnion::SendHeaders(_) | packed::SyncMessageUnion::InIBD(_)
+                )
+            },
+        );
+
+        if node.wait_banned(&connector, Duration::from_secs(5)).is_ok() {
+            return Err("banned".to_string());
+        }
+        let dropped = wait_until(5, || connector.get_session(node).is_none());
+        if dropped {
+            return Err("dropped".to_string());
+        }
+        answered.map(|_| ())
+    }
+}
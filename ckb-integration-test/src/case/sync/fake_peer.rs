@@ -0,0 +1,107 @@
+use crate::prelude::*;
+use ckb_testkit::ckb_types::core::BlockNumber;
+use ckb_testkit::util::wait_until;
+use ckb_testkit::FakeSyncPeerBuilder;
+use std::thread::sleep;
+use std::time::Duration;
+
+const CHAIN_LENGTH: BlockNumber = 20;
+
+#[derive(Debug)]
+struct CaseParams {
+    id: usize,
+    withheld_block: Option<BlockNumber>,
+    expected_tip: BlockNumber,
+}
+
+/// ## Note
+///
+/// A `FakeSyncPeer` serves a chain of height `20` to the node, which shares the first block
+/// with it, then announces the headers. The node should download the blocks from the fake peer
+/// via the header-first sync.
+///
+/// ```text
+/// ┌────┬────────────────┬──────────────────┐
+/// │ id │ withheld block │ node's final tip │
+/// ├────┼────────────────┼──────────────────┤
+/// │ 0  │ -              │ 20               │
+/// ├────┼────────────────┼──────────────────┤
+/// │ 1  │ 10             │ 9                │
+/// └────┴────────────────┴──────────────────┘
+/// ```
+pub struct SyncFromFakePeer;
+
+impl Case for SyncFromFakePeer {
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn run(&self, nodes: Nodes) {
+        let node2021 = nodes.get_node("node2021");
+
+        // Mine a recent block so that the nodes are out of IBD
+        node2021.mine(1);
+        let template_node = node2021.clone_node("template-node2021");
+        node2021.mine_to(CHAIN_LENGTH);
+
+        for case in self.cases_params() {
+            let node = template_node.clone_node(&format!("case-{}-node2021", case.id));
+            let mut builder = FakeSyncPeerBuilder::from_node(node2021);
+            if let Some(withheld_block) = case.withheld_block {
+                builder = builder.withhold_block(withheld_block);
+            }
+            let mut peer = builder.build();
+            peer.connect(&node).expect("connect fake peer");
+            peer.announce(&node).expect("announce headers");
+
+            let reached = wait_until(30, || node.get_tip_block_number() >= case.expected_tip);
+            assert!(
+                reached,
+                "case.id: {}, node's tip: {}, expected: {}, node.log: {}",
+                case.id,
+                node.get_tip_block_number(),
+                case.expected_tip,
+                node.log_path().to_string_lossy()
+            );
+
+            // Make sure the node does not grow further
+            sleep(Duration::from_secs(5));
+            assert_eq!(
+                case.expected_tip,
+                node.get_tip_block_number(),
+                "case.id: {}, fake peer stats: {:?}, node.log: {}",
+                case.id,
+                peer.stats(),
+                node.log_path().to_string_lossy()
+            );
+        }
+    }
+}
+
+impl SyncFromFakePeer {
+    fn cases_params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                withheld_block: None,
+                expected_tip: CHAIN_LENGTH,
+            },
+            CaseParams {
+                id: 1,
+                withheld_block: Some(10),
+                expected_tip: 9,
+            },
+        ]
+    }
+}
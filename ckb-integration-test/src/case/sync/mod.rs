@@ -0,0 +1,3 @@
+pub(super) mod attack;
+pub(super) mod compression;
+pub(super) mod fake_peer;
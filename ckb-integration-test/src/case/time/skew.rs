@@ -0,0 +1,129 @@
+use crate::prelude::*;
+use ckb_testkit::connector::{ConnectorBuilder, SharedState, SimpleServiceHandler};
+use ckb_testkit::util::{find_available_port, wait_until};
+use ckb_testkit::SupportProtocols;
+use std::fs;
+use std::sync::{Arc, RwLock};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// https://github.com/nervosnetwork/ckb/blob/v0.101.0/sync/src/net_time_checker.rs
+const MAX_SAMPLES: usize = 11;
+const WARNING: &str = "Please check your computer's local clock";
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    offset_millis: i64,
+    expected_warning: bool,
+}
+
+/// ## Note
+///
+/// The node samples the time messages of its outbound peers, and warns when the median offset
+/// from its local clock exceeds 2 hours. A connector makes the node dial it, then sends time
+/// messages with the given offset.
+///
+/// ```text
+/// ┌────┬──────────┬───────────────────┐
+/// │ id │ offset   │ warns local clock │
+/// ├────┼──────────┼───────────────────┤
+/// │ 0  │ 0        │ no                │
+/// ├────┼──────────┼───────────────────┤
+/// │ 1  │ +3 hours │ yes               │
+/// ├────┼──────────┼───────────────────┤
+/// │ 2  │ -3 hours │ yes               │
+/// └────┴──────────┴───────────────────┘
+/// ```
+pub struct TimeSkew;
+
+impl ParameterizedCase for TimeSkew {
+    type Params = CaseParams;
+    type Context = ();
+
+    fn case_options(&self) -> CaseOptions {
+        CaseOptions {
+            make_all_nodes_connected: false,
+            make_all_nodes_synced: false,
+            make_all_nodes_connected_and_synced: false,
+            node_options: vec![NodeOptions {
+                node_name: String::from("node2021"),
+                ckb_binary: CKB2021.to_string(),
+                initial_database: "testdata/db/empty",
+                chain_spec: "testdata/spec/ckb2021",
+                app_config: "testdata/config/ckb2021",
+            }],
+        }
+    }
+
+    fn prepare(&self, _nodes: &Nodes) {}
+
+    fn params(&self) -> Vec<CaseParams> {
+        const HOUR: i64 = 60 * 60 * 1000;
+        vec![
+            CaseParams {
+                id: 0,
+                offset_millis: 0,
+                expected_warning: false,
+            },
+            CaseParams {
+                id: 1,
+                offset_millis: 3 * HOUR,
+                expected_warning: true,
+            },
+            CaseParams {
+                id: 2,
+                offset_millis: -3 * HOUR,
+                expected_warning: true,
+            },
+        ]
+    }
+
+    fn run_one(&self, _context: &(), nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let node = nodes.get_node("node2021");
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let listening_address = format!("/ip4/127.0.0.1/tcp/{}", find_available_port())
+            .parse()
+            .unwrap();
+        let mut connector = ConnectorBuilder::new()
+            .listening_addresses(vec![listening_address])
+            .simple_protocols(
+                &shared,
+                &[
+                    SupportProtocols::Sync,
+                    SupportProtocols::Identify,
+                    SupportProtocols::Time,
+                ],
+            )
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        connector.accept_from(node)?;
+
+        for _ in 0..MAX_SAMPLES {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time")
+                .as_millis() as i64;
+            connector.send_time_message(node, (now + case.offset_millis) as u64)?;
+        }
+
+        let warned = || {
+            fs::read_to_string(node.log_path())
+                .map(|log| log.contains(WARNING))
+                .unwrap_or(false)
+        };
+        let actual_warning = if case.expected_warning {
+            wait_until(10, warned)
+        } else {
+            sleep(Duration::from_secs(3));
+            warned()
+        };
+        assert_eq!(
+            case.expected_warning,
+            actual_warning,
+            "case.id: {}, node.log: {}",
+            case.id,
+            node.log_path().to_string_lossy()
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,198 @@
+use crate::case::rfc0035::{requirements, HARDFORK_DELAY_WINDOW, RFC0035_BLOCK_NUMBER};
+use crate::prelude::*;
+use ckb_testkit::ckb_types::core::{BlockNumber, BlockView};
+use ckb_testkit::UpgradeOptions;
+
+/// Upgrade a ckb2019 node holding the Epoch2 testdata to ckb2021 at different heights before
+/// the fork2021, which activates at epoch `3`, i.e. height `3000`. The node should keep its
+/// chain, report the hardfork features of the new chain spec, mine across the activation, and
+/// stay in sync with a native ckb2021 node.
+///
+/// ## Cases
+///
+/// ```text
+/// ┌───┬─────────────────────────────────────┬───────────┬──────────────────────────┐
+/// │id │ upgrade height                      │ downgrade │ result                   │
+/// ├───┼─────────────────────────────────────┼───────────┼──────────────────────────┤
+/// │0  │ tip of Epoch2V1TestData             │ no        │ Ok(())                   │
+/// │1  │ 3000 - HARDFORK_DELAY_WINDOW - 1    │ no        │ Ok(())                   │
+/// │2  │ 2999, the last block of epoch 2     │ no        │ Ok(())                   │
+/// │3  │ 3000 - HARDFORK_DELAY_WINDOW - 1    │ yes       │ ckb2019 refuses, Ok(())  │
+/// └───┴─────────────────────────────────────┴───────────┴──────────────────────────┘
+/// ```
+///
+/// ## Note
+///
+/// Case 3 downgrades the upgraded node back to ckb2019 before the activation. ckb2019 cannot
+/// migrate the database back, and should refuse to open the one migrated by ckb2021 rather
+/// than run on it. The node is then upgraded to ckb2021 again, without migrating, and should
+/// keep its chain.
+pub struct UpgradeAcrossFork2021;
+
+impl ParameterizedCase for UpgradeAcrossFork2021 {
+    type Params = CaseParams;
+    type Context = ();
+
+    fn case_options(&self) -> CaseOptions {
+        Default::default()
+    }
+
+    // `ckb migrate` is introduced in v0.100.0
+    fn requires(&self) -> Requirements {
+        requirements(&self.case_options())
+            .testdata("Epoch2V1TestData")
+            .testdata("Epoch2V2TestData")
+    }
+
+    // Every row sets up its own nodes, see `setup_node`
+    fn prepare(&self, _nodes: &Nodes) {}
+
+    fn params(&self) -> Vec<CaseParams> {
+        vec![
+            CaseParams {
+                id: 0,
+                upgrade_height: None,
+                downgrade: false,
+            },
+            CaseParams {
+                id: 1,
+                upgrade_height: Some(RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1),
+                downgrade: false,
+            },
+            CaseParams {
+                id: 2,
+                upgrade_height: Some(RFC0035_BLOCK_NUMBER - 1),
+                downgrade: false,
+            },
+            CaseParams {
+                id: 3,
+                upgrade_height: Some(RFC0035_BLOCK_NUMBER - HARDFORK_DELAY_WINDOW - 1),
+                downgrade: true,
+            },
+        ]
+    }
+
+    fn run_one(&self, _context: &(), _nodes: &Nodes, case: &CaseParams) -> Result<(), String> {
+        let mut node = self.setup_node(
+            format!("case-{}-upgraded", case.id),
+            CKB2019,
+            "testdata/db/Epoch2V1TestData",
+            "testdata/spec/ckb2019",
+            "testdata/config/ckb2019",
+        );
+        if let Some(upgrade_height) = case.upgrade_height {
+            node.mine_to(upgrade_height);
+        }
+        let tip_block = node.get_tip_block();
+
+        node.upgrade_binary(
+            CKB2021,
+            UpgradeOptions {
+                chain_spec: Some("testdata/spec/ckb2021"),
+                app_config: Some("testdata/config/ckb2021"),
+                migrate: true,
+            },
+        );
+        check_tip_unchanged(&node, &tip_block)?;
+
+        if case.downgrade {
+            let downgraded = node.try_upgrade_binary(
+                CKB2019,
+                UpgradeOptions {
+                    chain_spec: Some("testdata/spec/ckb2019"),
+                    app_config: Some("testdata/config/ckb2019"),
+                    migrate: false,
+                },
+            );
+            if downgraded.is_ok() {
+                return Err(format!(
+                    "expected ckb2019 to refuse the database migrated by ckb2021, node.log: {}",
+                    node.log_path().to_string_lossy()
+                ));
+            }
+            node.try_upgrade_binary(
+                CKB2021,
+                UpgradeOptions {
+                    chain_spec: Some("testdata/spec/ckb2021"),
+                    app_config: Some("testdata/config/ckb2021"),
+                    migrate: false,
+                },
+            )
+            .map_err(|err| format!("upgrade again after the refused downgrade, {}", err))?;
+            check_tip_unchanged(&node, &tip_block)?;
+        }
+        let rfc0032_epoch = node
+            .consensus()
+            .hardfork_features
+            .iter()
+            .find(|feature| feature.rfc == "0032")
+            .and_then(|feature| feature.epoch_number.as_ref())
+            .map(|epoch_number| epoch_number.value());
+        if rfc0032_epoch != Some(3) {
+            return Err(format!(
+                "expected rfc0032 activated at epoch 3 after upgrading, got {:?}",
+                rfc0032_epoch
+            ));
+        }
+
+        node.mine_to(RFC0035_BLOCK_NUMBER + HARDFORK_DELAY_WINDOW + 1);
+        let node2021 = self.setup_node(
+            format!("case-{}-node2021", case.id),
+            CKB2021,
+            "testdata/db/Epoch2V2TestData",
+            "testdata/spec/ckb2021",
+            "testdata/config/ckb2021",
+        );
+        node2021.p2p_connect(&node);
+        let nodes: Nodes = vec![node, node2021].into();
+        nodes
+            .waiting_for_sync()
+            .map_err(|unsynced| format!("waiting for sync, tips: {:?}", unsynced))
+    }
+}
+
+#[derive(Debug)]
+pub struct CaseParams {
+    id: usize,
+    // Mine the ckb2019 node to this height before upgrading. `None` means the tip of the
+    // testdata.
+    upgrade_height: Option<BlockNumber>,
+    // Downgrade to ckb2019 after upgrading, then upgrade to ckb2021 again
+    downgrade: bool,
+}
+
+impl UpgradeAcrossFork2021 {
+    fn setup_node(
+        &self,
+        node_name: String,
+        ckb_binary: &str,
+        initial_database: &'static str,
+        chain_spec: &'static str,
+        app_config: &'static str,
+    ) -> Node {
+        let node_options = NodeOptions {
+            node_name,
+            ckb_binary: ckb_binary.to_string(),
+            initial_database,
+            chain_spec,
+            app_config,
+        };
+        let mut node = Node::init(self.case_name(), node_options);
+        node.start();
+        node
+    }
+}
+
+fn check_tip_unchanged(node: &Node, tip_block: &BlockView) -> Result<(), String> {
+    let tip_after = node.get_tip_block();
+    if tip_after.hash() != tip_block.hash() {
+        return Err(format!(
+            "the chain changes after upgrading, tip before: {} {:#x}, tip after: {} {:#x}",
+            tip_block.number(),
+            tip_block.hash(),
+            tip_after.number(),
+            tip_after.hash(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1 @@
+pub(super) mod fork2021;
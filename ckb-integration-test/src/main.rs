@@ -1,35 +1,109 @@
 pub mod case;
 pub mod prelude;
+pub mod report;
 pub mod testdata;
 pub mod util;
 
 use clap::{value_t_or_exit, App, Arg, ArgMatches, SubCommand};
-use lazy_static::lazy_static;
+use std::collections::BTreeMap;
 use std::env;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process::exit;
-use std::sync::RwLock;
+use std::time::Duration;
 
-// TODO Create a shortcut for CKB2019/CKB2021
-lazy_static! {
-    pub static ref CKB2019: RwLock<PathBuf> = RwLock::new(PathBuf::new());
-    pub static ref CKB2021: RwLock<PathBuf> = RwLock::new(PathBuf::new());
-}
+/// Name of the ckb binary predating the fork2021, registered by `--ckb2019`, defaults to the
+/// built-in ckb_v0.43.2 binary
+pub const CKB2019: &str = "ckb2019";
+/// Name of the ckb binary activating the fork2021, registered by `--ckb2021`
+pub const CKB2021: &str = "ckb2021";
 
-fn filter_cases(arg_matches: &ArgMatches) -> Vec<Box<dyn case::Case>> {
+// A case name may be followed by the indexes of the rows to run, like "RFC0029[1,15]", see
+// `ParameterizedCase`. `None` means all rows.
+fn filter_cases(arg_matches: &ArgMatches) -> Vec<(Box<dyn case::Case>, Option<Vec<usize>>)> {
     if let Some(filtering_cases) = arg_matches.values_of("cases") {
-        filtering_cases
-            .filter_map(|case_name| {
-                for case in crate::case::all_cases() {
-                    if case.case_name() == case_name {
-                        return Some(case);
+        let mut selected: Vec<(Box<dyn case::Case>, Option<Vec<usize>>)> = Vec::new();
+        for filtering_case in filtering_cases {
+            let (case_name, rows) = parse_case_selector(filtering_case);
+            let case = crate::case::all_cases()
+                .into_iter()
+                .find(|case| case.case_name() == case_name)
+                .unwrap_or_else(|| panic!("unknown case \"{}\"", case_name));
+            if let Some(rows) = rows.as_ref() {
+                let row_count = case.row_count();
+                if let Some(row) = rows.iter().find(|row| **row >= row_count) {
+                    panic!(
+                        "case \"{}\" has {} rows, row {} is out of range",
+                        case_name, row_count, row
+                    );
+                }
+            }
+            match selected
+                .iter_mut()
+                .find(|(case, _)| case.case_name() == case_name)
+            {
+                // Selecting all rows of a case overrides selecting some of them
+                Some((_, selected_rows)) => {
+                    *selected_rows = match (selected_rows.take(), rows) {
+                        (Some(mut selected_rows), Some(rows)) => {
+                            for row in rows {
+                                if !selected_rows.contains(&row) {
+                                    selected_rows.push(row);
+                                }
+                            }
+                            Some(selected_rows)
+                        }
+                        _ => None,
+                    };
+                }
+                None => selected.push((case, rows)),
+            }
+        }
+        selected
+    } else {
+        crate::case::all_cases()
+            .into_iter()
+            .map(|case| (case, None))
+            .collect()
+    }
+}
+
+fn parse_case_selector(selector: &str) -> (&str, Option<Vec<usize>>) {
+    match selector.strip_suffix(']').and_then(|s| s.split_once('[')) {
+        Some((case_name, rows)) => {
+            let rows = rows
+                .split(',')
+                .map(|row| {
+                    row.trim().parse::<usize>().unwrap_or_else(|err| {
+                        panic!(
+                            "invalid row \"{}\" in \"{}\", error: {}",
+                            row, selector, err
+                        )
+                    })
+                })
+                .collect();
+            (case_name, Some(rows))
+        }
+        None => (selector, None),
+    }
+}
+
+fn filter_testdata_generators(arg_matches: &ArgMatches) -> Vec<Box<dyn testdata::Testdata>> {
+    if let Some(filtering_testdata) = arg_matches.values_of("only") {
+        filtering_testdata
+            .map(|testdata_name| {
+                for generator in crate::testdata::all_testdata_generators() {
+                    if generator.testdata_name() == testdata_name
+                        || generator.outputs().contains(&testdata_name)
+                    {
+                        return generator;
                     }
                 }
-                panic!("unknown case \"{}\"", case_name);
+                panic!("unknown testdata \"{}\"", testdata_name);
             })
             .collect()
     } else {
-        crate::case::all_cases()
+        crate::testdata::all_testdata_generators()
     }
 }
 
@@ -41,16 +115,47 @@ fn main() {
     match matches.subcommand() {
         ("run", Some(arg_matches)) => {
             crate::init_ckb_binaries(&arg_matches);
-            for case in filter_cases(&arg_matches) {
-                crate::case::run_case(case);
+            crate::testdata::allow_missing_manifest(
+                arg_matches.is_present("allow-missing-manifest"),
+            );
+            let case_timeout =
+                Duration::from_secs(value_t_or_exit!(arg_matches, "case-timeout", u64));
+            let row_jobs = value_t_or_exit!(arg_matches, "row-jobs", usize);
+            let mut results = Vec::new();
+            for (case, rows) in filter_cases(&arg_matches) {
+                results.extend(crate::case::run_case(case, rows, case_timeout, row_jobs));
+            }
+            crate::report::print_summary(&results);
+            if let Some(path) = arg_matches.value_of("junit-report") {
+                crate::report::write_junit_report(path.as_ref(), &results)
+                    .unwrap_or_else(|err| panic!("failed to write JUnit report, error: {}", err));
+            }
+            if let Some(path) = arg_matches.value_of("json-report") {
+                crate::report::write_json_report(path.as_ref(), &results)
+                    .unwrap_or_else(|err| panic!("failed to write JSON report, error: {}", err));
+            }
+            if results.iter().any(|result| result.is_failed()) {
+                exit(1);
+            }
+            if arg_matches.is_present("fail-on-skip")
+                && results
+                    .iter()
+                    .any(|result| result.outcome == crate::report::CaseOutcome::Skipped)
+            {
+                exit(1);
             }
         }
         ("generate-testdata", Some(arg_matches)) => {
             crate::init_ckb_binaries(&arg_matches);
-            let testdatas = crate::testdata::all_testdata_generators();
-            for testdata in testdatas {
-                testdata.generate();
-            }
+            let generators = filter_testdata_generators(&arg_matches);
+            let force = arg_matches.is_present("force");
+            crate::testdata::generate_testdata(generators, force);
+        }
+        ("print-capture", Some(arg_matches)) => {
+            crate::print_capture(&arg_matches);
+        }
+        ("replay", Some(arg_matches)) => {
+            crate::replay_capture(&arg_matches);
         }
         _ => {
             println!("invalid usage");
@@ -82,59 +187,169 @@ fn clap_app() -> App<'static, 'static> {
         .subcommand(
             SubCommand::with_name("run")
                 .about("Run test cases")
+                .args(&ckb_binary_args())
                 .arg(
-                    Arg::with_name("ckb2019")
-                        // hide the help information about `--ckb2019`, we use built-in
-                        // ckb2019 binary, located in testdata/bin/
-                        .hidden(true)
+                    Arg::with_name("cases")
                         .required(false)
-                        .long("ckb2019")
+                        .long("cases")
                         .takes_value(true)
-                        .value_name("PATH")
-                        .help("Path to ckb2019 executable"),
+                        .multiple(true)
+                        .value_name("CASE_NAME")
+                        .help("Only run specified cases. Run all cases if this parameter is not setting. Select rows of a table-driven case like RFC0029[1,15]"),
+                )
+                .arg(
+                    Arg::with_name("row-jobs")
+                        .required(false)
+                        .long("row-jobs")
+                        .takes_value(true)
+                        .value_name("N")
+                        .default_value("1")
+                        .help("Run up to N rows of a table-driven case in parallel"),
+                )
+                .arg(
+                    Arg::with_name("case-timeout")
+                        .required(false)
+                        .long("case-timeout")
+                        .takes_value(true)
+                        .value_name("SECONDS")
+                        .default_value("3600")
+                        .help("Kill the nodes of a case and report it as timed out if it runs longer than this"),
+                )
+                .arg(
+                    Arg::with_name("allow-missing-manifest")
+                        .required(false)
+                        .long("allow-missing-manifest")
+                        .takes_value(false)
+                        .help("Accept the testdata without manifest, which can not be checked for staleness"),
                 )
                 .arg(
-                    Arg::with_name("ckb2021")
-                        .long("ckb2021")
+                    Arg::with_name("fail-on-skip")
+                        .required(false)
+                        .long("fail-on-skip")
+                        .takes_value(false)
+                        .help("Exit with failure if any case is skipped, e.g. as a mis-pointed ckb binary does not meet the version bounds"),
+                )
+                .arg(
+                    Arg::with_name("junit-report")
+                        .required(false)
+                        .long("junit-report")
                         .takes_value(true)
                         .value_name("PATH")
-                        .help("Path to ckb2021 executable"),
+                        .help("Write a JUnit XML report of the case results"),
                 )
                 .arg(
-                    Arg::with_name("cases")
+                    Arg::with_name("json-report")
                         .required(false)
-                        .long("cases")
+                        .long("json-report")
                         .takes_value(true)
-                        .multiple(true)
-                        .value_name("CASE_NAME")
-                        .help("Only run specified cases. Run all cases if this parameter is not setting"),
+                        .value_name("PATH")
+                        .help("Write a JSON report of the case results"),
                 )
         )
         .subcommand(
             SubCommand::with_name("generate-testdata")
                 .about("Run testdata generators")
+                .args(&ckb_binary_args())
                 .arg(
-                    Arg::with_name("ckb2019")
-                        // hide the help information about `--ckb2019`, we use built-in
-                        // ckb2019 binary, located in testdata/bin/
-                        .hidden(true)
+                    Arg::with_name("only")
                         .required(false)
-                        .long("ckb2019")
+                        .long("only")
+                        .takes_value(true)
+                        .multiple(true)
+                        .value_name("TESTDATA_NAME")
+                        .help("Only generate specified testdata. Check all testdata if this parameter is not setting"),
+                )
+                .arg(
+                    Arg::with_name("force")
+                        .required(false)
+                        .long("force")
+                        .takes_value(false)
+                        .help("Regenerate testdata even if they are up to date"),
+                )
+        )
+        .subcommand(
+            SubCommand::with_name("print-capture")
+                .about("Print the frames of a capture file, see ConnectorBuilder::capture_to")
+                .arg(
+                    Arg::with_name("capture")
+                        .required(true)
+                        .long("capture")
                         .takes_value(true)
                         .value_name("PATH")
-                        .help("Path to ckb2019 executable"),
+                        .help("Path to the capture file"),
                 )
+        )
+        .subcommand(
+            SubCommand::with_name("replay")
+                .about("Replay the outbound frames of a captured session to a running node")
                 .arg(
-                    Arg::with_name("ckb2021")
-                        .long("ckb2021")
+                    Arg::with_name("capture")
+                        .required(true)
+                        .long("capture")
                         .takes_value(true)
                         .value_name("PATH")
+                        .help("Path to the capture file"),
+                )
+                .arg(
+                    Arg::with_name("rpc-url")
+                        .required(true)
+                        .long("rpc-url")
+                        .takes_value(true)
+                        .value_name("URL")
+                        .help("RPC url of the target node, e.g. http://127.0.0.1:8114"),
+                )
+                .arg(
+                    Arg::with_name("session")
+                        .required(false)
+                        .long("session")
+                        .takes_value(true)
+                        .value_name("SESSION_ID")
+                        .help("The captured session to replay. Replay the first session which has outbound frames if this parameter is not setting"),
+                )
+                .arg(
+                    Arg::with_name("preserve-timing")
                         .required(false)
-                        .help("Path to ckb2021 executable"),
+                        .long("preserve-timing")
+                        .takes_value(false)
+                        .help("Keep the intervals between the frames as captured"),
                 )
         )
 }
 
+fn ckb_binary_args() -> Vec<Arg<'static, 'static>> {
+    vec![
+        Arg::with_name("ckb2019")
+            // hide the help information about `--ckb2019`, we use built-in
+            // ckb2019 binary, located in testdata/bin/
+            .hidden(true)
+            .required(false)
+            .long("ckb2019")
+            .takes_value(true)
+            .value_name("PATH")
+            .help("Path to ckb2019 executable, a shortcut of `--ckb ckb2019=PATH`"),
+        Arg::with_name("ckb2021")
+            .required(false)
+            .long("ckb2021")
+            .takes_value(true)
+            .value_name("PATH")
+            .help("Path to ckb2021 executable, a shortcut of `--ckb ckb2021=PATH`"),
+        Arg::with_name("ckb")
+            .required(false)
+            .long("ckb")
+            .takes_value(true)
+            .multiple(true)
+            .number_of_values(1)
+            .value_name("NAME=PATH")
+            .help("Register a named ckb executable, like `--ckb v0.100=/path/to/ckb`. Cases running a version matrix run every registered executable"),
+        Arg::with_name("ckb-config")
+            .required(false)
+            .long("ckb-config")
+            .takes_value(true)
+            .value_name("PATH")
+            .help("Register the named ckb executables listed in a TOML file, like `v0_100 = \"/path/to/ckb\"`. Relative paths are relative to the file"),
+    ]
+}
+
 fn init_logger(clap_matches: &ArgMatches) -> ckb_logger_service::LoggerInitGuard {
     let filter = if clap_matches.is_present("debug") {
         "debug"
@@ -153,28 +368,74 @@ fn init_logger(clap_matches: &ArgMatches) -> ckb_logger_service::LoggerInitGuard
         .unwrap_or_else(|err| panic!("failed to init the logger service, error: {}", err))
 }
 
+// Register the ckb binaries listed in `--ckb-config`, then `--ckb`, then the `--ckb2019` and
+// `--ckb2021` shortcuts. A later one overrides the earlier one of the same name.
 fn init_ckb_binaries(matches: &ArgMatches) {
-    let ckb2019 = {
-        if let Some(ckb2019_str) = matches.value_of("ckb2019") {
-            PathBuf::from(ckb2019_str)
-        } else {
-            // Use default ckb_v0.43.2 binary according to the running system
-            match os_info::get().os_type() {
-                os_info::Type::Macos => PathBuf::from("testdata/bin/ckb_v0.43.2-macOS"),
-                os_info::Type::Windows => PathBuf::from("testdata/bin/ckb_v0.43.2-Windows"),
-                _ => PathBuf::from("testdata/bin/ckb_v0.43.2-Linux"),
-            }
+    let mut binaries = BTreeMap::new();
+    if let Some(config) = matches.value_of("ckb-config") {
+        binaries.extend(load_ckb_config(Path::new(config)));
+    }
+    if let Some(values) = matches.values_of("ckb") {
+        for value in values {
+            let (name, path) = value
+                .split_once('=')
+                .unwrap_or_else(|| panic!("invalid --ckb \"{}\", expected NAME=PATH", value));
+            binaries.insert(name.trim().to_string(), PathBuf::from(path.trim()));
         }
-    };
-    let ckb2021 = value_t_or_exit!(matches, "ckb2021", PathBuf);
-    if !ckb2019.exists() || !ckb2019.is_file() {
-        panic!("--ckb2019 points to non-executable")
     }
-    if !ckb2021.exists() || !ckb2021.is_file() {
-        panic!("--ckb2021 points to non-executable")
+    if let Some(path) = matches.value_of("ckb2019") {
+        binaries.insert(CKB2019.to_string(), PathBuf::from(path));
+    }
+    if let Some(path) = matches.value_of("ckb2021") {
+        binaries.insert(CKB2021.to_string(), PathBuf::from(path));
+    }
+    binaries.entry(CKB2019.to_string()).or_insert_with(|| {
+        // Use default ckb_v0.43.2 binary according to the running system
+        match os_info::get().os_type() {
+            os_info::Type::Macos => PathBuf::from("testdata/bin/ckb_v0.43.2-macOS"),
+            os_info::Type::Windows => PathBuf::from("testdata/bin/ckb_v0.43.2-Windows"),
+            _ => PathBuf::from("testdata/bin/ckb_v0.43.2-Linux"),
+        }
+    });
+    if !binaries.contains_key(CKB2021) {
+        panic!("missing ckb2021 executable, specify it by --ckb2021 PATH");
+    }
+
+    for (name, path) in binaries {
+        if !path.is_file() {
+            panic!(
+                "ckb binary \"{}\" points to non-executable \"{}\"",
+                name,
+                path.display()
+            )
+        }
+        let binary = ckb_testkit::register_ckb_binary(&name, absolutize(path))
+            .unwrap_or_else(|err| panic!("failed to register ckb binary, error: {}", err));
+        ckb_testkit::info!(
+            "register ckb binary \"{}\": \"{}\", {}",
+            binary.name,
+            binary.path.display(),
+            binary.version
+        );
     }
-    *CKB2019.write().unwrap() = absolutize(ckb2019);
-    *CKB2021.write().unwrap() = absolutize(ckb2021);
+}
+
+// The file maps names to paths, like
+//
+// ```toml
+// v0_43 = "testdata/bin/ckb_v0.43.2-Linux"
+// v0_100 = "/usr/local/bin/ckb"
+// ```
+fn load_ckb_config(path: &Path) -> BTreeMap<String, PathBuf> {
+    let content = fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("failed to read {}, error: {}", path.display(), err));
+    let binaries: BTreeMap<String, PathBuf> = toml::from_str(&content)
+        .unwrap_or_else(|err| panic!("failed to parse {}, error: {}", path.display(), err));
+    let base_dir = path.parent().unwrap_or_else(|| Path::new(""));
+    binaries
+        .into_iter()
+        .map(|(name, binary_path)| (name, base_dir.join(binary_path)))
+        .collect()
 }
 
 fn absolutize(path: PathBuf) -> PathBuf {
@@ -186,3 +447,36 @@ fn absolutize(path: PathBuf) -> PathBuf {
         path
     }
 }
+
+fn print_capture(matches: &ArgMatches) {
+    let capture = value_t_or_exit!(matches, "capture", PathBuf);
+    let frames = ckb_testkit::connector::read_capture(&capture)
+        .unwrap_or_else(|err| panic!("failed to read capture, error: {}", err));
+    let start_timestamp = frames.first().map(|frame| frame.timestamp).unwrap_or(0);
+    for frame in frames.iter() {
+        println!(
+            "{}",
+            ckb_testkit::connector::pretty_print(frame, start_timestamp)
+        );
+    }
+}
+
+fn replay_capture(matches: &ArgMatches) {
+    let capture = value_t_or_exit!(matches, "capture", PathBuf);
+    let rpc_url = value_t_or_exit!(matches, "rpc-url", String);
+    let mut replayer = ckb_testkit::connector::Replayer::from_capture(&capture)
+        .unwrap_or_else(|err| panic!("failed to read capture, error: {}", err))
+        .preserve_timing(matches.is_present("preserve-timing"));
+    if matches.is_present("session") {
+        let session_id = value_t_or_exit!(matches, "session", usize);
+        replayer = replayer.session(session_id.into());
+    }
+    let node = ckb_testkit::Node::init_from_url(&rpc_url, PathBuf::new());
+    let connector = replayer
+        .replay(&node)
+        .unwrap_or_else(|err| panic!("failed to replay, error: {}", err));
+    match connector.wait_session_closed(&node, std::time::Duration::from_secs(5)) {
+        Ok(closed) => println!("node dropped the session: {:?}", closed),
+        Err(_) => println!("node kept the session after replaying"),
+    }
+}
@@ -1,3 +1,3 @@
-pub use crate::case::{Case, CaseOptions};
+pub use crate::case::{skip, Case, CaseOptions, Parameterized, ParameterizedCase, Requirements};
 pub use crate::{CKB2019, CKB2021};
 pub use ckb_testkit::{Node, NodeOptions, Nodes};
@@ -0,0 +1,228 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseOutcome {
+    Passed,
+    /// The case panicked, mostly a failed assertion, or its testdata is missing or stale
+    Failed,
+    /// The case exceeded `--case-timeout` and its nodes were killed
+    TimedOut,
+    /// The ckb binaries or features required by the case are not met, see `Case::requires`,
+    /// or the case called `skip`
+    Skipped,
+}
+
+impl CaseOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CaseOutcome::Passed => "passed",
+            CaseOutcome::Failed => "failed",
+            CaseOutcome::TimedOut => "timed_out",
+            CaseOutcome::Skipped => "skipped",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CaseResult {
+    pub case_name: String,
+    pub outcome: CaseOutcome,
+    pub duration: Duration,
+    /// The panic message, the timeout, or the reason of skipping
+    pub message: Option<String>,
+    /// Log paths of the nodes started by the case
+    pub log_paths: Vec<PathBuf>,
+}
+
+impl CaseResult {
+    pub fn is_passed(&self) -> bool {
+        self.outcome == CaseOutcome::Passed
+    }
+
+    pub fn is_failed(&self) -> bool {
+        self.outcome == CaseOutcome::Failed || self.outcome == CaseOutcome::TimedOut
+    }
+}
+
+fn count(results: &[CaseResult], outcome: CaseOutcome) -> usize {
+    results
+        .iter()
+        .filter(|result| result.outcome == outcome)
+        .count()
+}
+
+fn total_duration(results: &[CaseResult]) -> Duration {
+    results.iter().map(|result| result.duration).sum()
+}
+
+pub fn print_summary(results: &[CaseResult]) {
+    let name_width = results
+        .iter()
+        .map(|result| result.case_name.len())
+        .chain(::std::iter::once("CASE".len()))
+        .max()
+        .unwrap_or_default();
+    println!();
+    println!(
+        "{:<width$}  {:<9}  DURATION",
+        "CASE",
+        "RESULT",
+        width = name_width
+    );
+    for result in results.iter() {
+        println!(
+            "{:<width$}  {:<9}  {:.1}s",
+            result.case_name,
+            result.outcome.as_str(),
+            result.duration.as_secs_f64(),
+            width = name_width
+        );
+    }
+    println!(
+        "\npassed: {}, failed: {}, timed_out: {}, skipped: {}, duration: {:.1}s",
+        count(results, CaseOutcome::Passed),
+        count(results, CaseOutcome::Failed),
+        count(results, CaseOutcome::TimedOut),
+        count(results, CaseOutcome::Skipped),
+        total_duration(results).as_secs_f64(),
+    );
+    for result in results.iter().filter(|result| !result.is_passed()) {
+        println!(
+            "\n{} {}: {}",
+            result.case_name,
+            result.outcome.as_str(),
+            result.message.as_deref().unwrap_or_default()
+        );
+        for log_path in result.log_paths.iter() {
+            println!("    node.log=\"{}\"", log_path.display());
+        }
+    }
+}
+
+/// Write the results as a JUnit XML report. Failed cases are reported as `<failure>`,
+/// timed-out cases as `<error>` and skipped cases as `<skipped>`.
+pub fn write_junit_report(path: &Path, results: &[CaseResult]) -> Result<(), String> {
+    let mut xml = String::new();
+    let _ = writeln!(xml, r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    let _ = writeln!(
+        xml,
+        r#"<testsuites tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+        results.len(),
+        count(results, CaseOutcome::Failed),
+        count(results, CaseOutcome::TimedOut),
+        count(results, CaseOutcome::Skipped),
+        total_duration(results).as_secs_f64(),
+    );
+    let _ = writeln!(
+        xml,
+        r#"  <testsuite name="ckb-integration-test" tests="{}" failures="{}" errors="{}" skipped="{}" time="{:.3}">"#,
+        results.len(),
+        count(results, CaseOutcome::Failed),
+        count(results, CaseOutcome::TimedOut),
+        count(results, CaseOutcome::Skipped),
+        total_duration(results).as_secs_f64(),
+    );
+    for result in results.iter() {
+        let _ = writeln!(
+            xml,
+            r#"    <testcase name="{}" classname="ckb-integration-test.{}" time="{:.3}">"#,
+            escape_xml(&result.case_name),
+            // Group the rows of a table-driven case, like "RFC0029[15]", under the case
+            escape_xml(result.case_name.split('[').next().unwrap_or_default()),
+            result.duration.as_secs_f64(),
+        );
+        let message = result.message.as_deref().unwrap_or_default();
+        match result.outcome {
+            CaseOutcome::Passed => {}
+            CaseOutcome::Failed => {
+                let _ = writeln!(
+                    xml,
+                    r#"      <failure type="panic" message="{}">{}</failure>"#,
+                    escape_xml(message),
+                    escape_xml(message),
+                );
+            }
+            CaseOutcome::TimedOut => {
+                let _ = writeln!(
+                    xml,
+                    r#"      <error type="timeout" message="{}">{}</error>"#,
+                    escape_xml(message),
+                    escape_xml(message),
+                );
+            }
+            CaseOutcome::Skipped => {
+                let _ = writeln!(xml, r#"      <skipped message="{}"/>"#, escape_xml(message));
+            }
+        }
+        if !result.log_paths.is_empty() {
+            let log_paths = result
+                .log_paths
+                .iter()
+                .map(|log_path| format!("node.log=\"{}\"", log_path.display()))
+                .collect::<Vec<_>>()
+                .join("\n");
+            let _ = writeln!(
+                xml,
+                "      <system-out>{}</system-out>",
+                escape_xml(&log_paths)
+            );
+        }
+        let _ = writeln!(xml, "    </testcase>");
+    }
+    let _ = writeln!(xml, "  </testsuite>");
+    let _ = writeln!(xml, "</testsuites>");
+    fs::write(path, xml)
+        .map_err(|err| format!("failed to write \"{}\", error: {}", path.display(), err))
+}
+
+/// Write the results as a JSON report.
+pub fn write_json_report(path: &Path, results: &[CaseResult]) -> Result<(), String> {
+    let cases = results
+        .iter()
+        .map(|result| {
+            serde_json::json!({
+                "name": result.case_name,
+                "outcome": result.outcome.as_str(),
+                "duration_secs": result.duration.as_secs_f64(),
+                "message": result.message,
+                "log_paths": result
+                    .log_paths
+                    .iter()
+                    .map(|log_path| log_path.to_string_lossy().to_string())
+                    .collect::<Vec<_>>(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let report = serde_json::json!({
+        "passed": count(results, CaseOutcome::Passed),
+        "failed": count(results, CaseOutcome::Failed),
+        "timed_out": count(results, CaseOutcome::TimedOut),
+        "skipped": count(results, CaseOutcome::Skipped),
+        "duration_secs": total_duration(results).as_secs_f64(),
+        "cases": cases,
+    });
+    let json = serde_json::to_string_pretty(&report)
+        .map_err(|err| format!("failed to serialize the report, error: {}", err))?;
+    fs::write(path, json)
+        .map_err(|err| format!("failed to write \"{}\", error: {}", path.display(), err))
+}
+
+fn escape_xml(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab and newlines are invalid in XML 1.0
+            c if c.is_control() && c != '\t' && c != '\n' && c != '\r' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
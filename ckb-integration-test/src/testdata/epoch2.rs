@@ -1,30 +1,46 @@
-use crate::testdata::{dump_testdata, Testdata};
+use crate::testdata::{dump_testdata, Testdata, TestdataInputs, TestdataTarget};
 use crate::{CKB2019, CKB2021};
 use ckb_testkit::{Node, NodeOptions};
 
 pub struct Epoch2TestData;
 
 impl Testdata for Epoch2TestData {
+    fn outputs(&self) -> Vec<&str> {
+        vec!["Epoch2V1TestData", "Epoch2V2TestData"]
+    }
+
+    fn inputs(&self) -> TestdataInputs {
+        TestdataInputs {
+            ckb_binaries: vec![
+                (CKB2019.to_string(), "0.43.2"),
+                (CKB2021.to_string(), "0.100"),
+            ],
+            chain_specs: vec!["testdata/spec/ckb2019", "testdata/spec/ckb2021"],
+            target: TestdataTarget::Epoch(2),
+            build_instructions: vec!["mine via ckb2019 and submit the same blocks to ckb2021"],
+        }
+    }
+
     fn generate(&self) {
         let mut node2019 = {
             let node_options = NodeOptions {
                 node_name: String::from("node2019"),
-                ckb_binary: CKB2019.read().unwrap().clone(),
+                ckb_binary: CKB2019.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2019",
                 app_config: "testdata/config/ckb2019",
             };
-            Node::init("Epoch2V1TestData", node_options, false)
+            Node::init("Epoch2V1TestData", node_options)
         };
         let mut node2021 = {
             let node_options = NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
             };
-            Node::init("Epoch2V2TestData", node_options, true)
+            Node::init("Epoch2V2TestData", node_options)
         };
 
         node2019.start();
@@ -1,24 +1,37 @@
-use crate::testdata::{dump_testdata, Testdata};
+use crate::testdata::{dump_testdata, Testdata, TestdataInputs, TestdataTarget};
 use crate::CKB2021;
-use ckb_testkit::{Node, NodeOptions};
+use ckb_testkit::{BulkMiningOptions, Node, NodeOptions};
 
 pub struct Height100000TestData;
 
 impl Testdata for Height100000TestData {
+    fn outputs(&self) -> Vec<&str> {
+        vec!["Height100000V2TestData"]
+    }
+
+    fn inputs(&self) -> TestdataInputs {
+        TestdataInputs {
+            ckb_binaries: vec![(CKB2021.to_string(), "0.100")],
+            chain_specs: vec!["testdata/spec/ckb2021"],
+            target: TestdataTarget::Height(100000),
+            build_instructions: vec!["bulk mine empty blocks via ckb2021"],
+        }
+    }
+
     fn generate(&self) {
         let mut node2021 = {
             let node_options = NodeOptions {
                 node_name: String::from("node2021"),
-                ckb_binary: CKB2021.read().unwrap().clone(),
+                ckb_binary: CKB2021.to_string(),
                 initial_database: "testdata/db/empty",
                 chain_spec: "testdata/spec/ckb2021",
                 app_config: "testdata/config/ckb2021",
             };
-            Node::init("Height1000002V2TestData", node_options, true)
+            Node::init("Height100000V2TestData", node_options)
         };
 
         node2021.start();
-        node2021.mine_to(100000);
-        dump_testdata(node2021, "Height1000002V2TestData");
+        node2021.bulk_mine_to(100000, BulkMiningOptions::default());
+        dump_testdata(node2021, "Height100000V2TestData");
     }
 }
@@ -1,19 +1,28 @@
-use crate::testdata::{dump_testdata, Testdata};
+use crate::testdata::{dump_testdata, Testdata, TestdataInputs, TestdataTarget};
 use crate::CKB2019;
 use ckb_testkit::{Node, NodeOptions};
 
 pub struct Height13TestData;
 
 impl Testdata for Height13TestData {
+    fn inputs(&self) -> TestdataInputs {
+        TestdataInputs {
+            ckb_binaries: vec![(CKB2019.to_string(), "0.43.2")],
+            chain_specs: vec!["testdata/spec/ckb2019"],
+            target: TestdataTarget::Height(13),
+            build_instructions: vec!["mine via ckb2019"],
+        }
+    }
+
     fn generate(&self) {
         let node_options = NodeOptions {
             node_name: String::from("node2019"),
-            ckb_binary: CKB2019.read().unwrap().clone(),
+            ckb_binary: CKB2019.to_string(),
             initial_database: "testdata/db/empty",
             chain_spec: "testdata/spec/ckb2019",
             app_config: "testdata/config/ckb2019",
         };
-        let mut node = Node::init(self.testdata_name(), node_options, false);
+        let mut node = Node::init(self.testdata_name(), node_options);
         node.start();
         node.mine(13);
         dump_testdata(node, self.testdata_name());
@@ -0,0 +1,187 @@
+use ckb_testkit::ckb_hash::blake2b_256;
+use ckb_testkit::ckb_types::core::{BlockNumber, EpochNumber};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The height which the generated chain reaches.
+#[derive(Debug, Clone, Copy)]
+pub enum TestdataTarget {
+    Height(BlockNumber),
+    Epoch(EpochNumber),
+}
+
+impl fmt::Display for TestdataTarget {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TestdataTarget::Height(number) => write!(f, "height {}", number),
+            TestdataTarget::Epoch(number) => write!(f, "epoch {}", number),
+        }
+    }
+}
+
+/// Everything that determines the content of a generated testdata. When any of them changes,
+/// the testdata is considered stale and should be regenerated.
+#[derive(Debug, Clone)]
+pub struct TestdataInputs {
+    /// Names of the ckb binaries used to produce the chain, with the version they are required
+    /// to be of, like `(CKB2019, "0.43.2")`. A binary is of the version if its version number
+    /// starts with it, so "0.100" accepts "0.100.0".
+    pub ckb_binaries: Vec<(String, &'static str)>,
+    /// Chain spec directories, identified by their file contents
+    pub chain_specs: Vec<&'static str>,
+    pub target: TestdataTarget,
+    /// Human-readable build instructions. Change them whenever the generating procedure changes.
+    pub build_instructions: Vec<&'static str>,
+}
+
+impl TestdataInputs {
+    /// Return an error if a required ckb binary is unregistered or of another version.
+    pub fn check_ckb_binaries(&self) -> Result<(), String> {
+        for (name, version) in self.ckb_binaries.iter() {
+            let binary = ckb_testkit::ckb_binary(name)
+                .ok_or_else(|| format!("ckb binary \"{}\" is not registered", name))?;
+            if !binary.version_number().starts_with(version) {
+                return Err(format!(
+                    "requires ckb binary \"{}\" of version {}, got \"{}\"",
+                    name, version, binary.version
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The manifest written alongside generated testdata, e.g.
+///
+/// ```text
+/// hash = 0x...
+/// ckb_binary = ckb2019 0.43.2
+/// chain_spec = testdata/spec/ckb2019 0x...
+/// target = height 13
+/// build_instruction = mine 13 blocks
+/// ckb_version = ckb2019 ckb 0.43.2 (...)
+/// ```
+///
+/// `hash` covers the declared inputs only. `ckb_version` records what the binaries reported
+/// when the testdata was generated, so a patch release of ckb does not make the testdata stale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestdataManifest {
+    pub hash: String,
+    pub entries: Vec<(String, String)>,
+}
+
+impl TestdataManifest {
+    pub fn from_inputs(inputs: &TestdataInputs) -> Self {
+        let mut entries = Vec::new();
+        for (name, version) in inputs.ckb_binaries.iter() {
+            entries.push(("ckb_binary".to_string(), format!("{} {}", name, version)));
+        }
+        for chain_spec in inputs.chain_specs.iter() {
+            entries.push((
+                "chain_spec".to_string(),
+                format!("{} {}", chain_spec, hash_dir(Path::new(chain_spec))),
+            ));
+        }
+        entries.push(("target".to_string(), inputs.target.to_string()));
+        for build_instruction in inputs.build_instructions.iter() {
+            entries.push((
+                "build_instruction".to_string(),
+                build_instruction.to_string(),
+            ));
+        }
+
+        let digest = entries
+            .iter()
+            .map(|(key, value)| format!("{} = {}\n", key, value))
+            .collect::<String>();
+        let hash = format!("0x{}", to_hex(&blake2b_256(digest.as_bytes())));
+        Self { hash, entries }
+    }
+
+    /// Record the `--version` output of the ckb binaries that generated the testdata.
+    pub fn record_ckb_versions(&mut self, inputs: &TestdataInputs) {
+        for (name, _) in inputs.ckb_binaries.iter() {
+            let binary = ckb_testkit::ckb_binary(name)
+                .unwrap_or_else(|| panic!("ckb binary \"{}\" is not registered", name));
+            self.entries.push((
+                "ckb_version".to_string(),
+                format!("{} {}", name, binary.version),
+            ));
+        }
+    }
+
+    pub fn load(path: &Path) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        let mut hash = None;
+        let mut entries = Vec::new();
+        for line in content.lines() {
+            let mut splited = line.splitn(2, " = ");
+            let key = splited.next()?.to_string();
+            let value = splited.next()?.to_string();
+            if key == "hash" {
+                hash = Some(value);
+            } else {
+                entries.push((key, value));
+            }
+        }
+        Some(Self {
+            hash: hash?,
+            entries,
+        })
+    }
+
+    pub fn dump(&self, path: &Path) {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap_or_else(|err| {
+                panic!(
+                    "failed to create dir \"{}\", error: {}",
+                    parent.display(),
+                    err
+                )
+            });
+        }
+        let mut content = format!("hash = {}\n", self.hash);
+        for (key, value) in self.entries.iter() {
+            content.push_str(&format!("{} = {}\n", key, value));
+        }
+        fs::write(path, content)
+            .unwrap_or_else(|err| panic!("failed to write {}, error: {}", path.display(), err));
+    }
+}
+
+// Hash the relative paths and contents of all files under `dir`, in sorted order.
+fn hash_dir(dir: &Path) -> String {
+    let mut files = Vec::new();
+    collect_files(dir, &mut files);
+    files.sort();
+
+    let mut blake2b = ckb_testkit::ckb_hash::new_blake2b();
+    for file in files {
+        let content = fs::read(&file)
+            .unwrap_or_else(|err| panic!("failed to read {}, error: {}", file.display(), err));
+        let relative = file.strip_prefix(dir).unwrap_or(&file);
+        blake2b.update(relative.to_string_lossy().as_bytes());
+        blake2b.update(&content);
+    }
+    let mut digest = [0u8; 32];
+    blake2b.finalize(&mut digest);
+    format!("0x{}", to_hex(&digest))
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(dir)
+        .unwrap_or_else(|err| panic!("failed to read dir {}, error: {}", dir.display(), err));
+    for entry in entries {
+        let path = entry.expect("read dir entry").path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
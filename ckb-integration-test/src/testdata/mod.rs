@@ -1,28 +1,156 @@
 use ckb_testkit::Node;
 use std::fs;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
 
 pub const TESTDATA_DIR: &str = "./testdata";
 
 mod epoch2;
 mod height100000;
 mod height13;
+mod manifest;
+
+pub use manifest::{TestdataInputs, TestdataManifest, TestdataTarget};
+
+static ALLOW_MISSING_MANIFEST: AtomicBool = AtomicBool::new(false);
+
+/// Accept the testdata which has no manifest, instead of failing, see `check_testdata`.
+pub fn allow_missing_manifest(allow: bool) {
+    ALLOW_MISSING_MANIFEST.store(allow, Ordering::SeqCst);
+}
 
 pub trait Testdata {
     fn testdata_name(&self) -> &str {
         testdata_name(self)
     }
+
+    /// Names of the databases this generator dumps into `testdata/db/`.
+    fn outputs(&self) -> Vec<&str> {
+        vec![self.testdata_name()]
+    }
+
+    /// Declared inputs of this generator, see [`TestdataInputs`].
+    fn inputs(&self) -> TestdataInputs;
+
     fn generate(&self);
+
+    fn manifest_path(&self) -> PathBuf {
+        PathBuf::from(TESTDATA_DIR)
+            .join("manifest")
+            .join(self.testdata_name())
+    }
+
+    /// Return the reason if the generated testdata is missing or stale.
+    fn check_stale(&self) -> Option<String> {
+        for output in self.outputs() {
+            let db_path = PathBuf::from(TESTDATA_DIR).join("db").join(output);
+            if !db_path.exists() {
+                return Some(format!("\"{}\" is missing", db_path.display()));
+            }
+        }
+        let expected = TestdataManifest::from_inputs(&self.inputs());
+        match TestdataManifest::load(&self.manifest_path()) {
+            None => Some(format!(
+                "manifest \"{}\" is missing, pass `--allow-missing-manifest` to accept it",
+                self.manifest_path().display()
+            )),
+            Some(actual) if actual.hash != expected.hash => Some(format!(
+                "manifest hash mismatch, expected: {}, actual: {}",
+                expected.hash, actual.hash
+            )),
+            Some(_) => None,
+        }
+    }
 }
 
 pub fn all_testdata_generators() -> Vec<Box<dyn Testdata>> {
     vec![
         Box::new(height13::Height13TestData),
         Box::new(epoch2::Epoch2TestData),
-        // Box::new(height100000::Height100000TestData),
+        Box::new(height100000::Height100000TestData),
     ]
 }
 
+/// Regenerate the stale testdata of `generators`, or all of them if `force` is true.
+pub fn generate_testdata(generators: Vec<Box<dyn Testdata>>, force: bool) {
+    for generator in generators {
+        let testdata_name = generator.testdata_name().to_string();
+        if !force {
+            match generator.check_stale() {
+                None => {
+                    ckb_testkit::info!("testdata \"{}\" is up to date, skip", testdata_name);
+                    continue;
+                }
+                Some(reason) => {
+                    ckb_testkit::info!("testdata \"{}\" is stale, {}", testdata_name, reason);
+                }
+            }
+        }
+        let inputs = generator.inputs();
+        if let Err(err) = inputs.check_ckb_binaries() {
+            panic!("failed to generate testdata \"{}\", {}", testdata_name, err);
+        }
+        ckb_testkit::info!("generating testdata \"{}\"", testdata_name);
+        generator.generate();
+        let mut manifest = TestdataManifest::from_inputs(&inputs);
+        manifest.record_ckb_versions(&inputs);
+        manifest.dump(&generator.manifest_path());
+    }
+}
+
+/// Return the reason if any of the required testdata is missing or stale. Testdata without
+/// manifest counts as stale, unless `allow_missing_manifest` is set.
+pub fn check_testdata(required: &[String]) -> Result<(), String> {
+    for output in required {
+        let generator = all_testdata_generators()
+            .into_iter()
+            .find(|generator| generator.outputs().contains(&output.as_str()));
+        let generator = match generator {
+            Some(generator) => generator,
+            None => {
+                let db_path = PathBuf::from(TESTDATA_DIR).join("db").join(output);
+                if !db_path.exists() {
+                    return Err(format!(
+                        "testdata \"{}\" is missing and no generator produces it",
+                        db_path.display()
+                    ));
+                }
+                continue;
+            }
+        };
+        match generator.check_stale() {
+            None => {}
+            Some(_)
+                if ALLOW_MISSING_MANIFEST.load(Ordering::SeqCst)
+                    && !generator.manifest_path().exists()
+                    && testdata_exists(&*generator) =>
+            {
+                ckb_testkit::warn!(
+                    "testdata \"{}\" has no manifest, run `generate-testdata --only {}` to create one",
+                    output,
+                    generator.testdata_name(),
+                );
+            }
+            Some(reason) => {
+                return Err(format!(
+                    "testdata \"{}\" is not ready, {}. Please run `generate-testdata --only {}`",
+                    output,
+                    reason,
+                    generator.testdata_name(),
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+fn testdata_exists(generator: &dyn Testdata) -> bool {
+    generator
+        .outputs()
+        .iter()
+        .all(|output| PathBuf::from(TESTDATA_DIR).join("db").join(output).exists())
+}
+
 fn testdata_name<T: ?Sized>(_: &T) -> &str {
     let type_name = ::std::any::type_name::<T>();
     type_name.split_terminator("::").last().unwrap()
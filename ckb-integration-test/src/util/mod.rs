@@ -1,10 +1,9 @@
-pub mod deployer;
 pub mod shortcuts;
 
 pub use shortcuts::{v0_100, v0_43};
 
 use ckb_testkit::ckb_types::core::{BlockNumber, EpochNumber};
-use ckb_testkit::Node;
+use ckb_testkit::{CkbBinary, Node};
 
 pub fn estimate_start_number_of_epoch(node: &Node, epoch_number: EpochNumber) -> BlockNumber {
     assert!(node.consensus().permanent_difficulty_in_dummy);
@@ -14,3 +13,28 @@ pub fn estimate_start_number_of_epoch(node: &Node, epoch_number: EpochNumber) ->
         .expect("genesis epoch should exist");
     genesis_epoch.length.value() * epoch_number
 }
+
+/// The registered ckb binaries of different versions, for the cases running against every
+/// version. Of the binaries of the same version, the first one by name is taken.
+pub fn distinct_binaries() -> Vec<CkbBinary> {
+    let mut binaries: Vec<CkbBinary> = Vec::new();
+    for binary in ckb_testkit::ckb_binaries() {
+        if binaries.iter().all(|taken| taken.version != binary.version) {
+            binaries.push(binary);
+        }
+    }
+    binaries
+}
+
+/// Every pair of the registered ckb binaries of different versions, for the cases running a
+/// version matrix, see `distinct_binaries`.
+pub fn mixed_pairs() -> Vec<(CkbBinary, CkbBinary)> {
+    let binaries = distinct_binaries();
+    let mut pairs = Vec::new();
+    for (i, binary_a) in binaries.iter().enumerate() {
+        for binary_b in binaries.iter().skip(i + 1) {
+            pairs.push((binary_a.clone(), binary_b.clone()));
+        }
+    }
+    pairs
+}
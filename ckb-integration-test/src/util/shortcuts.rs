@@ -1,29 +1,18 @@
 use crate::prelude::*;
-use lazy_static::lazy_static;
-
-lazy_static! {
-    static ref V0_43: String = {
-        let stdout = ::std::process::Command::new(CKB2019.read().unwrap().clone())
-            .arg("--version")
-            .output()
-            .expect("failed to execute process")
-            .stdout;
-        String::from_utf8(stdout).unwrap()
-    };
-    static ref V0_100: String = {
-        let stdout = ::std::process::Command::new(CKB2021.read().unwrap().clone())
-            .arg("--version")
-            .output()
-            .expect("failed to execute process")
-            .stdout;
-        String::from_utf8(stdout).unwrap()
-    };
-}
 
+/// The `--version` output of the registered ckb2019 binary, like "ckb 0.43.2 (...)".
 pub fn v0_43() -> String {
-    V0_43.clone()
+    ckb_version(CKB2019)
 }
 
+/// The `--version` output of the registered ckb2021 binary, like "ckb 0.100.0 (...)".
 pub fn v0_100() -> String {
-    V0_100.clone()
+    ckb_version(CKB2021)
+}
+
+/// The `--version` output of the ckb binary registered under `name`.
+pub fn ckb_version(name: &str) -> String {
+    ckb_testkit::ckb_binary(name)
+        .unwrap_or_else(|| panic!("unregistered ckb binary \"{}\"", name))
+        .version
 }
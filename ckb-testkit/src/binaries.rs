@@ -0,0 +1,87 @@
+//! Named ckb binaries. `NodeOptions::ckb_binary` refers to a binary by the name it is registered
+//! under, so that the same node options run against whichever binary the runner registers.
+use lazy_static::lazy_static;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+use std::sync::RwLock;
+
+lazy_static! {
+    static ref CKB_BINARIES: RwLock<BTreeMap<String, CkbBinary>> = RwLock::new(BTreeMap::new());
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CkbBinary {
+    pub name: String,
+    pub path: PathBuf,
+    /// The output of `ckb --version`, like "ckb 0.100.0 (7ac2d4c 2021-10-25)"
+    pub version: String,
+}
+
+impl CkbBinary {
+    /// Run `<path> --version` to detect the version of the binary. A bare `path`, like "ckb",
+    /// is searched in `PATH`.
+    pub fn probe(name: &str, path: PathBuf) -> Result<Self, String> {
+        let output = Command::new(&path)
+            .arg("--version")
+            .output()
+            .map_err(|err| {
+                format!(
+                    "failed to execute \"{} --version\", error: {}",
+                    path.display(),
+                    err
+                )
+            })?;
+        let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if !output.status.success() || version.is_empty() {
+            return Err(format!(
+                "failed to detect the version of \"{}\", status: {}",
+                path.display(),
+                output.status
+            ));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            path,
+            version,
+        })
+    }
+
+    /// The version number, like "0.100.0"
+    pub fn version_number(&self) -> &str {
+        self.version
+            .split_whitespace()
+            .find(|token| token.starts_with(|c: char| c.is_ascii_digit()))
+            .unwrap_or_default()
+    }
+
+    /// Whether the binary speaks the ckb2021 RPC, that is, its version >= 0.44.0.
+    pub fn is_ckb2021(&self) -> bool {
+        version_compare::VersionCompare::compare_to(
+            self.version_number(),
+            "0.44.0",
+            &version_compare::CompOp::Ge,
+        )
+        .unwrap_or(true)
+    }
+}
+
+/// Probe and register the binary at `path` under `name`, replacing the one registered under
+/// the same name.
+pub fn register_ckb_binary(name: &str, path: PathBuf) -> Result<CkbBinary, String> {
+    let binary = CkbBinary::probe(name, path)?;
+    CKB_BINARIES
+        .write()
+        .unwrap()
+        .insert(name.to_string(), binary.clone());
+    Ok(binary)
+}
+
+pub fn ckb_binary(name: &str) -> Option<CkbBinary> {
+    CKB_BINARIES.read().unwrap().get(name).cloned()
+}
+
+/// The registered binaries, ordered by name.
+pub fn ckb_binaries() -> Vec<CkbBinary> {
+    CKB_BINARIES.read().unwrap().values().cloned().collect()
+}
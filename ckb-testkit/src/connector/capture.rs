@@ -0,0 +1,440 @@
+//! Capture the frames of connector sessions into a file, pretty-print and replay them.
+//!
+//! # File Format
+//!
+//! A capture file starts with the 8-byte magic `CKBCAP01`, followed by the frames:
+//!
+//! ```text
+//! +-------+------+---------------------------------------------+
+//! | Bytes | Type | Field                                       |
+//! |-------+------+---------------------------------------------|
+//! | 0~7   | u64  | timestamp, microseconds since the Unix epoch|
+//! | 8     | u8   | flags, see below                            |
+//! | 9~12  | u32  | session id                                  |
+//! | 13~16 | u32  | protocol id                                 |
+//! | 17~20 | u32  | length of data                              |
+//! | 21~   |      | data, the message before compression, or    |
+//! |       |      | the frame as sent if it is a raw frame      |
+//! +-------+------+---------------------------------------------+
+//! ```
+//!
+//! The flags byte is the direction in bit 0, outbound 0 and inbound 1, and whether the
+//! frame was injected via `Connector::send_frame` in bit 1. Other bits are reserved.
+//!
+//! All integers are little-endian.
+use super::compress::decompress;
+use super::{
+    Connector, ConnectorBuilder, SharedState, SimpleProtocolHandler, SimpleServiceHandler,
+    SupportProtocols,
+};
+use crate::Node;
+use ckb_types::{packed, prelude::*};
+use p2p::{
+    bytes::{Bytes, BytesMut},
+    ProtocolId, SessionId,
+};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const MAGIC: &[u8; 8] = b"CKBCAP01";
+const FLAG_INBOUND: u8 = 0b0000_0001;
+const FLAG_RAW: u8 = 0b0000_0010;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Outbound,
+    Inbound,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapturedFrame {
+    /// Microseconds since the Unix epoch
+    pub timestamp: u64,
+    pub direction: Direction,
+    pub session_id: SessionId,
+    pub protocol_id: ProtocolId,
+    /// The frame was sent via `Connector::send_frame`, `data` is the frame as sent, including
+    /// the compress flag byte of compressed protocols
+    pub raw: bool,
+    pub data: Bytes,
+}
+
+/// Append frames into a capture file. Every frame is flushed, so that the capture is
+/// complete even if the case panics.
+pub struct CaptureWriter {
+    writer: BufWriter<File>,
+}
+
+impl CaptureWriter {
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writer.write_all(MAGIC)?;
+        writer.flush()?;
+        Ok(Self { writer })
+    }
+
+    pub fn write(&mut self, frame: &CapturedFrame) -> io::Result<()> {
+        let mut flags = 0u8;
+        if frame.direction == Direction::Inbound {
+            flags |= FLAG_INBOUND;
+        }
+        if frame.raw {
+            flags |= FLAG_RAW;
+        }
+        self.writer.write_all(&frame.timestamp.to_le_bytes())?;
+        self.writer.write_all(&[flags])?;
+        self.writer
+            .write_all(&(frame.session_id.value() as u32).to_le_bytes())?;
+        self.writer
+            .write_all(&(frame.protocol_id.value() as u32).to_le_bytes())?;
+        self.writer
+            .write_all(&(frame.data.len() as u32).to_le_bytes())?;
+        self.writer.write_all(&frame.data)?;
+        self.writer.flush()
+    }
+}
+
+/// Read all frames of a capture file.
+pub fn read_capture<P: AsRef<Path>>(path: P) -> Result<Vec<CapturedFrame>, String> {
+    let path = path.as_ref();
+    let file = File::open(path)
+        .map_err(|err| format!("failed to open capture {}, error: {}", path.display(), err))?;
+    let mut reader = BufReader::new(file);
+    let mut magic = [0u8; 8];
+    reader
+        .read_exact(&mut magic)
+        .map_err(|err| format!("failed to read capture header, error: {}", err))?;
+    if &magic != MAGIC {
+        return Err(format!("{} is not a capture file", path.display()));
+    }
+
+    let mut frames = Vec::new();
+    loop {
+        let mut header = [0u8; 21];
+        match reader.read_exact(&mut header) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(format!("failed to read frame, error: {}", err)),
+        }
+        let read_u32 = |offset: usize| {
+            let mut buf = [0u8; 4];
+            buf.copy_from_slice(&header[offset..offset + 4]);
+            u32::from_le_bytes(buf)
+        };
+        let mut timestamp = [0u8; 8];
+        timestamp.copy_from_slice(&header[0..8]);
+        let flags = header[8];
+        if flags & !(FLAG_INBOUND | FLAG_RAW) != 0 {
+            return Err(format!("invalid frame flags {:#010b}", flags));
+        }
+        let direction = if flags & FLAG_INBOUND == 0 {
+            Direction::Outbound
+        } else {
+            Direction::Inbound
+        };
+        let mut data = vec![0u8; read_u32(17) as usize];
+        reader
+            .read_exact(&mut data)
+            .map_err(|err| format!("truncated frame #{}, error: {}", frames.len(), err))?;
+        frames.push(CapturedFrame {
+            timestamp: u64::from_le_bytes(timestamp),
+            direction,
+            session_id: SessionId::new(read_u32(9) as usize),
+            protocol_id: ProtocolId::new(read_u32(13) as usize),
+            raw: flags & FLAG_RAW != 0,
+            data: Bytes::from(data),
+        });
+    }
+    Ok(frames)
+}
+
+/// Format a frame in one line, decoding the data with the molecule types of the protocol.
+///
+/// ```text
+/// +1.205s session 1 > /ckb/syn {"type":"GetHeaders", ...}
+/// +1.310s session 1 > /ckb/syn (raw) malformed frame: 0x80...
+/// ```
+pub fn pretty_print(frame: &CapturedFrame, start_timestamp: u64) -> String {
+    let elapsed = frame.timestamp.saturating_sub(start_timestamp);
+    let arrow = match frame.direction {
+        Direction::Outbound => ">",
+        Direction::Inbound => "<",
+    };
+    let protocol = SupportProtocols::from_protocol_id(frame.protocol_id);
+    let protocol_name = protocol
+        .as_ref()
+        .map(|protocol| protocol.name())
+        .unwrap_or_else(|| format!("protocol-{}", frame.protocol_id.value()));
+    let content = match protocol {
+        Some(protocol) if frame.raw && protocol.is_compressed() => {
+            match decompress(BytesMut::from(&frame.data[..])) {
+                Ok(data) => decode_to_string(&protocol, &data),
+                Err(_) => format!("malformed frame: {}", to_hex(&frame.data)),
+            }
+        }
+        Some(protocol) => decode_to_string(&protocol, &frame.data),
+        None => format!("raw: {}", to_hex(&frame.data)),
+    };
+    format!(
+        "+{}.{:03}s session {} {} {}{} {}",
+        elapsed / 1_000_000,
+        elapsed % 1_000_000 / 1_000,
+        frame.session_id.value(),
+        arrow,
+        protocol_name,
+        if frame.raw { " (raw)" } else { "" },
+        content
+    )
+}
+
+fn decode_to_string(protocol: &SupportProtocols, data: &[u8]) -> String {
+    let decoded =
+        match protocol {
+            SupportProtocols::Sync => {
+                packed::SyncMessage::from_compatible_slice(data).map(|message| message.to_string())
+            }
+            SupportProtocols::Relay | SupportProtocols::RelayV2 => {
+                packed::RelayMessage::from_compatible_slice(data).map(|message| message.to_string())
+            }
+            SupportProtocols::Discovery => packed::DiscoveryMessage::from_compatible_slice(data)
+                .map(|message| message.to_string()),
+            SupportProtocols::Identify => packed::IdentifyMessage::from_compatible_slice(data)
+                .map(|message| message.to_string()),
+            SupportProtocols::Ping => {
+                packed::PingMessage::from_compatible_slice(data).map(|message| message.to_string())
+            }
+            SupportProtocols::Time => {
+                packed::Time::from_compatible_slice(data).map(|message| message.to_string())
+            }
+            SupportProtocols::Alert => {
+                packed::Alert::from_compatible_slice(data).map(|message| message.to_string())
+            }
+            SupportProtocols::DisconnectMessage => {
+                return format!("{:?}", String::from_utf8_lossy(data));
+            }
+            SupportProtocols::Feeler => return format!("raw: {}", to_hex(data)),
+        };
+    decoded.unwrap_or_else(|_| format!("malformed: {}", to_hex(data)))
+}
+
+// At most 64 bytes are printed
+fn to_hex(data: &[u8]) -> String {
+    let hex = data
+        .iter()
+        .take(64)
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    if data.len() > 64 {
+        format!("0x{}...({} bytes)", hex, data.len())
+    } else {
+        format!("0x{}", hex)
+    }
+}
+
+pub(super) fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .expect("system time")
+        .as_micros() as u64
+}
+
+/// Replay the outbound frames of a captured session to a node, from a fresh connector.
+///
+/// ```ignore
+/// let connector = Replayer::from_capture("regression.cap")?.replay(node)?;
+/// let closed = connector.wait_session_closed(node, Duration::from_secs(5));
+/// ```
+pub struct Replayer {
+    frames: Vec<CapturedFrame>,
+    session_id: Option<SessionId>,
+    preserve_timing: bool,
+}
+
+impl Replayer {
+    pub fn new(frames: Vec<CapturedFrame>) -> Self {
+        Self {
+            frames,
+            session_id: None,
+            preserve_timing: false,
+        }
+    }
+
+    pub fn from_capture<P: AsRef<Path>>(path: P) -> Result<Self, String> {
+        read_capture(path).map(Self::new)
+    }
+
+    /// Replay the session of `session_id`. By default it is the first session which has
+    /// outbound frames.
+    pub fn session(mut self, session_id: SessionId) -> Self {
+        self.session_id = Some(session_id);
+        self
+    }
+
+    /// Keep the intervals between the frames as captured, instead of sending them at once.
+    pub fn preserve_timing(mut self, preserve_timing: bool) -> Self {
+        self.preserve_timing = preserve_timing;
+        self
+    }
+
+    /// The outbound frames of the replayed session
+    pub fn outbound_frames(&self) -> Vec<&CapturedFrame> {
+        let session_id = self.session_id.or_else(|| {
+            self.frames
+                .iter()
+                .find(|frame| frame.direction == Direction::Outbound)
+                .map(|frame| frame.session_id)
+        });
+        self.frames
+            .iter()
+            .filter(|frame| {
+                frame.direction == Direction::Outbound && Some(frame.session_id) == session_id
+            })
+            .collect()
+    }
+
+    /// Connect to `node` and send the outbound frames in order. Return the connector, which
+    /// is still connected unless the node dropped it, for inspecting the node's reaction.
+    pub fn replay(&self, node: &Node) -> Result<Connector, String> {
+        let frames = self.outbound_frames();
+        let mut protocols = frames
+            .iter()
+            .map(|frame| {
+                SupportProtocols::from_protocol_id(frame.protocol_id).ok_or_else(|| {
+                    format!(
+                        "unsupported protocol id {} in capture",
+                        frame.protocol_id.value()
+                    )
+                })
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        protocols.push(SupportProtocols::Sync);
+        protocols.push(SupportProtocols::Identify);
+
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut builder = ConnectorBuilder::new();
+        let mut registered = HashSet::new();
+        for protocol in protocols {
+            if registered.insert(protocol.protocol_id()) {
+                // Raw metas, which send the raw frames as-is and compress the others
+                builder = builder.protocol_meta(
+                    SimpleProtocolHandler::new(Arc::clone(&shared), protocol).build_raw(),
+                );
+            }
+        }
+        let mut connector = builder.build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        connector.connect(node)?;
+
+        let mut last_timestamp = frames.first().map(|frame| frame.timestamp);
+        for (index, frame) in frames.iter().enumerate() {
+            if self.preserve_timing {
+                if let Some(last_timestamp) = last_timestamp {
+                    let interval = frame.timestamp.saturating_sub(last_timestamp);
+                    sleep(Duration::from_micros(interval));
+                }
+                last_timestamp = Some(frame.timestamp);
+            }
+            let protocol = SupportProtocols::from_protocol_id(frame.protocol_id).expect("checked");
+            let sent = if frame.raw {
+                connector.send_frame(node, protocol, frame.data.clone())
+            } else {
+                connector.send(node, protocol, frame.data.clone())
+            };
+            sent.map_err(|err| format!("failed to replay frame #{}, error: {}", index, err))?;
+        }
+        Ok(connector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn frames() -> Vec<CapturedFrame> {
+        vec![
+            CapturedFrame {
+                timestamp: 1_600_000_000_000_000,
+                direction: Direction::Outbound,
+                session_id: SessionId::new(1),
+                protocol_id: SupportProtocols::Sync.protocol_id(),
+                raw: false,
+                data: Bytes::from_static(b"\x01\x02\x03"),
+            },
+            CapturedFrame {
+                timestamp: 1_600_000_000_500_000,
+                direction: Direction::Inbound,
+                session_id: SessionId::new(1),
+                protocol_id: SupportProtocols::Identify.protocol_id(),
+                raw: false,
+                data: Bytes::new(),
+            },
+            CapturedFrame {
+                timestamp: 1_600_000_001_000_000,
+                direction: Direction::Outbound,
+                session_id: SessionId::new(u32::MAX as usize),
+                protocol_id: SupportProtocols::Relay.protocol_id(),
+                raw: true,
+                data: Bytes::from(vec![0x80; 300]),
+            },
+        ]
+    }
+
+    #[test]
+    fn capture_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("round_trip.cap");
+        let mut writer = CaptureWriter::create(&path).unwrap();
+        for frame in frames() {
+            writer.write(&frame).unwrap();
+        }
+        drop(writer);
+
+        let content = fs::read(&path).unwrap();
+        assert_eq!(&content[..8], b"CKBCAP01");
+        // The header of the first frame
+        assert_eq!(&content[8..16], &1_600_000_000_000_000u64.to_le_bytes());
+        assert_eq!(content[16], 0);
+        assert_eq!(&content[17..21], &1u32.to_le_bytes());
+        assert_eq!(&content[25..29], &3u32.to_le_bytes());
+        assert_eq!(
+            content.len(),
+            8 + frames()
+                .iter()
+                .map(|frame| 21 + frame.data.len())
+                .sum::<usize>()
+        );
+        assert_eq!(read_capture(&path), Ok(frames()));
+    }
+
+    #[test]
+    fn read_capture_rejects_malformed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("malformed.cap");
+        let mut writer = CaptureWriter::create(&path).unwrap();
+        writer.write(&frames()[0]).unwrap();
+        drop(writer);
+        let content = fs::read(&path).unwrap();
+
+        fs::write(&path, b"CKBCAP02").unwrap();
+        assert!(read_capture(&path).is_err());
+
+        // Reserved flags
+        let mut reserved = content.clone();
+        reserved[16] |= 0b0000_0100;
+        fs::write(&path, &reserved).unwrap();
+        assert!(read_capture(&path).is_err());
+
+        // Truncated data
+        fs::write(&path, &content[..content.len() - 1]).unwrap();
+        assert!(read_capture(&path).is_err());
+
+        // No frames
+        fs::write(&path, &content[..8]).unwrap();
+        assert_eq!(read_capture(&path), Ok(vec![]));
+    }
+}
@@ -7,7 +7,8 @@ use std::io;
 pub(crate) const COMPRESSION_SIZE_THRESHOLD: usize = 1024;
 const UNCOMPRESS_FLAG: u8 = 0b0000_0000;
 const COMPRESS_FLAG: u8 = 0b1000_0000;
-const MAX_UNCOMPRESSED_LEN: usize = 1 << 23; // 8MB
+/// The node rejects compressed messages which claim to be longer than this after decompression
+pub const MAX_UNCOMPRESSED_LEN: usize = 1 << 23; // 8MB
 
 /// Compressed decompression structure
 ///
@@ -114,3 +115,56 @@ pub fn compress(src: Bytes) -> Bytes {
 pub fn decompress(src: BytesMut) -> Result<Bytes, io::Error> {
     Message::from_compressed(src).decompress()
 }
+
+/// How to encode a message into a frame, see [`Connector::send_with_compression`].
+///
+/// [`Connector::send_with_compression`]: super::Connector::send_with_compression
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    /// Compress if the message exceeds the threshold, as the node does
+    Auto,
+    /// Never compress, only prepend the uncompressed flag
+    Never,
+    /// Always compress with snappy, even if the message is small
+    Always,
+    /// Set the compress flag over the uncompressed data
+    FlagOnly,
+    /// Compress with snappy, then cut the snappy body in half so that the decompression fails
+    Corrupted,
+}
+
+/// Encode `data` into a frame with the compress flag as `compression` specified
+pub fn compress_with(data: Bytes, compression: Compression) -> Bytes {
+    match compression {
+        Compression::Auto => compress(data),
+        Compression::Never => Message::from_raw(data).inner.freeze(),
+        Compression::Always | Compression::Corrupted => {
+            let compressed = SnapEncoder::new()
+                .compress_vec(&data)
+                .expect("snappy compress");
+            let len = if compression == Compression::Corrupted {
+                compressed.len() / 2
+            } else {
+                compressed.len()
+            };
+            let mut frame = BytesMut::with_capacity(len + 1);
+            frame.put_u8(COMPRESS_FLAG);
+            frame.put_slice(&compressed[..len]);
+            frame.freeze()
+        }
+        Compression::FlagOnly => {
+            let mut message = Message::from_raw(data);
+            message.set_compress_flag();
+            message.inner.freeze()
+        }
+    }
+}
+
+/// A frame of valid snappy data which decompresses into `uncompressed_len` zeros. The node
+/// rejects it before decompressing if `uncompressed_len` exceeds [`MAX_UNCOMPRESSED_LEN`].
+pub fn build_decompression_bomb(uncompressed_len: usize) -> Bytes {
+    compress_with(
+        Bytes::from(vec![0u8; uncompressed_len]),
+        Compression::Always,
+    )
+}
@@ -0,0 +1,97 @@
+//! Decode the received network messages into molecule types.
+use ckb_types::{packed, prelude::*};
+
+/// A message type which can be decoded from the data received under a protocol.
+pub trait ProtocolMessage: Sized {
+    fn decode(data: &[u8]) -> Result<Self, String>;
+
+    /// The name used in logs and error messages, e.g. `"GetHeaders"`.
+    fn name(&self) -> &str;
+}
+
+impl ProtocolMessage for packed::SyncMessageUnion {
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        packed::SyncMessage::from_compatible_slice(data)
+            .map(|message| message.to_enum())
+            .map_err(|err| format!("failed to decode SyncMessage, error: {:?}", err))
+    }
+
+    fn name(&self) -> &str {
+        self.item_name()
+    }
+}
+
+impl ProtocolMessage for packed::RelayMessageUnion {
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        packed::RelayMessage::from_compatible_slice(data)
+            .map(|message| message.to_enum())
+            .map_err(|err| format!("failed to decode RelayMessage, error: {:?}", err))
+    }
+
+    fn name(&self) -> &str {
+        self.item_name()
+    }
+}
+
+impl ProtocolMessage for packed::DiscoveryPayloadUnion {
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        packed::DiscoveryMessage::from_compatible_slice(data)
+            .map(|message| message.payload().to_enum())
+            .map_err(|err| format!("failed to decode DiscoveryMessage, error: {:?}", err))
+    }
+
+    fn name(&self) -> &str {
+        self.item_name()
+    }
+}
+
+impl ProtocolMessage for packed::IdentifyMessage {
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        packed::IdentifyMessage::from_compatible_slice(data)
+            .map_err(|err| format!("failed to decode IdentifyMessage, error: {:?}", err))
+    }
+
+    fn name(&self) -> &str {
+        "IdentifyMessage"
+    }
+}
+
+impl ProtocolMessage for packed::PingPayloadUnion {
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        packed::PingMessage::from_compatible_slice(data)
+            .map(|message| message.payload().to_enum())
+            .map_err(|err| format!("failed to decode PingMessage, error: {:?}", err))
+    }
+
+    fn name(&self) -> &str {
+        self.item_name()
+    }
+}
+
+impl ProtocolMessage for packed::Time {
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        packed::Time::from_compatible_slice(data)
+            .map_err(|err| format!("failed to decode Time, error: {:?}", err))
+    }
+
+    fn name(&self) -> &str {
+        "Time"
+    }
+}
+
+impl ProtocolMessage for packed::Alert {
+    fn decode(data: &[u8]) -> Result<Self, String> {
+        packed::Alert::from_compatible_slice(data)
+            .map_err(|err| format!("failed to decode Alert, error: {:?}", err))
+    }
+
+    fn name(&self) -> &str {
+        "Alert"
+    }
+}
+
+/// Decode the reason sent under the DisconnectMessage protocol, which is UTF-8 bytes.
+pub fn decode_disconnect_message(data: &[u8]) -> Result<String, String> {
+    String::from_utf8(data.to_vec())
+        .map_err(|err| format!("failed to decode DisconnectMessage, error: {:?}", err))
+}
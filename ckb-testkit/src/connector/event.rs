@@ -0,0 +1,93 @@
+use p2p::{multiaddr::Multiaddr, secio::PeerId, ProtocolId, SessionId};
+use std::time::Instant;
+
+/// The events recorded by the connector's handlers, see [`Connector::events`].
+///
+/// [`Connector::events`]: super::Connector::events
+#[derive(Debug, Clone)]
+pub enum ConnectorEvent {
+    SessionOpened {
+        session_id: SessionId,
+        peer_id: Option<PeerId>,
+        address: Multiaddr,
+        inbound: bool,
+    },
+    SessionClosed {
+        session_id: SessionId,
+        peer_id: Option<PeerId>,
+        address: Multiaddr,
+    },
+    ProtocolOpened {
+        session_id: SessionId,
+        protocol_id: ProtocolId,
+        version: String,
+    },
+    ProtocolClosed {
+        session_id: SessionId,
+        protocol_id: ProtocolId,
+    },
+    /// The reason sent by the remote peer under DisconnectMessage protocol
+    DisconnectMessage {
+        session_id: SessionId,
+        reason: String,
+    },
+    ListenStarted {
+        address: Multiaddr,
+    },
+    ListenClosed {
+        address: Multiaddr,
+    },
+    /// A service error reported by tentacle
+    Error {
+        kind: ConnectorErrorKind,
+        message: String,
+    },
+}
+
+/// The kind of a [`ConnectorEvent::Error`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConnectorErrorKind {
+    /// Failed to dial `address`
+    Dialer { address: Multiaddr },
+    /// Failed to listen on `address`
+    Listen { address: Multiaddr },
+    /// A protocol of the session failed, e.g. the codec rejected a frame
+    Protocol {
+        session_id: SessionId,
+        protocol_id: ProtocolId,
+    },
+    /// The other errors, e.g. session timeouts and muxer errors
+    Other,
+}
+
+impl ConnectorEvent {
+    /// The session the event belongs to, `None` for the service-wise events.
+    pub fn session_id(&self) -> Option<SessionId> {
+        match self {
+            ConnectorEvent::SessionOpened { session_id, .. }
+            | ConnectorEvent::SessionClosed { session_id, .. }
+            | ConnectorEvent::ProtocolOpened { session_id, .. }
+            | ConnectorEvent::ProtocolClosed { session_id, .. }
+            | ConnectorEvent::DisconnectMessage { session_id, .. }
+            | ConnectorEvent::Error {
+                kind: ConnectorErrorKind::Protocol { session_id, .. },
+                ..
+            } => Some(*session_id),
+            ConnectorEvent::ListenStarted { .. }
+            | ConnectorEvent::ListenClosed { .. }
+            | ConnectorEvent::Error { .. } => None,
+        }
+    }
+}
+
+/// The closing of a session, see [`Connector::wait_session_closed`].
+///
+/// [`Connector::wait_session_closed`]: super::Connector::wait_session_closed
+#[derive(Debug, Clone)]
+pub struct SessionClosed {
+    pub session_id: SessionId,
+    pub opened_at: Instant,
+    pub closed_at: Instant,
+    /// The reason sent by the remote peer under DisconnectMessage protocol, if any
+    pub disconnect_message: Option<String>,
+}
@@ -1,7 +1,9 @@
 use super::{
+    decode::{decode_disconnect_message, ProtocolMessage},
     message::{
-        build_discovery_get_nodes, build_discovery_nodes, build_identify_message,
-        build_relay_transaction, build_relay_transaction_hashes,
+        build_disconnect_message, build_discovery_get_nodes, build_discovery_nodes,
+        build_identify_message, build_ping, build_pong, build_relay_transaction,
+        build_relay_transaction_hashes, build_time_message,
     },
     Connector, SupportProtocols,
 };
@@ -12,9 +14,11 @@ use ckb_types::{
     packed,
     prelude::*,
 };
-use p2p::multiaddr::Multiaddr;
 /// Util functions attached to `Connector`.
-use std::time::Duration;
+use crossbeam::channel::Receiver;
+use p2p::{multiaddr::Multiaddr, secio::PeerId};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
 
 impl Connector {
     pub fn send_relay_transaction(
@@ -89,21 +93,40 @@ impl Connector {
         Ok(())
     }
 
+    pub fn send_ping(&self, node: &Node, nonce: u32) -> Result<(), String> {
+        let message = build_ping(nonce);
+        self.send(node, SupportProtocols::Ping, message.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn send_pong(&self, node: &Node, nonce: u32) -> Result<(), String> {
+        let message = build_pong(nonce);
+        self.send(node, SupportProtocols::Ping, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// `timestamp` is in milliseconds. The node only samples the time messages from its
+    /// outbound peers, see [`Connector::accept_from`].
+    pub fn send_time_message(&self, node: &Node, timestamp: u64) -> Result<(), String> {
+        let message = build_time_message(timestamp);
+        self.send(node, SupportProtocols::Time, message.as_bytes())?;
+        Ok(())
+    }
+
+    /// See [`build_alert`](super::message::build_alert)
+    pub fn send_alert(&self, node: &Node, alert: &packed::Alert) -> Result<(), String> {
+        self.send(node, SupportProtocols::Alert, alert.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn send_disconnect_message(&self, node: &Node, reason: &str) -> Result<(), String> {
+        let message = build_disconnect_message(reason);
+        self.send(node, SupportProtocols::DisconnectMessage, message)?;
+        Ok(())
+    }
+
     pub fn recv(&self, node: &Node, protocol: &SupportProtocols) -> Result<Bytes, String> {
-        let session = self.get_session(node).ok_or(format!(
-            "session to {} is notfound",
-            node.p2p_address_with_node_id()
-        ))?;
-        let receiver = {
-            let shared = self.shared.read().unwrap();
-            shared
-                .get_protocol_receiver(&session.id, &protocol.protocol_id())
-                .ok_or(format!(
-                    "protocol \"{}\" to {} is notfound",
-                    protocol.name(),
-                    node.p2p_address_with_node_id()
-                ))?
-        };
+        let receiver = self.protocol_receiver(node, protocol)?;
         receiver.recv().map_err(|err| format!("{:?}", err))
     }
 
@@ -113,22 +136,156 @@ impl Connector {
         node: &Node,
         protocol: &SupportProtocols,
     ) -> Result<Bytes, String> {
+        let receiver = self.protocol_receiver(node, protocol)?;
+        receiver
+            .recv_timeout(timeout)
+            .map_err(|err| format!("{:?}", err))
+    }
+
+    pub fn recv_sync(
+        &self,
+        timeout: Duration,
+        node: &Node,
+    ) -> Result<packed::SyncMessageUnion, String> {
+        let data = self.recv_timeout(timeout, node, &SupportProtocols::Sync)?;
+        ProtocolMessage::decode(&data)
+    }
+
+    /// `relay_protocol` is either `SupportProtocols::Relay` or `SupportProtocols::RelayV2`.
+    pub fn recv_relay(
+        &self,
+        timeout: Duration,
+        node: &Node,
+        relay_protocol: &SupportProtocols,
+    ) -> Result<packed::RelayMessageUnion, String> {
+        assert!(
+            relay_protocol.protocol_id() == SupportProtocols::Relay.protocol_id()
+                || relay_protocol.protocol_id() == SupportProtocols::RelayV2.protocol_id()
+        );
+        let data = self.recv_timeout(timeout, node, relay_protocol)?;
+        ProtocolMessage::decode(&data)
+    }
+
+    pub fn recv_discovery(
+        &self,
+        timeout: Duration,
+        node: &Node,
+    ) -> Result<packed::DiscoveryPayloadUnion, String> {
+        let data = self.recv_timeout(timeout, node, &SupportProtocols::Discovery)?;
+        ProtocolMessage::decode(&data)
+    }
+
+    pub fn recv_identify(
+        &self,
+        timeout: Duration,
+        node: &Node,
+    ) -> Result<packed::IdentifyMessage, String> {
+        let data = self.recv_timeout(timeout, node, &SupportProtocols::Identify)?;
+        ProtocolMessage::decode(&data)
+    }
+
+    pub fn recv_ping(
+        &self,
+        timeout: Duration,
+        node: &Node,
+    ) -> Result<packed::PingPayloadUnion, String> {
+        let data = self.recv_timeout(timeout, node, &SupportProtocols::Ping)?;
+        ProtocolMessage::decode(&data)
+    }
+
+    pub fn recv_time(&self, timeout: Duration, node: &Node) -> Result<packed::Time, String> {
+        let data = self.recv_timeout(timeout, node, &SupportProtocols::Time)?;
+        ProtocolMessage::decode(&data)
+    }
+
+    pub fn recv_alert(&self, timeout: Duration, node: &Node) -> Result<packed::Alert, String> {
+        let data = self.recv_timeout(timeout, node, &SupportProtocols::Alert)?;
+        ProtocolMessage::decode(&data)
+    }
+
+    /// Wait for the reason sent by `node` under DisconnectMessage protocol. The reason is kept
+    /// after the session closed, so it is fine to call this after the disconnection.
+    pub fn wait_disconnect_message(
+        &self,
+        node: &Node,
+        timeout: Duration,
+    ) -> Result<String, String> {
+        let peer_id = node.node_id().parse::<PeerId>().expect("valid node id");
+        let start_time = Instant::now();
+        while start_time.elapsed() <= timeout {
+            let data = self.shared.read().unwrap().get_disconnect_message(&peer_id);
+            if let Some(data) = data {
+                return decode_disconnect_message(&data);
+            }
+            sleep(Duration::from_millis(100));
+        }
+        Err(format!(
+            "timeout when waiting DisconnectMessage from \"{}\"",
+            node.node_name()
+        ))
+    }
+
+    /// Receive messages under `protocol` until one satisfies `predicate`, the non-matching
+    /// messages are skipped. On timeout, the error lists the names of the skipped messages.
+    ///
+    /// ```ignore
+    /// connector.expect_message(node, &SupportProtocols::RelayV2, timeout, |message| {
+    ///     matches!(message, RelayMessageUnion::GetRelayTransactions(_))
+    /// })?;
+    /// ```
+    pub fn expect_message<M, F>(
+        &self,
+        node: &Node,
+        protocol: &SupportProtocols,
+        timeout: Duration,
+        predicate: F,
+    ) -> Result<M, String>
+    where
+        M: ProtocolMessage,
+        F: Fn(&M) -> bool,
+    {
+        let receiver = self.protocol_receiver(node, protocol)?;
+        let deadline = Instant::now() + timeout;
+        let mut skipped = Vec::new();
+        loop {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let data = match receiver.recv_timeout(deadline - now) {
+                Ok(data) => data,
+                Err(_) => break,
+            };
+            match M::decode(&data) {
+                Ok(message) if predicate(&message) => return Ok(message),
+                Ok(message) => skipped.push(message.name().to_string()),
+                Err(err) => skipped.push(err),
+            }
+        }
+        Err(format!(
+            "timeout when expecting message under protocol \"{}\" from \"{}\", skipped: {:?}",
+            protocol.name(),
+            node.node_name(),
+            skipped
+        ))
+    }
+
+    fn protocol_receiver(
+        &self,
+        node: &Node,
+        protocol: &SupportProtocols,
+    ) -> Result<Receiver<Bytes>, String> {
         let session = self.get_session(node).ok_or(format!(
             "session to {} is notfound",
             node.p2p_address_with_node_id()
         ))?;
-        let receiver = {
-            let shared = self.shared.read().unwrap();
-            shared
-                .get_protocol_receiver(&session.id, &protocol.protocol_id())
-                .ok_or(format!(
-                    "protocol \"{}\" to {} is notfound",
-                    protocol.name(),
-                    node.p2p_address_with_node_id()
-                ))?
-        };
-        receiver
-            .recv_timeout(timeout)
-            .map_err(|err| format!("{:?}", err))
+        let shared = self.shared.read().unwrap();
+        shared
+            .get_protocol_receiver(&session.id, &protocol.protocol_id())
+            .ok_or(format!(
+                "protocol \"{}\" to {} is notfound",
+                protocol.name(),
+                node.p2p_address_with_node_id()
+            ))
     }
 }
@@ -0,0 +1,355 @@
+use super::message::{
+    build_block_proposal, build_block_transactions, build_compact_block, build_sync_send_block,
+    network_identifier,
+};
+use super::{
+    Connector, ConnectorBuilder, ConnectorSender, SharedState, SimpleProtocolHandler,
+    SimpleServiceHandler, SupportProtocols,
+};
+use crate::Node;
+use ckb_types::{
+    core::{BlockView, TransactionView},
+    packed::{self, Byte32},
+    prelude::*,
+};
+use p2p::{bytes::Bytes, SessionId};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// FakeRelayPeer Builder
+///
+/// ```ignore
+/// let mut peer = FakeRelayPeerBuilder::new(SupportProtocols::RelayV2)
+///     .transactions(vec![transaction])
+///     .withhold_block_transactions()
+///     .build();
+/// peer.connect(node)?;
+/// peer.relay_compact_block(node, &block, &HashSet::new())?;
+/// ```
+pub struct FakeRelayPeerBuilder {
+    relay_protocol: SupportProtocols,
+    transactions: Vec<TransactionView>,
+    behaviour: Behaviour,
+}
+
+/// A fake peer which relays compact blocks over the Relay or RelayV2 protocol. It answers
+/// `GetBlockTransactions` and `GetBlockProposal` from the connected nodes automatically, and
+/// `GetBlocks` under the Sync protocol, which the nodes fall back to when they fail to
+/// reconstruct a compact block.
+pub struct FakeRelayPeer {
+    connector: Connector,
+    relay_protocol: SupportProtocols,
+    blocks: Arc<RwLock<HashMap<Byte32, BlockView>>>,
+    transactions: Arc<Vec<TransactionView>>,
+    behaviour: Arc<Behaviour>,
+    stats: Arc<RwLock<FakeRelayPeerStats>>,
+    stopped: Arc<AtomicBool>,
+    responders: Vec<JoinHandle<()>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Behaviour {
+    withhold_block_transactions: bool,
+    withhold_block_proposals: bool,
+    withhold_sync_blocks: bool,
+}
+
+/// What the fake peer has received and served.
+#[derive(Debug, Clone, Default)]
+pub struct FakeRelayPeerStats {
+    /// `(block_hash, indexes)` of the received `GetBlockTransactions`
+    pub received_get_block_transactions: Vec<(Byte32, Vec<u32>)>,
+    pub served_block_transactions: Vec<Byte32>,
+    pub received_get_block_proposals: Vec<packed::ProposalShortId>,
+    pub served_block_proposals: Vec<Byte32>,
+    pub received_get_blocks: Vec<Byte32>,
+    pub served_sync_blocks: Vec<Byte32>,
+}
+
+impl FakeRelayPeerBuilder {
+    /// `relay_protocol` is either `SupportProtocols::Relay` or `SupportProtocols::RelayV2`.
+    pub fn new(relay_protocol: SupportProtocols) -> Self {
+        Self {
+            relay_protocol,
+            transactions: Vec::new(),
+            behaviour: Default::default(),
+        }
+    }
+
+    /// Transactions used to answer `GetBlockProposal`.
+    pub fn transactions(mut self, transactions: Vec<TransactionView>) -> Self {
+        self.transactions = transactions;
+        self
+    }
+
+    /// Never answer `GetBlockTransactions`.
+    pub fn withhold_block_transactions(mut self) -> Self {
+        self.behaviour.withhold_block_transactions = true;
+        self
+    }
+
+    /// Never answer `GetBlockProposal`.
+    pub fn withhold_block_proposals(mut self) -> Self {
+        self.behaviour.withhold_block_proposals = true;
+        self
+    }
+
+    /// Never answer `GetBlocks` under the Sync protocol.
+    pub fn withhold_sync_blocks(mut self) -> Self {
+        self.behaviour.withhold_sync_blocks = true;
+        self
+    }
+
+    pub fn build(self) -> FakeRelayPeer {
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let connector = ConnectorBuilder::new()
+            .protocol_meta(
+                SimpleProtocolHandler::new(Arc::clone(&shared), self.relay_protocol.clone())
+                    .build(true),
+            )
+            .protocol_meta(
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true),
+            )
+            .protocol_meta(
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Identify)
+                    .build(false),
+            )
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        FakeRelayPeer {
+            connector,
+            relay_protocol: self.relay_protocol,
+            blocks: Default::default(),
+            transactions: Arc::new(self.transactions),
+            behaviour: Arc::new(self.behaviour),
+            stats: Default::default(),
+            stopped: Arc::new(AtomicBool::new(false)),
+            responders: Vec::new(),
+        }
+    }
+}
+
+impl FakeRelayPeer {
+    /// Connect to `node`, complete the identify handshake and start answering its Relay and
+    /// Sync requests.
+    pub fn connect(&mut self, node: &Node) -> Result<(), String> {
+        self.connector.connect(node)?;
+        let session = self.connector.get_session(node).ok_or_else(|| {
+            format!(
+                "The connection was disconnected to \"{}\"",
+                node.node_name()
+            )
+        })?;
+
+        let client_version = node.rpc_client().local_node_info().version;
+        self.connector.send_identify_message(
+            node,
+            &network_identifier(node),
+            &client_version,
+            vec![],
+            session.address.clone(),
+        )?;
+
+        for protocol in [self.relay_protocol.clone(), SupportProtocols::Sync].iter() {
+            let receiver = self
+                .connector
+                .shared()
+                .read()
+                .unwrap()
+                .get_protocol_receiver(&session.id, &protocol.protocol_id())
+                .ok_or_else(|| {
+                    format!(
+                        "{} protocol to \"{}\" is not opened",
+                        protocol.name(),
+                        node.node_name()
+                    )
+                })?;
+            let responder = Responder {
+                session_id: session.id,
+                sender: self.connector.sender(),
+                relay_protocol: self.relay_protocol.clone(),
+                blocks: Arc::clone(&self.blocks),
+                transactions: Arc::clone(&self.transactions),
+                behaviour: Arc::clone(&self.behaviour),
+                stats: Arc::clone(&self.stats),
+            };
+            let stopped = Arc::clone(&self.stopped);
+            let is_sync = protocol.protocol_id() == SupportProtocols::Sync.protocol_id();
+            let handle = ::std::thread::spawn(move || {
+                while !stopped.load(Ordering::SeqCst) {
+                    match receiver.recv_timeout(Duration::from_millis(100)) {
+                        Ok(data) if is_sync => responder.handle_sync(data),
+                        Ok(data) => responder.handle_relay(data),
+                        Err(crossbeam::channel::RecvTimeoutError::Timeout) => continue,
+                        Err(crossbeam::channel::RecvTimeoutError::Disconnected) => break,
+                    }
+                }
+            });
+            self.responders.push(handle);
+        }
+        Ok(())
+    }
+
+    /// Relay the compact block of `block` to `node`, see `build_compact_block`. The block is
+    /// kept to answer the node's requests.
+    pub fn relay_compact_block(
+        &self,
+        node: &Node,
+        block: &BlockView,
+        prefilled_transactions_indexes: &HashSet<usize>,
+    ) -> Result<(), String> {
+        self.blocks
+            .write()
+            .unwrap()
+            .insert(block.hash(), block.clone());
+        let message = build_compact_block(block, prefilled_transactions_indexes);
+        self.connector
+            .send(node, self.relay_protocol.clone(), message.as_bytes())
+    }
+
+    pub fn stats(&self) -> FakeRelayPeerStats {
+        self.stats.read().unwrap().clone()
+    }
+
+    pub fn connector(&self) -> &Connector {
+        &self.connector
+    }
+}
+
+impl Drop for FakeRelayPeer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        for handle in self.responders.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+// Answer the Relay and Sync requests of a session
+struct Responder {
+    session_id: SessionId,
+    sender: ConnectorSender,
+    relay_protocol: SupportProtocols,
+    blocks: Arc<RwLock<HashMap<Byte32, BlockView>>>,
+    transactions: Arc<Vec<TransactionView>>,
+    behaviour: Arc<Behaviour>,
+    stats: Arc<RwLock<FakeRelayPeerStats>>,
+}
+
+impl Responder {
+    fn handle_relay(&self, data: Bytes) {
+        let message = match packed::RelayMessage::from_compatible_slice(&data) {
+            Ok(message) => message,
+            Err(err) => {
+                crate::error!("FakeRelayPeer received malformed RelayMessage: {:?}", err);
+                return;
+            }
+        };
+        match message.to_enum() {
+            packed::RelayMessageUnion::GetBlockTransactions(get_block_transactions) => {
+                let block_hash = get_block_transactions.block_hash();
+                let indexes = get_block_transactions
+                    .indexes()
+                    .into_iter()
+                    .map(|index| Unpack::<u32>::unpack(&index))
+                    .collect::<Vec<_>>();
+                let uncle_indexes = get_block_transactions
+                    .uncle_indexes()
+                    .into_iter()
+                    .map(|index| Unpack::<u32>::unpack(&index))
+                    .collect::<Vec<_>>();
+                self.stats
+                    .write()
+                    .unwrap()
+                    .received_get_block_transactions
+                    .push((block_hash.clone(), indexes.clone()));
+                if self.behaviour.withhold_block_transactions {
+                    return;
+                }
+                let block = self.blocks.read().unwrap().get(&block_hash).cloned();
+                if let Some(block) = block {
+                    let message = build_block_transactions(&block, &indexes, &uncle_indexes);
+                    self.send(&self.relay_protocol, message.as_bytes());
+                    self.stats
+                        .write()
+                        .unwrap()
+                        .served_block_transactions
+                        .push(block_hash);
+                }
+            }
+            packed::RelayMessageUnion::GetBlockProposal(get_block_proposal) => {
+                let proposals = get_block_proposal
+                    .proposals()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                self.stats
+                    .write()
+                    .unwrap()
+                    .received_get_block_proposals
+                    .extend(proposals.iter().cloned());
+                if self.behaviour.withhold_block_proposals {
+                    return;
+                }
+                let transactions = self
+                    .transactions
+                    .iter()
+                    .filter(|transaction| proposals.contains(&transaction.proposal_short_id()))
+                    .cloned()
+                    .collect::<Vec<_>>();
+                if !transactions.is_empty() {
+                    let message = build_block_proposal(&transactions);
+                    self.send(&self.relay_protocol, message.as_bytes());
+                    self.stats
+                        .write()
+                        .unwrap()
+                        .served_block_proposals
+                        .extend(transactions.iter().map(|transaction| transaction.hash()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_sync(&self, data: Bytes) {
+        let message = match packed::SyncMessage::from_compatible_slice(&data) {
+            Ok(message) => message,
+            Err(err) => {
+                crate::error!("FakeRelayPeer received malformed SyncMessage: {:?}", err);
+                return;
+            }
+        };
+        if let packed::SyncMessageUnion::GetBlocks(get_blocks) = message.to_enum() {
+            for block_hash in get_blocks.block_hashes().into_iter() {
+                self.stats
+                    .write()
+                    .unwrap()
+                    .received_get_blocks
+                    .push(block_hash.clone());
+                if self.behaviour.withhold_sync_blocks {
+                    continue;
+                }
+                let block = self.blocks.read().unwrap().get(&block_hash).cloned();
+                if let Some(block) = block {
+                    let message = build_sync_send_block(&block);
+                    self.send(&SupportProtocols::Sync, message.as_bytes());
+                    self.stats
+                        .write()
+                        .unwrap()
+                        .served_sync_blocks
+                        .push(block_hash);
+                }
+            }
+        }
+    }
+
+    fn send(&self, protocol: &SupportProtocols, data: Bytes) {
+        if let Err(err) = self
+            .sender
+            .send_to_session(self.session_id, protocol.clone(), data)
+        {
+            crate::error!("FakeRelayPeer send message error: {}", err);
+        }
+    }
+}
@@ -0,0 +1,304 @@
+use super::message::{
+    build_sync_in_ibd, build_sync_send_block, build_sync_send_headers, network_identifier,
+};
+use super::{
+    Connector, ConnectorBuilder, ConnectorSender, SharedState, SimpleProtocolHandler,
+    SimpleServiceHandler, SupportProtocols,
+};
+use crate::Node;
+use ckb_types::{
+    core::{BlockNumber, BlockView, HeaderView},
+    packed::{self, Byte32},
+    prelude::*,
+};
+use p2p::SessionId;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::{sleep, JoinHandle};
+use std::time::Duration;
+
+// https://github.com/nervosnetwork/ckb/blob/v0.101.0/util/constant/src/sync.rs
+const MAX_HEADERS_LEN: usize = 2_000;
+
+/// FakeSyncPeer Builder
+///
+/// ```ignore
+/// let mut peer = FakeSyncPeerBuilder::from_node(source_node)
+///     .withhold_block(10)
+///     .serve_slowly(Duration::from_secs(1))
+///     .build();
+/// peer.connect(target_node)?;
+/// peer.announce(target_node)?;
+/// ```
+pub struct FakeSyncPeerBuilder {
+    chain: Vec<BlockView>,
+    behaviour: Behaviour,
+}
+
+/// A fake peer which serves a chain over the Sync protocol. It answers `GetHeaders` and
+/// `GetBlocks` from the connected nodes automatically, so the Sync messages are consumed by
+/// the peer rather than left in the connector's mailbox.
+pub struct FakeSyncPeer {
+    connector: Connector,
+    chain: Arc<Vec<BlockView>>,
+    behaviour: Arc<Behaviour>,
+    stats: Arc<RwLock<FakeSyncPeerStats>>,
+    stopped: Arc<AtomicBool>,
+    responders: Vec<JoinHandle<()>>,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Behaviour {
+    withheld_blocks: HashSet<BlockNumber>,
+    serve_delay: Option<Duration>,
+    headers: Option<Vec<HeaderView>>,
+    reply_in_ibd: bool,
+}
+
+/// What the fake peer has received and served.
+#[derive(Debug, Clone, Default)]
+pub struct FakeSyncPeerStats {
+    pub received_get_headers: usize,
+    pub requested_blocks: Vec<Byte32>,
+    pub served_blocks: Vec<Byte32>,
+    pub received_in_ibd: bool,
+}
+
+impl FakeSyncPeerBuilder {
+    /// `chain[i]` must be the block of number `i`, and `chain[0]` is the genesis block shared
+    /// with the nodes to connect.
+    pub fn new(chain: Vec<BlockView>) -> Self {
+        assert!(!chain.is_empty(), "chain should contain the genesis block");
+        Self {
+            chain,
+            behaviour: Default::default(),
+        }
+    }
+
+    /// Serve the main chain of `node`.
+    pub fn from_node(node: &Node) -> Self {
+        let chain = (0..=node.get_tip_block_number())
+            .map(|number| node.get_block_by_number(number))
+            .collect();
+        Self::new(chain)
+    }
+
+    /// Never serve the block of `number`.
+    pub fn withhold_block(mut self, number: BlockNumber) -> Self {
+        self.behaviour.withheld_blocks.insert(number);
+        self
+    }
+
+    /// Wait `delay` before answering every request.
+    pub fn serve_slowly(mut self, delay: Duration) -> Self {
+        self.behaviour.serve_delay = Some(delay);
+        self
+    }
+
+    /// Serve `headers` instead of the headers of the chain. `headers[i]` must be the header of
+    /// number `i`. The blocks served are still the blocks of the chain.
+    pub fn header_chain(mut self, headers: Vec<HeaderView>) -> Self {
+        assert!(!headers.is_empty(), "headers should contain the genesis");
+        self.behaviour.headers = Some(headers);
+        self
+    }
+
+    /// Answer `GetHeaders` with `InIBD`, like a node in IBD.
+    pub fn reply_in_ibd(mut self) -> Self {
+        self.behaviour.reply_in_ibd = true;
+        self
+    }
+
+    pub fn build(self) -> FakeSyncPeer {
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let connector = ConnectorBuilder::new()
+            .protocol_meta(
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true),
+            )
+            .protocol_meta(
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Identify)
+                    .build(false),
+            )
+            .build(SimpleServiceHandler::new(Arc::clone(&shared)), shared);
+        FakeSyncPeer {
+            connector,
+            chain: Arc::new(self.chain),
+            behaviour: Arc::new(self.behaviour),
+            stats: Default::default(),
+            stopped: Arc::new(AtomicBool::new(false)),
+            responders: Vec::new(),
+        }
+    }
+}
+
+impl FakeSyncPeer {
+    /// Connect to `node`, complete the identify handshake and start answering its Sync
+    /// requests.
+    pub fn connect(&mut self, node: &Node) -> Result<(), String> {
+        self.connector.connect(node)?;
+        let session = self.connector.get_session(node).ok_or_else(|| {
+            format!(
+                "The connection was disconnected to \"{}\"",
+                node.node_name()
+            )
+        })?;
+
+        let client_version = node.rpc_client().local_node_info().version;
+        self.connector.send_identify_message(
+            node,
+            &network_identifier(node),
+            &client_version,
+            vec![],
+            session.address.clone(),
+        )?;
+
+        let receiver = self
+            .connector
+            .shared()
+            .read()
+            .unwrap()
+            .get_protocol_receiver(&session.id, &SupportProtocols::Sync.protocol_id())
+            .ok_or_else(|| format!("Sync protocol to \"{}\" is not opened", node.node_name()))?;
+        let responder = Responder {
+            session_id: session.id,
+            sender: self.connector.sender(),
+            chain: Arc::clone(&self.chain),
+            behaviour: Arc::clone(&self.behaviour),
+            stats: Arc::clone(&self.stats),
+        };
+        let stopped = Arc::clone(&self.stopped);
+        let handle = ::std::thread::spawn(move || {
+            while !stopped.load(Ordering::SeqCst) {
+                match receiver.recv_timeout(Duration::from_millis(100)) {
+                    Ok(data) => responder.handle(data),
+                    Err(crossbeam::channel::RecvTimeoutError::Timeout) => continue,
+                    Err(crossbeam::channel::RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+        self.responders.push(handle);
+        Ok(())
+    }
+
+    /// Send the served headers after the genesis to `node`, as if the chain is newly
+    /// discovered. At most `MAX_HEADERS_LEN` headers are sent, the node requests the rest via
+    /// `GetHeaders`.
+    pub fn announce(&self, node: &Node) -> Result<(), String> {
+        let headers = served_headers(&self.chain, &self.behaviour);
+        let end = headers.len().min(MAX_HEADERS_LEN + 1);
+        let message = build_sync_send_headers(&headers[1..end]);
+        self.connector
+            .send(node, SupportProtocols::Sync, message.as_bytes())
+    }
+
+    pub fn stats(&self) -> FakeSyncPeerStats {
+        self.stats.read().unwrap().clone()
+    }
+
+    pub fn tip_header(&self) -> HeaderView {
+        self.chain.last().expect("checked").header()
+    }
+
+    pub fn connector(&self) -> &Connector {
+        &self.connector
+    }
+}
+
+impl Drop for FakeSyncPeer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        for handle in self.responders.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+// Answer the Sync requests of a session
+struct Responder {
+    session_id: SessionId,
+    sender: ConnectorSender,
+    chain: Arc<Vec<BlockView>>,
+    behaviour: Arc<Behaviour>,
+    stats: Arc<RwLock<FakeSyncPeerStats>>,
+}
+
+impl Responder {
+    fn handle(&self, data: p2p::bytes::Bytes) {
+        let message = match packed::SyncMessage::from_slice(&data) {
+            Ok(message) => message,
+            Err(err) => {
+                crate::error!("FakeSyncPeer received malformed SyncMessage: {:?}", err);
+                return;
+            }
+        };
+        if let Some(delay) = self.behaviour.serve_delay {
+            sleep(delay);
+        }
+        match message.to_enum() {
+            packed::SyncMessageUnion::GetHeaders(get_headers) => {
+                self.stats.write().unwrap().received_get_headers += 1;
+                if self.behaviour.reply_in_ibd {
+                    self.send(build_sync_in_ibd());
+                    return;
+                }
+                let headers = served_headers(&self.chain, &self.behaviour);
+                let locator = get_headers
+                    .block_locator_hashes()
+                    .into_iter()
+                    .collect::<Vec<_>>();
+                // The locator is ordered from the tip to the genesis, find the first known one
+                let start = locator
+                    .iter()
+                    .find_map(|hash| headers.iter().position(|header| &header.hash() == hash))
+                    .unwrap_or(0);
+                let hash_stop = get_headers.hash_stop();
+                let mut response = Vec::new();
+                for header in headers.iter().skip(start + 1).take(MAX_HEADERS_LEN) {
+                    response.push(header.clone());
+                    if header.hash() == hash_stop {
+                        break;
+                    }
+                }
+                self.send(build_sync_send_headers(&response));
+            }
+            packed::SyncMessageUnion::GetBlocks(get_blocks) => {
+                for block_hash in get_blocks.block_hashes().into_iter() {
+                    self.stats
+                        .write()
+                        .unwrap()
+                        .requested_blocks
+                        .push(block_hash.clone());
+                    let block = self.chain.iter().find(|block| block.hash() == block_hash);
+                    if let Some(block) = block {
+                        if self.behaviour.withheld_blocks.contains(&block.number()) {
+                            continue;
+                        }
+                        self.send(build_sync_send_block(block));
+                        self.stats.write().unwrap().served_blocks.push(block_hash);
+                    }
+                }
+            }
+            packed::SyncMessageUnion::InIBD(_) => {
+                self.stats.write().unwrap().received_in_ibd = true;
+            }
+            _ => {}
+        }
+    }
+
+    fn send(&self, message: packed::SyncMessage) {
+        if let Err(err) =
+            self.sender
+                .send_to_session(self.session_id, SupportProtocols::Sync, message.as_bytes())
+        {
+            crate::error!("FakeSyncPeer send message error: {}", err);
+        }
+    }
+}
+
+fn served_headers(chain: &[BlockView], behaviour: &Behaviour) -> Vec<HeaderView> {
+    match behaviour.headers {
+        Some(ref headers) => headers.clone(),
+        None => chain.iter().map(|block| block.header()).collect(),
+    }
+}
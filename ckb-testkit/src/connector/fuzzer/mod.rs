@@ -0,0 +1,311 @@
+//! A fuzzing harness which sends mutated frames to a node, each from a fresh connector.
+//!
+//! ```ignore
+//! let report = P2PFuzzer::new(SupportProtocols::Sync, seed)
+//!     .iterations(50)
+//!     .run(node)?;
+//! // Reproduce the 7th iteration
+//! P2PFuzzer::new(SupportProtocols::Sync, seed).replay(node, 7)?;
+//! ```
+mod mutator;
+mod rng;
+
+pub use mutator::{FuzzFrame, Mutation, Mutator};
+pub use rng::FuzzRng;
+
+use super::message::network_identifier;
+use super::{
+    Connector, ConnectorBuilder, SharedState, SimpleProtocolHandler, SimpleServiceHandler,
+    SupportProtocols,
+};
+use crate::util::wait_until;
+use crate::Node;
+use std::fs;
+use std::sync::{Arc, RwLock};
+
+/// What happened to the session after the node received a mutated frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FuzzOutcome {
+    Kept,
+    Dropped,
+    Banned,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzCase {
+    pub iteration: u64,
+    pub mutation: Mutation,
+    pub frame_len: usize,
+    pub expects_rejection: bool,
+    pub outcome: FuzzOutcome,
+}
+
+#[derive(Debug, Clone)]
+pub struct FuzzReport {
+    pub protocol: SupportProtocols,
+    pub seed: u64,
+    pub cases: Vec<FuzzCase>,
+}
+
+/// Send mutated frames under a protocol, then check that the node stays alive, drops the
+/// session or bans the peer when the frame is malformed, and does not panic.
+///
+/// The frames only depend on the seed, the iteration and the node's genesis block, so a
+/// failure is reproducible via [`P2PFuzzer::replay`].
+pub struct P2PFuzzer {
+    protocol: SupportProtocols,
+    seed: u64,
+    iterations: u64,
+    mutations: Vec<Mutation>,
+    observe_secs: u64,
+}
+
+impl P2PFuzzer {
+    pub fn new(protocol: SupportProtocols, seed: u64) -> Self {
+        let mutations = Mutation::all()
+            .into_iter()
+            .filter(|mutation| mutation.is_applicable(&protocol))
+            .collect();
+        Self {
+            protocol,
+            seed,
+            iterations: 20,
+            mutations,
+            observe_secs: 3,
+        }
+    }
+
+    pub fn iterations(mut self, iterations: u64) -> Self {
+        self.iterations = iterations;
+        self
+    }
+
+    /// Only use `mutations`, the ones not applicable to the protocol are ignored.
+    pub fn mutations(mut self, mutations: Vec<Mutation>) -> Self {
+        self.mutations = mutations
+            .into_iter()
+            .filter(|mutation| mutation.is_applicable(&self.protocol))
+            .collect();
+        assert!(
+            !self.mutations.is_empty(),
+            "no mutations applicable to protocol \"{}\"",
+            self.protocol.name()
+        );
+        self
+    }
+
+    /// How long to wait for the node to react to a frame, 3 seconds by default.
+    pub fn observe_secs(mut self, observe_secs: u64) -> Self {
+        self.observe_secs = observe_secs;
+        self
+    }
+
+    pub fn run(&self, node: &Node) -> Result<FuzzReport, String> {
+        let log_offset = fs::metadata(node.log_path())
+            .map(|metadata| metadata.len() as usize)
+            .unwrap_or(0);
+        let mutator = Mutator::new(self.protocol.clone(), node);
+        let mut cases = Vec::new();
+        for iteration in 0..self.iterations {
+            cases.push(self.run_iteration(node, &mutator, iteration)?);
+        }
+        check_panics(node, log_offset)?;
+        Ok(FuzzReport {
+            protocol: self.protocol.clone(),
+            seed: self.seed,
+            cases,
+        })
+    }
+
+    /// Run only the `iteration`-th iteration, to reproduce a failure reported by `run`.
+    pub fn replay(&self, node: &Node, iteration: u64) -> Result<FuzzCase, String> {
+        let log_offset = fs::metadata(node.log_path())
+            .map(|metadata| metadata.len() as usize)
+            .unwrap_or(0);
+        let mutator = Mutator::new(self.protocol.clone(), node);
+        let case = self.run_iteration(node, &mutator, iteration)?;
+        check_panics(node, log_offset)?;
+        Ok(case)
+    }
+
+    /// The frame sent at the `iteration`-th iteration.
+    pub fn generate(&self, mutator: &Mutator, iteration: u64) -> FuzzFrame {
+        let mut rng = FuzzRng::new(self.seed ^ iteration.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        let mutation = *rng.choose(&self.mutations);
+        mutator.mutate(mutation, &mut rng)
+    }
+
+    fn run_iteration(
+        &self,
+        node: &Node,
+        mutator: &Mutator,
+        iteration: u64,
+    ) -> Result<FuzzCase, String> {
+        let frame = self.generate(mutator, iteration);
+        let reproduction = format!(
+            "protocol: \"{}\", seed: {}, iteration: {}, mutation: {:?}, frame_len: {}, node.log: {}",
+            self.protocol.name(),
+            self.seed,
+            iteration,
+            frame.mutation,
+            frame.data.len(),
+            node.log_path().to_string_lossy()
+        );
+        crate::debug!("P2PFuzzer sends frame, {}", reproduction);
+
+        let mut connector = self.build_connector();
+        connector
+            .connect(node)
+            .map_err(|err| format!("{}, connect error: {}", reproduction, err))?;
+        if self.protocol.protocol_id() != SupportProtocols::Identify.protocol_id() {
+            let observed_address = connector
+                .get_session(node)
+                .map(|session| session.address)
+                .ok_or_else(|| format!("{}, disconnected before fuzzing", reproduction))?;
+            connector
+                .send_identify_message(
+                    node,
+                    &network_identifier(node),
+                    &node.rpc_client().local_node_info().version,
+                    vec![],
+                    observed_address,
+                )
+                .map_err(|err| format!("{}, {}", reproduction, err))?;
+        }
+        connector
+            .send_frame(node, self.protocol.clone(), frame.data.clone())
+            .map_err(|err| format!("{}, {}", reproduction, err))?;
+
+        let is_banned = || node.get_banned(&connector).is_some();
+        let is_dropped = || connector.get_session(node).is_none();
+        wait_until(self.observe_secs, || is_banned() || is_dropped());
+        let outcome = if is_banned() {
+            FuzzOutcome::Banned
+        } else if is_dropped() {
+            FuzzOutcome::Dropped
+        } else {
+            FuzzOutcome::Kept
+        };
+        if outcome == FuzzOutcome::Banned {
+            // Unban the connector so that the next one can connect
+            node.unban(&connector);
+        }
+
+        if node.rpc_client().inner().local_node_info().is_err() {
+            return Err(format!("{}, node is not alive", reproduction));
+        }
+        if frame.expects_rejection && outcome == FuzzOutcome::Kept {
+            return Err(format!(
+                "{}, expect the session dropped or the peer banned, but it is kept",
+                reproduction
+            ));
+        }
+        Ok(FuzzCase {
+            iteration,
+            mutation: frame.mutation,
+            frame_len: frame.data.len(),
+            expects_rejection: frame.expects_rejection,
+            outcome,
+        })
+    }
+
+    // The fuzzed protocol sends frames as is, with a codec allowing frames beyond
+    // `max_frame_length`
+    fn build_connector(&self) -> Connector {
+        let shared = Arc::new(RwLock::new(SharedState::new()));
+        let mut builder = ConnectorBuilder::new().protocol_meta(
+            SimpleProtocolHandler::new(Arc::clone(&shared), self.protocol.clone()).build_raw(),
+        );
+        if self.protocol.protocol_id() != SupportProtocols::Sync.protocol_id() {
+            builder = builder.protocol_meta(
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Sync).build(true),
+            );
+        }
+        if self.protocol.protocol_id() != SupportProtocols::Identify.protocol_id() {
+            builder = builder.protocol_meta(
+                SimpleProtocolHandler::new(Arc::clone(&shared), SupportProtocols::Identify)
+                    .build(false),
+            );
+        }
+        builder.build(SimpleServiceHandler::new(Arc::clone(&shared)), shared)
+    }
+}
+
+// Return an error if the node panicked since `log_offset`
+fn check_panics(node: &Node, log_offset: usize) -> Result<(), String> {
+    let log = fs::read(node.log_path()).unwrap_or_default();
+    let log = String::from_utf8_lossy(&log[log_offset.min(log.len())..]);
+    let panics = log
+        .lines()
+        .filter(|line| line.contains("panicked at"))
+        .collect::<Vec<_>>();
+    if panics.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "node panicked, node.log: {}, panics: {:?}",
+            node.log_path().to_string_lossy(),
+            panics
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connector::decompress;
+    use ckb_types::{
+        bytes::BytesMut,
+        core::{BlockBuilder, BlockView, TransactionBuilder},
+        packed,
+        prelude::*,
+    };
+
+    fn genesis() -> BlockView {
+        BlockBuilder::default()
+            .transaction(TransactionBuilder::default().build())
+            .build()
+    }
+
+    fn mutator(protocol: SupportProtocols) -> Mutator {
+        Mutator::from_genesis(protocol, &genesis(), "/ckb/fuzz", "0.101.0")
+    }
+
+    #[test]
+    fn same_seed_and_iteration_generate_same_frame() {
+        for protocol in [
+            SupportProtocols::Sync,
+            SupportProtocols::Relay,
+            SupportProtocols::Discovery,
+            SupportProtocols::Identify,
+            SupportProtocols::Ping,
+        ] {
+            let frames = |seed: u64| {
+                let fuzzer = P2PFuzzer::new(protocol.clone(), seed);
+                let mutator = mutator(protocol.clone());
+                (0..16)
+                    .map(|iteration| fuzzer.generate(&mutator, iteration))
+                    .collect::<Vec<_>>()
+            };
+            assert_eq!(frames(42), frames(42), "protocol: {}", protocol.name());
+            assert_ne!(frames(42), frames(43), "protocol: {}", protocol.name());
+        }
+    }
+
+    #[test]
+    fn inflated_item_count_fails_molecule_verification() {
+        for seed in 0..16 {
+            let mut rng = FuzzRng::new(seed);
+            let frame =
+                mutator(SupportProtocols::Sync).mutate(Mutation::InflatedItemCount, &mut rng);
+            let message = decompress(BytesMut::from(&frame.data[..])).unwrap();
+            assert!(frame.expects_rejection);
+            assert!(packed::SyncMessage::from_slice(&message).is_err());
+
+            let frame =
+                mutator(SupportProtocols::Relay).mutate(Mutation::InflatedItemCount, &mut rng);
+            let message = decompress(BytesMut::from(&frame.data[..])).unwrap();
+            assert!(packed::RelayMessage::from_slice(&message).is_err());
+        }
+    }
+}
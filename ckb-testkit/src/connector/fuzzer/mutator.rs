@@ -0,0 +1,381 @@
+use super::rng::FuzzRng;
+use crate::connector::message::{
+    build_alert, build_discovery_get_nodes, build_discovery_nodes, build_identify_message,
+    build_ping, build_pong, build_raw_alert, build_relay_transaction,
+    build_relay_transaction_hashes, build_sync_get_blocks, build_sync_get_headers,
+    build_sync_in_ibd, build_sync_send_block, build_sync_send_headers, build_time_message,
+    network_identifier,
+};
+use crate::connector::{compress, SupportProtocols};
+use crate::Node;
+use ckb_types::{bytes::Bytes, core::BlockView, packed, prelude::*};
+use p2p::multiaddr::Multiaddr;
+use snap::raw::Encoder as SnapEncoder;
+
+// https://github.com/nervosnetwork/ckb/blob/v0.101.0/network/src/compress.rs
+const COMPRESS_FLAG: u8 = 0b1000_0000;
+const MAX_UNCOMPRESSED_LEN: usize = 1 << 23;
+
+/// The ways to mutate a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mutation {
+    /// Cut a valid message at a random position.
+    Truncate,
+    /// Overwrite the item id of the message's union with an undefined one.
+    InvalidItemId,
+    /// Make the total size or a field offset of a molecule table inconsistent with the data.
+    CorruptHeader,
+    /// Flip a few random bits of a valid message. The result may still be valid.
+    BitFlip,
+    /// A well-formed message whose vector holds as many items as the frame allows.
+    OversizedVector,
+    /// Make the item count of a vector claim more items than the message holds.
+    InflatedItemCount,
+    /// Random bytes whose length is around the protocol's `max_frame_length`, either below or
+    /// above it.
+    NearMaxFrame,
+    /// Set the snappy flag, followed by a malformed snappy payload.
+    BadSnappy,
+}
+
+impl Mutation {
+    pub fn all() -> Vec<Mutation> {
+        vec![
+            Mutation::Truncate,
+            Mutation::InvalidItemId,
+            Mutation::CorruptHeader,
+            Mutation::BitFlip,
+            Mutation::OversizedVector,
+            Mutation::InflatedItemCount,
+            Mutation::NearMaxFrame,
+            Mutation::BadSnappy,
+        ]
+    }
+
+    pub fn is_applicable(&self, protocol: &SupportProtocols) -> bool {
+        match self {
+            Mutation::Truncate | Mutation::CorruptHeader | Mutation::BitFlip => {
+                is_molecule(protocol)
+            }
+            Mutation::InvalidItemId => has_union(protocol),
+            Mutation::OversizedVector => has_vector(protocol),
+            Mutation::InflatedItemCount => has_hash_vector(protocol),
+            Mutation::NearMaxFrame => true,
+            Mutation::BadSnappy => protocol.is_compressed(),
+        }
+    }
+}
+
+/// A mutated frame, ready to be sent as is.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzFrame {
+    pub mutation: Mutation,
+    pub data: Bytes,
+    /// Whether the node must drop the session or ban the peer on receiving this frame. It is
+    /// `false` when the frame may still be acceptable.
+    pub expects_rejection: bool,
+}
+
+/// Mutate the valid messages of a protocol with the knowledge of their molecule schemas.
+pub struct Mutator {
+    protocol: SupportProtocols,
+    seeds: Vec<Bytes>,
+    address: Multiaddr,
+}
+
+impl Mutator {
+    /// The valid messages to mutate are built from the genesis block of `node`.
+    pub fn new(protocol: SupportProtocols, node: &Node) -> Self {
+        Self::from_genesis(
+            protocol,
+            node.genesis_block(),
+            &network_identifier(node),
+            &node.rpc_client().local_node_info().version,
+        )
+    }
+
+    /// Like [`Mutator::new`], with the parts of the node the valid messages are built from.
+    pub(super) fn from_genesis(
+        protocol: SupportProtocols,
+        genesis: &BlockView,
+        identifier: &str,
+        version: &str,
+    ) -> Self {
+        let address: Multiaddr = "/ip4/127.0.0.1/tcp/8114".parse().unwrap();
+        let seeds = match protocol {
+            SupportProtocols::Sync => vec![
+                build_sync_get_headers(vec![genesis.hash()], Default::default()).as_bytes(),
+                build_sync_send_headers(&[genesis.header()]).as_bytes(),
+                build_sync_get_blocks(vec![genesis.hash()]).as_bytes(),
+                build_sync_send_block(genesis).as_bytes(),
+                build_sync_in_ibd().as_bytes(),
+            ],
+            SupportProtocols::Relay | SupportProtocols::RelayV2 => vec![
+                build_relay_transaction_hashes(vec![genesis.transactions()[0].hash()]).as_bytes(),
+                build_relay_transaction(&genesis.transactions()[0], 0).as_bytes(),
+            ],
+            SupportProtocols::Discovery => vec![
+                build_discovery_get_nodes(None, 1000, 1).as_bytes(),
+                build_discovery_nodes(false, vec![address.clone()]).as_bytes(),
+            ],
+            SupportProtocols::Identify => vec![build_identify_message(
+                identifier,
+                version,
+                vec![address.clone()],
+                address.clone(),
+            )
+            .as_bytes()],
+            SupportProtocols::Ping => vec![build_ping(0).as_bytes(), build_pong(0).as_bytes()],
+            SupportProtocols::Time => vec![build_time_message(0).as_bytes()],
+            SupportProtocols::Alert => {
+                vec![build_alert(build_raw_alert(1, 0, 0, 0, "fuzz"), &[]).as_bytes()]
+            }
+            SupportProtocols::DisconnectMessage => vec![Bytes::from_static(b"fuzz")],
+            SupportProtocols::Feeler => vec![Bytes::new()],
+        };
+        Self {
+            protocol,
+            seeds,
+            address,
+        }
+    }
+
+    /// Mutate a random valid message with `mutation`, which must be applicable to the
+    /// protocol.
+    pub fn mutate(&self, mutation: Mutation, rng: &mut FuzzRng) -> FuzzFrame {
+        assert!(
+            mutation.is_applicable(&self.protocol),
+            "{:?} is not applicable to protocol \"{}\"",
+            mutation,
+            self.protocol.name()
+        );
+        let seed = rng.choose(&self.seeds).to_vec();
+        let max_frame_length = self.protocol.max_frame_length();
+        let (data, expects_rejection) = match mutation {
+            Mutation::Truncate => {
+                let len = rng.gen_range(0, seed.len());
+                (self.frame(&seed[..len]), true)
+            }
+            Mutation::InvalidItemId => {
+                let mut message = seed;
+                let offset = self.union_offset(&message);
+                // Far beyond the number of items of any union
+                let item_id = rng.next_u32() | 0x100;
+                message[offset..offset + 4].copy_from_slice(&item_id.to_le_bytes());
+                (self.frame(&message), true)
+            }
+            Mutation::CorruptHeader => {
+                let mut message = seed;
+                let table_start = if self.is_union_message() { 4 } else { 0 };
+                let total_size = read_u32(&message, table_start);
+                let fields_count = if total_size > 4 {
+                    read_u32(&message, table_start + 4) / 4 - 1
+                } else {
+                    0
+                };
+                let position = rng.gen_range(0, fields_count as usize + 1);
+                let corrupted = if position == 0 && rng.gen_bool() {
+                    rng.gen_range(0, total_size as usize) as u32
+                } else {
+                    total_size + rng.gen_range(1, 1024) as u32
+                };
+                let offset = table_start + 4 * position;
+                message[offset..offset + 4].copy_from_slice(&corrupted.to_le_bytes());
+                (self.frame(&message), true)
+            }
+            Mutation::BitFlip => {
+                let mut message = seed;
+                for _ in 0..rng.gen_range(1, 8) {
+                    let bit = rng.gen_range(0, message.len() * 8);
+                    message[bit / 8] ^= 1 << (bit % 8);
+                }
+                (self.frame(&message), false)
+            }
+            Mutation::OversizedVector => (self.frame(&self.oversized(rng)), false),
+            Mutation::InflatedItemCount => (self.frame(&self.inflated(rng)), true),
+            Mutation::NearMaxFrame => {
+                let len = rng.gen_range(max_frame_length - 16, max_frame_length + 17);
+                let data = Bytes::from(rng.gen_bytes(len));
+                (data, len > max_frame_length || is_molecule(&self.protocol))
+            }
+            Mutation::BadSnappy => {
+                let mut data = vec![COMPRESS_FLAG];
+                match rng.gen_range(0, 3) {
+                    // Garbage
+                    0 => {
+                        let len = rng.gen_range(1, 256);
+                        data.extend(rng.gen_bytes(len));
+                    }
+                    // Claim an uncompressed length exceeding the limit
+                    1 => {
+                        let len = MAX_UNCOMPRESSED_LEN + rng.gen_range(1, 1 << 20);
+                        data.extend(encode_varint(len as u64));
+                        data.extend(rng.gen_bytes(16));
+                    }
+                    // Cut a valid snappy payload
+                    _ => {
+                        let compressed = SnapEncoder::new()
+                            .compress_vec(&seed)
+                            .expect("snappy compress");
+                        let len = rng.gen_range(1, compressed.len());
+                        data.extend_from_slice(&compressed[..len]);
+                    }
+                }
+                (Bytes::from(data), true)
+            }
+        };
+        FuzzFrame {
+            mutation,
+            data,
+            expects_rejection,
+        }
+    }
+
+    // Fill the frame with a vector of as many items as the protocol allows
+    fn oversized(&self, rng: &mut FuzzRng) -> Bytes {
+        let max_frame_length = self.protocol.max_frame_length();
+        let random_hashes = |rng: &mut FuzzRng, limit: usize| {
+            (0..rng.gen_range(limit / 2, limit))
+                .map(|_| packed::Byte32::from_slice(&rng.gen_bytes(32)).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let address_len = self.address.to_vec().len();
+        match self.protocol {
+            SupportProtocols::Sync => {
+                let hashes = random_hashes(rng, (max_frame_length - 64) / 32);
+                build_sync_get_blocks(hashes).as_bytes()
+            }
+            SupportProtocols::Relay | SupportProtocols::RelayV2 => {
+                let hashes = random_hashes(rng, (max_frame_length - 64) / 32);
+                build_relay_transaction_hashes(hashes).as_bytes()
+            }
+            SupportProtocols::Discovery => {
+                let limit = (max_frame_length - 64) / (address_len + 24);
+                let addresses = vec![self.address.clone(); rng.gen_range(limit / 2, limit)];
+                build_discovery_nodes(false, addresses).as_bytes()
+            }
+            SupportProtocols::Identify => {
+                let limit = (max_frame_length - 128) / (address_len + 8);
+                let addresses = vec![self.address.clone(); rng.gen_range(limit / 2, limit)];
+                let seed = packed::IdentifyMessage::from_slice(&self.seeds[0]).expect("seed");
+                let listen_addrs =
+                    build_identify_message("", "", addresses, self.address.clone()).listen_addrs();
+                seed.as_builder()
+                    .listen_addrs(listen_addrs)
+                    .build()
+                    .as_bytes()
+            }
+            SupportProtocols::Alert => {
+                let len = rng.gen_range((max_frame_length - 256) / 2, max_frame_length - 256);
+                let message = "A".repeat(len);
+                build_alert(build_raw_alert(1, 0, 0, 0, &message), &[]).as_bytes()
+            }
+            _ => unreachable!("checked by Mutation::is_applicable"),
+        }
+    }
+
+    // A vector of hashes whose item count exceeds the hashes it holds, so that the message
+    // fails the molecule verification
+    fn inflated(&self, rng: &mut FuzzRng) -> Bytes {
+        let hashes = (0..rng.gen_range(1, 8))
+            .map(|_| packed::Byte32::from_slice(&rng.gen_bytes(32)).unwrap())
+            .collect::<Vec<_>>();
+        let message = match self.protocol {
+            SupportProtocols::Sync => build_sync_get_blocks(hashes).as_bytes(),
+            SupportProtocols::Relay | SupportProtocols::RelayV2 => {
+                build_relay_transaction_hashes(hashes).as_bytes()
+            }
+            _ => unreachable!("checked by Mutation::is_applicable"),
+        };
+        // The union item id, then a table whose only field is the vector, i.e. `GetBlocks` or
+        // `RelayTransactionHashes`
+        let mut message = message.to_vec();
+        let vector_start = 4 + read_u32(&message, 8) as usize;
+        let count = read_u32(&message, vector_start) + rng.gen_range(1, 1 << 16) as u32;
+        message[vector_start..vector_start + 4].copy_from_slice(&count.to_le_bytes());
+        Bytes::from(message)
+    }
+
+    // Wrap the message into a frame as the protocol handler does
+    fn frame(&self, message: &[u8]) -> Bytes {
+        let message = Bytes::from(message.to_vec());
+        if self.protocol.is_compressed() {
+            compress(message)
+        } else {
+            message
+        }
+    }
+
+    // Sync and Relay messages are unions themselves
+    fn is_union_message(&self) -> bool {
+        matches!(
+            self.protocol,
+            SupportProtocols::Sync | SupportProtocols::Relay | SupportProtocols::RelayV2
+        )
+    }
+
+    // The position of the union item id in the message
+    fn union_offset(&self, message: &[u8]) -> usize {
+        if self.is_union_message() {
+            0
+        } else {
+            // DiscoveryMessage and PingMessage, whose first field is the payload union
+            read_u32(message, 4) as usize
+        }
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    let mut buf = [0u8; 4];
+    buf.copy_from_slice(&data[offset..offset + 4]);
+    u32::from_le_bytes(buf)
+}
+
+fn encode_varint(mut value: u64) -> Vec<u8> {
+    let mut encoded = Vec::new();
+    while value >= 0x80 {
+        encoded.push((value as u8) | 0x80);
+        value >>= 7;
+    }
+    encoded.push(value as u8);
+    encoded
+}
+
+// The messages of DisconnectMessage are plain strings and Feeler carries no messages
+fn is_molecule(protocol: &SupportProtocols) -> bool {
+    !matches!(
+        protocol,
+        SupportProtocols::DisconnectMessage | SupportProtocols::Feeler
+    )
+}
+
+fn has_union(protocol: &SupportProtocols) -> bool {
+    matches!(
+        protocol,
+        SupportProtocols::Sync
+            | SupportProtocols::Relay
+            | SupportProtocols::RelayV2
+            | SupportProtocols::Discovery
+            | SupportProtocols::Ping
+    )
+}
+
+// The messages built by `Mutator::inflated`
+fn has_hash_vector(protocol: &SupportProtocols) -> bool {
+    matches!(
+        protocol,
+        SupportProtocols::Sync | SupportProtocols::Relay | SupportProtocols::RelayV2
+    )
+}
+
+fn has_vector(protocol: &SupportProtocols) -> bool {
+    matches!(
+        protocol,
+        SupportProtocols::Sync
+            | SupportProtocols::Relay
+            | SupportProtocols::RelayV2
+            | SupportProtocols::Discovery
+            | SupportProtocols::Identify
+            | SupportProtocols::Alert
+    )
+}
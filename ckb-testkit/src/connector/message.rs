@@ -1,10 +1,24 @@
 //! A set of functions used to construct network messages.
+use crate::Node;
+use ckb_crypto::secp::Privkey;
+use ckb_hash::blake2b_256;
 use ckb_types::{
-    core::{Cycle, TransactionView},
+    bytes::Bytes,
+    core::{BlockView, Cycle, HeaderView, TransactionView},
     packed,
     prelude::*,
+    H256,
 };
 use p2p::multiaddr::Multiaddr;
+use std::collections::HashSet;
+
+/// The network identifier carried by `IdentifyMessage`, which must match the node's, e.g.
+/// `"/ckb_dev/92b197aa"`.
+pub fn network_identifier(node: &Node) -> String {
+    let consensus = node.consensus();
+    let genesis_hash = format!("{:x}", consensus.genesis_hash);
+    format!("/{}/{}", consensus.id, &genesis_hash[..8])
+}
 
 pub fn build_identify_message(
     network_identifier: &str,
@@ -37,7 +51,12 @@ pub fn build_identify_message(
         .listen_addrs({
             let to_vec = listening_addresses
                 .into_iter()
-                .map(|addr| packed::Address::from_slice(&addr.to_vec()).unwrap())
+                .map(|addr| {
+                    let bytes = packed::Bytes::new_builder()
+                        .set(addr.to_vec().into_iter().map(Into::into).collect())
+                        .build();
+                    packed::Address::new_builder().bytes(bytes).build()
+                })
                 .collect::<Vec<_>>();
             packed::AddressVec::new_builder().set(to_vec).build()
         })
@@ -80,6 +99,68 @@ pub fn build_relay_transaction_hashes(hashes: Vec<packed::Byte32>) -> packed::Re
         .build()
 }
 
+/// Build a compact block of `block`, prefilling the transactions of
+/// `prefilled_transactions_indexes`. The cellbase is always prefilled. Blocks with an
+/// extension are built into `CompactBlockV1`, sent as a `CompactBlock` with an extra field.
+pub fn build_compact_block(
+    block: &BlockView,
+    prefilled_transactions_indexes: &HashSet<usize>,
+) -> packed::RelayMessage {
+    let compact_block =
+        packed::CompactBlock::build_from_block(block, prefilled_transactions_indexes);
+    packed::RelayMessage::new_builder()
+        .set(compact_block)
+        .build()
+}
+
+/// Answer `GetBlockTransactions` with the transactions and uncles of `block` at the indexes.
+/// Out-of-range indexes are skipped.
+pub fn build_block_transactions(
+    block: &BlockView,
+    indexes: &[u32],
+    uncle_indexes: &[u32],
+) -> packed::RelayMessage {
+    let transactions = indexes
+        .iter()
+        .filter_map(|index| block.transaction(*index as usize))
+        .map(|transaction| transaction.data())
+        .collect::<Vec<_>>();
+    let uncles = uncle_indexes
+        .iter()
+        .filter_map(|index| block.data().uncles().get(*index as usize))
+        .collect::<Vec<_>>();
+    let block_transactions = packed::BlockTransactions::new_builder()
+        .block_hash(block.hash())
+        .transactions(
+            packed::TransactionVec::new_builder()
+                .set(transactions)
+                .build(),
+        )
+        .uncles(packed::UncleBlockVec::new_builder().set(uncles).build())
+        .build();
+    packed::RelayMessage::new_builder()
+        .set(block_transactions)
+        .build()
+}
+
+/// Answer `GetBlockProposal` with `transactions`.
+pub fn build_block_proposal(transactions: &[TransactionView]) -> packed::RelayMessage {
+    let transactions = transactions
+        .iter()
+        .map(|transaction| transaction.data())
+        .collect::<Vec<_>>();
+    let block_proposal = packed::BlockProposal::new_builder()
+        .transactions(
+            packed::TransactionVec::new_builder()
+                .set(transactions)
+                .build(),
+        )
+        .build();
+    packed::RelayMessage::new_builder()
+        .set(block_proposal)
+        .build()
+}
+
 pub fn build_discovery_get_nodes(
     listening_port: Option<u16>,
     max_nodes: u32,
@@ -135,3 +216,103 @@ pub fn build_discovery_nodes(
         .payload(discovery_payload)
         .build()
 }
+
+pub fn build_sync_get_headers(
+    block_locator_hashes: Vec<packed::Byte32>,
+    hash_stop: packed::Byte32,
+) -> packed::SyncMessage {
+    let get_headers = packed::GetHeaders::new_builder()
+        .block_locator_hashes(block_locator_hashes.pack())
+        .hash_stop(hash_stop)
+        .build();
+    packed::SyncMessage::new_builder().set(get_headers).build()
+}
+
+pub fn build_sync_send_headers(headers: &[HeaderView]) -> packed::SyncMessage {
+    let send_headers = packed::SendHeaders::new_builder()
+        .headers(
+            packed::HeaderVec::new_builder()
+                .set(headers.iter().map(|header| header.data()).collect())
+                .build(),
+        )
+        .build();
+    packed::SyncMessage::new_builder().set(send_headers).build()
+}
+
+pub fn build_sync_get_blocks(block_hashes: Vec<packed::Byte32>) -> packed::SyncMessage {
+    let get_blocks = packed::GetBlocks::new_builder()
+        .block_hashes(block_hashes.pack())
+        .build();
+    packed::SyncMessage::new_builder().set(get_blocks).build()
+}
+
+pub fn build_sync_send_block(block: &BlockView) -> packed::SyncMessage {
+    let send_block = packed::SendBlock::new_builder().block(block.data()).build();
+    packed::SyncMessage::new_builder().set(send_block).build()
+}
+
+pub fn build_sync_in_ibd() -> packed::SyncMessage {
+    packed::SyncMessage::new_builder()
+        .set(packed::InIBD::new_builder().build())
+        .build()
+}
+
+pub fn build_ping(nonce: u32) -> packed::PingMessage {
+    let ping = packed::Ping::new_builder().nonce(nonce.pack()).build();
+    packed::PingMessage::new_builder()
+        .payload(packed::PingPayload::new_builder().set(ping).build())
+        .build()
+}
+
+pub fn build_pong(nonce: u32) -> packed::PingMessage {
+    let pong = packed::Pong::new_builder().nonce(nonce.pack()).build();
+    packed::PingMessage::new_builder()
+        .payload(packed::PingPayload::new_builder().set(pong).build())
+        .build()
+}
+
+/// `timestamp` is in milliseconds. The Time protocol sends the bare `Time` struct.
+pub fn build_time_message(timestamp: u64) -> packed::Time {
+    packed::Time::new_builder()
+        .timestamp(timestamp.pack())
+        .build()
+}
+
+/// The node sends the reason as UTF-8 bytes right before disconnecting.
+pub fn build_disconnect_message(reason: &str) -> Bytes {
+    Bytes::from(reason.as_bytes().to_vec())
+}
+
+/// `notice_until` is a timestamp in milliseconds, the node ignores expired alerts.
+pub fn build_raw_alert(
+    id: u32,
+    cancel: u32,
+    priority: u32,
+    notice_until: u64,
+    message: &str,
+) -> packed::RawAlert {
+    packed::RawAlert::new_builder()
+        .id(id.pack())
+        .cancel(cancel.pack())
+        .priority(priority.pack())
+        .notice_until(notice_until.pack())
+        .message(message.pack())
+        .build()
+}
+
+/// Sign `raw_alert` with each of `privkeys`. The node accepts the alert only if enough
+/// signatures are from its `alert_signature.public_keys`.
+pub fn build_alert(raw_alert: packed::RawAlert, privkeys: &[&Privkey]) -> packed::Alert {
+    let alert_hash = H256::from(blake2b_256(raw_alert.as_slice()));
+    let signatures = privkeys
+        .iter()
+        .map(|privkey| {
+            let signature = privkey.sign_recoverable(&alert_hash).expect("sign alert");
+            Bytes::from(signature.serialize()).pack()
+        })
+        .collect::<Vec<packed::Bytes>>();
+    packed::Alert::new_builder()
+        .raw(raw_alert)
+        .signatures(signatures.pack())
+        .build()
+}
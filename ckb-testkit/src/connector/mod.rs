@@ -1,27 +1,55 @@
+mod capture;
 mod compress;
+mod decode;
+mod event;
 mod extension;
+mod fake_relay_peer;
+mod fake_sync_peer;
+mod fuzzer;
 pub mod message;
 mod shared;
 mod simple_protocol_handler;
 mod simple_service_handler;
 mod support_protocols;
+mod sync_attack;
 
-pub use compress::{compress, decompress};
+pub use capture::{pretty_print, read_capture, CaptureWriter, CapturedFrame, Direction, Replayer};
+pub use compress::{
+    build_decompression_bomb, compress, compress_with, decompress, Compression,
+    MAX_UNCOMPRESSED_LEN,
+};
+pub use decode::{decode_disconnect_message, ProtocolMessage};
+pub use event::{ConnectorErrorKind, ConnectorEvent, SessionClosed};
+pub use fake_relay_peer::{FakeRelayPeer, FakeRelayPeerBuilder, FakeRelayPeerStats};
+pub use fake_sync_peer::{FakeSyncPeer, FakeSyncPeerBuilder, FakeSyncPeerStats};
+pub use fuzzer::{
+    FuzzCase, FuzzFrame, FuzzOutcome, FuzzReport, FuzzRng, Mutation, Mutator, P2PFuzzer,
+};
 pub use shared::SharedState;
 pub use simple_protocol_handler::SimpleProtocolHandler;
 pub use simple_service_handler::SimpleServiceHandler;
 pub use support_protocols::SupportProtocols;
+pub use sync_attack::{observe_reaction, SyncAttack, SyncReaction};
 
 use crate::Node;
 use ckb_stop_handler::{SignalSender, StopHandler};
 use futures::prelude::*;
+use message::network_identifier;
 use p2p::{
-    builder::ServiceBuilder, bytes::Bytes, context::SessionContext, multiaddr::Multiaddr,
-    secio::SecioKeyPair, service::ProtocolMeta as P2PProtocolMeta, service::Service as P2PService,
-    service::ServiceControl as P2PServiceControl, service::TargetProtocol as P2PTargetProtocol,
-    traits::ServiceHandle as P2PServiceHandle, ProtocolId,
+    builder::ServiceBuilder,
+    bytes::Bytes,
+    context::SessionContext,
+    multiaddr::Multiaddr,
+    secio::{PeerId, SecioKeyPair},
+    service::ProtocolMeta as P2PProtocolMeta,
+    service::Service as P2PService,
+    service::ServiceControl as P2PServiceControl,
+    service::TargetProtocol as P2PTargetProtocol,
+    traits::ServiceHandle as P2PServiceHandle,
+    ProtocolId, SessionId,
 };
 use std::collections::HashSet;
+use std::path::PathBuf;
 use std::sync::{Arc, RwLock};
 use std::thread::sleep;
 use std::time::{Duration, Instant};
@@ -33,12 +61,15 @@ pub struct ConnectorBuilder {
     listening_addresses: Vec<Multiaddr>,
     // protocol metas
     protocol_metas: Vec<P2PProtocolMeta>,
+    // capture file
+    capture_path: Option<PathBuf>,
 }
 
 /// Connector is a fake node
 pub struct Connector {
     #[allow(dead_code)]
     key_pair: SecioKeyPair,
+    listening_addresses: Vec<Multiaddr>,
     shared: Arc<RwLock<SharedState>>,
     p2p_service_controller: P2PServiceControl,
     _stop_handler: StopHandler<tokio::sync::oneshot::Sender<()>>,
@@ -50,6 +81,7 @@ impl Default for ConnectorBuilder {
             key_pair: SecioKeyPair::secp256k1_generated(),
             listening_addresses: Vec::new(),
             protocol_metas: Vec::new(),
+            capture_path: None,
         }
     }
 }
@@ -74,6 +106,26 @@ impl ConnectorBuilder {
         self
     }
 
+    /// Add a [`SimpleProtocolHandler`] of every one of `protocols`, compressed as the node
+    /// does, see [`SupportProtocols::is_compressed`].
+    pub fn simple_protocols(
+        mut self,
+        shared: &Arc<RwLock<SharedState>>,
+        protocols: &[SupportProtocols],
+    ) -> Self {
+        for protocol in protocols {
+            let be_compressed = protocol.is_compressed();
+            self.protocol_metas.push(
+                SimpleProtocolHandler::new(Arc::clone(shared), protocol.clone())
+                    .build(be_compressed),
+            );
+        }
+        self
+    }
+
+    /// Listen on `listening_addresses`, so that nodes can dial the connector, see
+    /// [`Connector::accept_from`].
+    ///
     /// ```rust
     /// use super::util::find_available_port;
     ///
@@ -85,6 +137,13 @@ impl ConnectorBuilder {
         self
     }
 
+    /// Record every sent and received frame into the file at `path`, see [`read_capture`],
+    /// [`pretty_print`] and [`Replayer`].
+    pub fn capture_to<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.capture_path = Some(path.into());
+        self
+    }
+
     pub fn build<T>(self, service_handle: T, shared: Arc<RwLock<SharedState>>) -> Connector
     where
         T: P2PServiceHandle + Unpin + Send + 'static,
@@ -103,7 +162,18 @@ impl ConnectorBuilder {
             self.protocol_metas.iter().any(|protocol| protocol.id() == SupportProtocols::Sync.protocol_id() ),
             "Sync protocol is the most underlying protocol to establish connection and must be contained in protocols",
         );
+        if let Some(ref capture_path) = self.capture_path {
+            let capture = CaptureWriter::create(capture_path).unwrap_or_else(|err| {
+                panic!(
+                    "failed to create capture {}, error: {}",
+                    capture_path.display(),
+                    err
+                )
+            });
+            shared.write().unwrap().set_capture(capture);
+        }
         let listening_addresses = self.listening_addresses.clone();
+        let self_listening_addresses = self.listening_addresses.clone();
         let key_pair = self.key_pair.clone();
 
         // Start P2P Service and maintain the controller
@@ -137,6 +207,7 @@ impl ConnectorBuilder {
 
         Connector {
             key_pair,
+            listening_addresses: self_listening_addresses,
             shared,
             p2p_service_controller,
             _stop_handler: StopHandler::new(
@@ -177,6 +248,98 @@ impl Connector {
             .map_err(|err| format!("Connector dial error: {:?}", err))?;
 
         // Wait for all protocols connections establishment
+        self.wait_protocols_opened(node)
+    }
+
+    /// [`Connector::connect`] to `node`, then send the `IdentifyMessage` of the node's network,
+    /// observing the session's address, which the node requires before it keeps the other
+    /// protocols open.
+    pub fn connect_and_identify(&mut self, node: &Node) -> Result<(), String> {
+        self.connect(node)?;
+        let session = self.get_session(node).ok_or_else(|| {
+            format!(
+                "The connection was disconnected to \"{}\"",
+                node.node_name()
+            )
+        })?;
+        self.send_identify_message(
+            node,
+            &network_identifier(node),
+            &node.rpc_client().local_node_info().version,
+            self.listening_addresses.clone(),
+            session.address,
+        )
+    }
+
+    /// Make `node` dial this connector, and block until all protocols of the inbound session
+    /// opened. The connector must be listening.
+    pub fn accept_from(&mut self, node: &Node) -> Result<(), String> {
+        let listening_address = self.listening_addresses.first().ok_or_else(|| {
+            "Connector is not listening, see ConnectorBuilder::listening_addresses".to_string()
+        })?;
+        crate::info!(
+            "Connector make node \"{}\" dial {}",
+            node.node_name(),
+            listening_address
+        );
+        node.rpc_client()
+            .add_node(self.peer_id().to_base58(), listening_address.to_string());
+        self.wait_inbound(node)
+    }
+
+    /// Block until `node` dials this connector and all protocols of the inbound session opened.
+    pub fn wait_inbound(&self, node: &Node) -> Result<(), String> {
+        let start_time = Instant::now();
+        while start_time.elapsed() <= Duration::from_secs(10) {
+            if let Some(session) = self.get_session(node) {
+                if !session.ty.is_inbound() {
+                    return Err(format!(
+                        "Connector already has an outbound session to \"{}\"",
+                        node.node_name()
+                    ));
+                }
+                // The node opens only Identify on its outbound sessions, and opens the others
+                // after receiving our `IdentifyMessage`
+                self.wait_protocol_opened(node, &SupportProtocols::Identify)?;
+                self.send_identify_message(
+                    node,
+                    &network_identifier(node),
+                    &node.rpc_client().local_node_info().version,
+                    self.listening_addresses.clone(),
+                    session.address.clone(),
+                )?;
+                return self.wait_protocols_opened(node);
+            }
+            sleep(Duration::from_millis(100));
+        }
+        Err(format!(
+            "Connector is timeout when waiting inbound session from {}",
+            node.node_name()
+        ))
+    }
+
+    // Block until `protocol` of the session to `node` opened.
+    fn wait_protocol_opened(&self, node: &Node, protocol: &SupportProtocols) -> Result<(), String> {
+        let start_time = Instant::now();
+        while start_time.elapsed() <= Duration::from_secs(5) {
+            let opened = self
+                .get_opened_protocol_ids(node)
+                .map(|protocol_ids| protocol_ids.contains(&protocol.protocol_id()))
+                .unwrap_or(false);
+            if opened {
+                return Ok(());
+            }
+            sleep(Duration::from_millis(100));
+        }
+        Err(format!(
+            "Connector is timeout when waiting protocol \"{}\" opened to {}",
+            protocol.name(),
+            node.node_name()
+        ))
+    }
+
+    // Block until all protocols of the session to `node` opened.
+    fn wait_protocols_opened(&self, node: &Node) -> Result<(), String> {
         let start_time = Instant::now();
         let mut last_logging_time = Instant::now();
         while start_time.elapsed() <= Duration::from_secs(5) {
@@ -221,29 +384,73 @@ impl Connector {
         ))
     }
 
-    /// Send `data` through the protocol of the session
+    /// Send `data` through the protocol of the session. The message is compressed as the
+    /// node does if the protocol is compressed, see `SimpleProtocolHandler::build`.
     pub fn send(&self, node: &Node, protocol: SupportProtocols, data: Bytes) -> Result<(), String> {
-        let session = self.get_session(node).ok_or_else(|| {
+        let session = self.expect_session(node)?;
+        self.sender()
+            .send_to_session(session.id, protocol, data)
+            .map_err(|err| format!("{}, node: \"{}\"", err, node.node_name()))
+    }
+
+    /// Send `data` through the protocol of the session, encoded as `compression` specified
+    /// regardless of how the protocol is compressed. Used to send frames with edge-case
+    /// compress flags or snappy bodies. The protocol must be built via
+    /// `SimpleProtocolHandler::build_raw`.
+    pub fn send_with_compression(
+        &self,
+        node: &Node,
+        protocol: SupportProtocols,
+        data: Bytes,
+        compression: Compression,
+    ) -> Result<(), String> {
+        self.send_frame(node, protocol, compress_with(data, compression))
+    }
+
+    /// Send `frame` through the protocol of the session as-is, without compression nor the
+    /// compress flag byte. The frame is captured as a raw frame.
+    ///
+    /// The protocol must not be built as compressed via `SimpleProtocolHandler::build`, which
+    /// would compress the frame again, use `SimpleProtocolHandler::build_raw` instead.
+    pub fn send_frame(
+        &self,
+        node: &Node,
+        protocol: SupportProtocols,
+        frame: Bytes,
+    ) -> Result<(), String> {
+        let session = self.expect_session(node)?;
+        self.sender()
+            .send_frame_to_session(session.id, protocol, frame)
+            .map_err(|err| format!("{}, node: \"{}\"", err, node.node_name()))
+    }
+
+    /// Return a sender of this connector, which can be moved into other threads, e.g. to
+    /// answer the messages of a session. The sent messages are captured as `Connector::send`
+    /// does.
+    pub fn sender(&self) -> ConnectorSender {
+        ConnectorSender {
+            shared: Arc::clone(&self.shared),
+            controller: self.p2p_service_controller.clone(),
+        }
+    }
+
+    fn expect_session(&self, node: &Node) -> Result<SessionContext, String> {
+        self.get_session(node).ok_or_else(|| {
             format!(
                 "The connection was disconnected to \"{}\"",
                 node.node_name()
             )
-        })?;
-        self.p2p_service_controller
-            .send_message_to(session.id, protocol.protocol_id(), data)
-            .map_err(|err| {
-                format!(
-                    "Connector send message under protocol \"{}\" to \"{}\", error: {:?}",
-                    protocol.name(),
-                    node.node_name(),
-                    err
-                )
-            })
+        })
     }
 
-    /// Return the session corresponding to the `node` if connected.
+    /// Return the session corresponding to the `node` if connected, no matter the session is
+    /// inbound or outbound.
     pub fn get_session(&self, node: &Node) -> Option<SessionContext> {
         if let Ok(shared) = self.shared.read() {
+            let node_peer_id = node.node_id().parse::<PeerId>().expect("valid node id");
+            if let Some(session) = shared.get_session_by_peer_id(&node_peer_id) {
+                return Some(session);
+            }
             let node_connected_addr = node.p2p_address_with_node_id().parse().unwrap();
             return shared.get_session(&node_connected_addr);
         }
@@ -252,15 +459,123 @@ impl Connector {
 
     /// Return the opened protocols of the session corresponding to the `node` if connected
     pub fn get_opened_protocol_ids(&self, node: &Node) -> Option<Vec<ProtocolId>> {
+        let session = self.get_session(node)?;
         if let Ok(shared) = self.shared.read() {
-            let node_connected_addr = node.p2p_address_with_node_id().parse().unwrap();
-            return shared
-                .get_session(&node_connected_addr)
-                .and_then(|session| shared.get_opened_protocol_ids(&session.id));
+            return shared.get_opened_protocol_ids(&session.id);
         }
         unreachable!()
     }
 
+    /// Return the recorded events, in the order of occurrence. The log keeps the latest
+    /// 10,000 events.
+    pub fn events(&self) -> Vec<(Instant, ConnectorEvent)> {
+        self.shared.read().unwrap().events()
+    }
+
+    /// Remove and return the recorded events, in the order of occurrence. Note that
+    /// [`Connector::wait_session_closed`] and [`Connector::wait_feeler`] look up the log, so
+    /// do not drain the events they wait for.
+    pub fn drain_events(&self) -> Vec<(Instant, ConnectorEvent)> {
+        self.shared.write().unwrap().drain_events()
+    }
+
+    /// Block until the last session to `node` is closed, then return when and why.
+    pub fn wait_session_closed(
+        &self,
+        node: &Node,
+        timeout: Duration,
+    ) -> Result<SessionClosed, String> {
+        let node_peer_id = node.node_id().parse::<PeerId>().expect("valid node id");
+        let start_time = Instant::now();
+        while start_time.elapsed() <= timeout {
+            let events = self.events();
+            let opened = events.iter().rev().find_map(|(at, event)| match event {
+                ConnectorEvent::SessionOpened {
+                    session_id,
+                    peer_id: Some(peer_id),
+                    ..
+                } if peer_id == &node_peer_id => Some((*session_id, *at)),
+                _ => None,
+            });
+            let (session_id, opened_at) = opened.ok_or_else(|| {
+                format!("Connector never opened session to \"{}\"", node.node_name())
+            })?;
+            let closed_at = events.iter().find_map(|(at, event)| match event {
+                ConnectorEvent::SessionClosed { session_id: id, .. } if id == &session_id => {
+                    Some(*at)
+                }
+                _ => None,
+            });
+            if let Some(closed_at) = closed_at {
+                let disconnect_message = events.iter().find_map(|(_, event)| match event {
+                    ConnectorEvent::DisconnectMessage {
+                        session_id: id,
+                        reason,
+                    } if id == &session_id => Some(reason.clone()),
+                    _ => None,
+                });
+                return Ok(SessionClosed {
+                    session_id,
+                    opened_at,
+                    closed_at,
+                    disconnect_message,
+                });
+            }
+            sleep(Duration::from_millis(100));
+        }
+        Err(format!(
+            "Connector is timeout when waiting the session to \"{}\" closed",
+            node.node_name()
+        ))
+    }
+
+    /// Block until `node` feels this connector, i.e. dials it and opens the Feeler protocol,
+    /// which the node does to check the reachability of the addresses in its peer store, then
+    /// disconnects. Return the feeler session.
+    ///
+    /// The connector must be listening and built with Feeler, and the node must learn its
+    /// address, e.g. from the `DiscoveryMessage` of another peer, see
+    /// [`Connector::send_discovery_nodes`]. The node runs feelers every
+    /// `connect_outbound_interval_secs`.
+    pub fn wait_feeler(&self, node: &Node, timeout: Duration) -> Result<SessionId, String> {
+        let node_peer_id = node.node_id().parse::<PeerId>().expect("valid node id");
+        let feeler_id = SupportProtocols::Feeler.protocol_id();
+        let start_time = Instant::now();
+        while start_time.elapsed() <= timeout {
+            let events = self.events();
+            let from_node = |session_id: &SessionId| {
+                events.iter().any(|(_, event)| match event {
+                    ConnectorEvent::SessionOpened {
+                        session_id: id,
+                        peer_id: Some(peer_id),
+                        inbound: true,
+                        ..
+                    } => id == session_id && peer_id == &node_peer_id,
+                    _ => false,
+                })
+            };
+            let feeler = events
+                .iter()
+                .filter_map(|(_, event)| match event {
+                    ConnectorEvent::ProtocolOpened {
+                        session_id,
+                        protocol_id,
+                        ..
+                    } if protocol_id == &feeler_id => Some(*session_id),
+                    _ => None,
+                })
+                .find(from_node);
+            if let Some(session_id) = feeler {
+                return Ok(session_id);
+            }
+            sleep(Duration::from_millis(100));
+        }
+        Err(format!(
+            "Connector is timeout when waiting the feeler of \"{}\"",
+            node.node_name()
+        ))
+    }
+
     /// Return the shared state
     pub fn shared(&self) -> &Arc<RwLock<SharedState>> {
         &self.shared
@@ -274,4 +589,88 @@ impl Connector {
     pub fn key_pair(&self) -> &SecioKeyPair {
         &self.key_pair
     }
+
+    pub fn peer_id(&self) -> PeerId {
+        self.key_pair.public_key().peer_id()
+    }
+
+    pub fn listening_addresses(&self) -> &[Multiaddr] {
+        &self.listening_addresses
+    }
+}
+
+/// Send messages through the sessions of a connector, see [`Connector::sender`].
+#[derive(Clone)]
+pub struct ConnectorSender {
+    shared: Arc<RwLock<SharedState>>,
+    controller: P2PServiceControl,
+}
+
+impl ConnectorSender {
+    /// Send `data` through the protocol of the session, as `Connector::send` does.
+    pub fn send_to_session(
+        &self,
+        session_id: SessionId,
+        protocol: SupportProtocols,
+        data: Bytes,
+    ) -> Result<(), String> {
+        let frame = {
+            let shared = self.shared.read().unwrap();
+            // Raw protocol metas leave the compression to the connector
+            if shared.is_protocol_raw(&protocol.protocol_id()) && protocol.is_compressed() {
+                compress(data.clone())
+            } else {
+                data.clone()
+            }
+        };
+        self.send(session_id, protocol, false, &data, frame)
+    }
+
+    /// Send `frame` through the protocol of the session as-is, as `Connector::send_frame` does.
+    pub fn send_frame_to_session(
+        &self,
+        session_id: SessionId,
+        protocol: SupportProtocols,
+        frame: Bytes,
+    ) -> Result<(), String> {
+        if self
+            .shared
+            .read()
+            .unwrap()
+            .is_protocol_compressed(&protocol.protocol_id())
+        {
+            return Err(format!(
+                "Connector cannot send raw frames under protocol \"{}\" which is built as \
+                 compressed, build it via SimpleProtocolHandler::build_raw",
+                protocol.name()
+            ));
+        }
+        self.send(session_id, protocol, true, &frame, frame.clone())
+    }
+
+    fn send(
+        &self,
+        session_id: SessionId,
+        protocol: SupportProtocols,
+        raw: bool,
+        captured: &Bytes,
+        frame: Bytes,
+    ) -> Result<(), String> {
+        self.shared.write().unwrap().capture(
+            Direction::Outbound,
+            session_id,
+            protocol.protocol_id(),
+            raw,
+            captured,
+        );
+        self.controller
+            .send_message_to(session_id, protocol.protocol_id(), frame)
+            .map_err(|err| {
+                format!(
+                    "Connector send message under protocol \"{}\", error: {:?}",
+                    protocol.name(),
+                    err
+                )
+            })
+    }
 }
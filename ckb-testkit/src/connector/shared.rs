@@ -1,6 +1,16 @@
+use super::capture::{now_micros, CaptureWriter, CapturedFrame, Direction};
+use super::event::ConnectorEvent;
 use crossbeam::channel::{unbounded, Receiver, Sender};
-use p2p::{bytes::Bytes, context::SessionContext, multiaddr::Multiaddr, ProtocolId, SessionId};
-use std::collections::HashMap;
+use p2p::{
+    bytes::Bytes, context::SessionContext, multiaddr::Multiaddr, secio::PeerId, ProtocolId,
+    SessionId,
+};
+use std::collections::{BTreeSet, HashMap, HashSet, VecDeque};
+use std::time::Instant;
+
+/// The event log keeps the latest events only, so that a long-running connector does not grow
+/// without bound
+const MAX_EVENTS: usize = 10_000;
 
 /// Shared state between protocol handlers and service handler. As it is used across multiple
 /// protocols, it should be wrapped into `Arc<RwLock<SharedState>>`.
@@ -13,6 +23,20 @@ pub struct SharedState {
             HashMap<ProtocolId, (Sender<Bytes>, Receiver<Bytes>)>,
         ),
     >,
+    /// Sessions indexed by the remote peer id, #{ peer_id => [session.id] }. A peer may have
+    /// several sessions at once, like an inbound one and an outbound one.
+    peers: HashMap<PeerId, BTreeSet<SessionId>>,
+    /// The last message received under DisconnectMessage protocol from each peer, kept after
+    /// the session closed, #{ peer_id => message }
+    disconnect_messages: HashMap<PeerId, Bytes>,
+    /// Event log, in the order of occurrence, at most `MAX_EVENTS`
+    events: VecDeque<(Instant, ConnectorEvent)>,
+    /// Capture the frames of all sessions if set, see `ConnectorBuilder::capture_to`
+    capture: Option<CaptureWriter>,
+    /// Protocols whose metas compress the sent messages, see `SimpleProtocolHandler::build`
+    compressed_protocols: HashSet<ProtocolId>,
+    /// Protocols whose metas send frames as-is, see `SimpleProtocolHandler::build_raw`
+    raw_protocols: HashSet<ProtocolId>,
 }
 
 impl SharedState {
@@ -20,16 +44,24 @@ impl SharedState {
     pub fn new() -> Self {
         Self {
             session_manager: HashMap::new(),
+            peers: HashMap::new(),
+            disconnect_messages: HashMap::new(),
+            events: VecDeque::new(),
+            capture: None,
+            compressed_protocols: HashSet::new(),
+            raw_protocols: HashSet::new(),
         }
     }
 
     pub fn add_session(&mut self, session: SessionContext) {
+        self.index_peer(&session);
         self.session_manager
             .entry(session.id)
             .or_insert_with(|| (session.clone(), HashMap::new()));
     }
 
     pub fn remove_session(&mut self, session_id: &SessionId) -> Option<SessionContext> {
+        self.unindex_peer(session_id);
         self.session_manager
             .remove(session_id)
             .map(|(session, _mailbox)| session)
@@ -45,8 +77,19 @@ impl SharedState {
         None
     }
 
+    /// Get session by the remote peer id, no matter the session is inbound or outbound. If the
+    /// peer has several sessions, return the latest one.
+    pub fn get_session_by_peer_id(&self, peer_id: &PeerId) -> Option<SessionContext> {
+        self.peer_session_id(peer_id).and_then(|session_id| {
+            self.session_manager
+                .get(&session_id)
+                .map(|(session, _)| session.clone())
+        })
+    }
+
     pub fn add_protocol(&mut self, session: &SessionContext, protocol_id: ProtocolId) {
         let (protocol_mailbox_sender, protocol_mailbox_receiver) = unbounded::<Bytes>();
+        self.index_peer(session);
         self.session_manager
             .entry(session.id)
             .or_insert_with(|| (session.clone(), HashMap::new()))
@@ -113,4 +156,144 @@ impl SharedState {
             .map(|(session, _)| session.id)
             .collect()
     }
+
+    pub fn set_disconnect_message(&mut self, session: &SessionContext, data: Bytes) {
+        self.record_event(ConnectorEvent::DisconnectMessage {
+            session_id: session.id,
+            reason: String::from_utf8_lossy(&data).to_string(),
+        });
+        if let Some(ref remote_pubkey) = session.remote_pubkey {
+            self.disconnect_messages
+                .insert(remote_pubkey.peer_id(), data);
+        }
+    }
+
+    pub fn get_disconnect_message(&self, peer_id: &PeerId) -> Option<Bytes> {
+        self.disconnect_messages.get(peer_id).cloned()
+    }
+
+    pub fn record_event(&mut self, event: ConnectorEvent) {
+        crate::debug!("Connector event: {:?}", event);
+        if self.events.len() == MAX_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back((Instant::now(), event));
+    }
+
+    /// Return the recorded events, in the order of occurrence
+    pub fn events(&self) -> Vec<(Instant, ConnectorEvent)> {
+        self.events.iter().cloned().collect()
+    }
+
+    /// Remove and return the recorded events, in the order of occurrence
+    pub fn drain_events(&mut self) -> Vec<(Instant, ConnectorEvent)> {
+        self.events.drain(..).collect()
+    }
+
+    pub fn set_protocol_compressed(&mut self, protocol_id: ProtocolId) {
+        self.compressed_protocols.insert(protocol_id);
+    }
+
+    pub fn is_protocol_compressed(&self, protocol_id: &ProtocolId) -> bool {
+        self.compressed_protocols.contains(protocol_id)
+    }
+
+    pub fn set_protocol_raw(&mut self, protocol_id: ProtocolId) {
+        self.raw_protocols.insert(protocol_id);
+    }
+
+    pub fn is_protocol_raw(&self, protocol_id: &ProtocolId) -> bool {
+        self.raw_protocols.contains(protocol_id)
+    }
+
+    pub fn set_capture(&mut self, capture: CaptureWriter) {
+        self.capture = Some(capture);
+    }
+
+    /// Append the frame into the capture file if capturing
+    pub fn capture(
+        &mut self,
+        direction: Direction,
+        session_id: SessionId,
+        protocol_id: ProtocolId,
+        raw: bool,
+        data: &Bytes,
+    ) {
+        if let Some(ref mut capture) = self.capture {
+            let frame = CapturedFrame {
+                timestamp: now_micros(),
+                direction,
+                session_id,
+                protocol_id,
+                raw,
+                data: data.clone(),
+            };
+            if let Err(err) = capture.write(&frame) {
+                crate::error!("failed to capture frame, error: {}", err);
+            }
+        }
+    }
+
+    fn index_peer(&mut self, session: &SessionContext) {
+        if let Some(ref remote_pubkey) = session.remote_pubkey {
+            self.insert_peer_session(remote_pubkey.peer_id(), session.id);
+        }
+    }
+
+    fn insert_peer_session(&mut self, peer_id: PeerId, session_id: SessionId) {
+        self.peers.entry(peer_id).or_default().insert(session_id);
+    }
+
+    fn unindex_peer(&mut self, session_id: &SessionId) {
+        self.peers.retain(|_, session_ids| {
+            session_ids.remove(session_id);
+            !session_ids.is_empty()
+        });
+    }
+
+    // Session ids grow monotonically, so the largest one is the latest session
+    fn peer_session_id(&self, peer_id: &PeerId) -> Option<SessionId> {
+        self.peers
+            .get(peer_id)
+            .and_then(|session_ids| session_ids.iter().next_back().copied())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_log_keeps_the_latest_events() {
+        let mut shared = SharedState::new();
+        for port in 0..MAX_EVENTS + 2 {
+            let address: Multiaddr = format!("/ip4/127.0.0.1/tcp/{}", port).parse().unwrap();
+            shared.record_event(ConnectorEvent::ListenStarted { address });
+        }
+        let events = shared.drain_events();
+        assert_eq!(events.len(), MAX_EVENTS);
+        match &events[0].1 {
+            ConnectorEvent::ListenStarted { address } => {
+                assert_eq!(address.to_string(), "/ip4/127.0.0.1/tcp/2")
+            }
+            event => panic!("unexpected event {:?}", event),
+        }
+        assert!(shared.events().is_empty());
+    }
+
+    #[test]
+    fn closing_one_of_two_sessions_keeps_the_peer_indexed() {
+        let mut shared = SharedState::new();
+        let peer_id = PeerId::random();
+        shared.insert_peer_session(peer_id.clone(), SessionId::new(1));
+        shared.insert_peer_session(peer_id.clone(), SessionId::new(2));
+        assert_eq!(shared.peer_session_id(&peer_id), Some(SessionId::new(2)));
+
+        shared.remove_session(&SessionId::new(2));
+        assert_eq!(shared.peer_session_id(&peer_id), Some(SessionId::new(1)));
+
+        shared.remove_session(&SessionId::new(1));
+        assert_eq!(shared.peer_session_id(&peer_id), None);
+        assert!(shared.peers.is_empty());
+    }
 }
@@ -1,4 +1,6 @@
+use super::capture::Direction;
 use super::compress::{compress, decompress};
+use super::event::ConnectorEvent;
 use super::SharedState;
 use super::SupportProtocols;
 use p2p::{
@@ -9,6 +11,7 @@ use p2p::{
     traits::ServiceProtocol as P2PServiceProtocol,
 };
 use std::sync::{Arc, RwLock};
+use tokio_util::codec::length_delimited;
 
 /// Simple protocol handler which implements tentacle's
 /// [`P2PServiceProtocol`](https://github.com/nervosnetwork/tentacle/blob/master/tentacle/src/traits.rs#L57-L77)
@@ -22,9 +25,15 @@ impl SimpleProtocolHandler {
         Self { shared, protocol }
     }
 
+    /// Build the protocol meta. If `be_compressed`, the sent messages are compressed and the
+    /// received frames are decompressed, as the node does.
     pub fn build(self, be_compressed: bool) -> P2PProtocolMeta {
         let meta_builder: P2PMetaBuilder = self.protocol.clone().into();
         if be_compressed {
+            self.shared
+                .write()
+                .unwrap()
+                .set_protocol_compressed(self.protocol.protocol_id());
             meta_builder
                 .before_send(compress)
                 .before_receive(|| Some(Box::new(decompress)))
@@ -36,12 +45,46 @@ impl SimpleProtocolHandler {
                 .build()
         }
     }
+
+    /// Build the protocol meta which sends frames as-is, for `Connector::send_frame` to inject
+    /// frames with edge-case compress flags or beyond `max_frame_length`. The codec allows
+    /// frames up to twice `max_frame_length`.
+    ///
+    /// The messages sent via `Connector::send` are still compressed by the connector and the
+    /// received frames decompressed if the protocol is compressed, see
+    /// [`SupportProtocols::is_compressed`].
+    pub fn build_raw(self) -> P2PProtocolMeta {
+        let max_frame_length = self.protocol.max_frame_length() * 2;
+        let be_compressed = self.protocol.is_compressed();
+        self.shared
+            .write()
+            .unwrap()
+            .set_protocol_raw(self.protocol.protocol_id());
+        let meta_builder: P2PMetaBuilder = self.protocol.clone().into();
+        let meta_builder = meta_builder.codec(move || {
+            Box::new(
+                length_delimited::Builder::new()
+                    .max_frame_length(max_frame_length)
+                    .new_codec(),
+            )
+        });
+        if be_compressed {
+            meta_builder
+                .before_receive(|| Some(Box::new(decompress)))
+                .service_handle(move || P2PProtocolHandle::Callback(Box::new(self)))
+                .build()
+        } else {
+            meta_builder
+                .service_handle(move || P2PProtocolHandle::Callback(Box::new(self)))
+                .build()
+        }
+    }
 }
 
 impl P2PServiceProtocol for SimpleProtocolHandler {
     fn init(&mut self, _context: &mut ProtocolContext) {}
 
-    fn connected(&mut self, context: ProtocolContextMutRef, _protocol_version: &str) {
+    fn connected(&mut self, context: ProtocolContextMutRef, protocol_version: &str) {
         crate::debug!(
             "SimpleProtocolHandler connected, protocol: {}, session: {:?}",
             self.protocol.name(),
@@ -49,6 +92,11 @@ impl P2PServiceProtocol for SimpleProtocolHandler {
         );
         if let Ok(mut shared) = self.shared.write() {
             shared.add_protocol(context.session, context.proto_id);
+            shared.record_event(ConnectorEvent::ProtocolOpened {
+                session_id: context.session.id,
+                protocol_id: context.proto_id,
+                version: protocol_version.to_string(),
+            });
         }
     }
 
@@ -60,6 +108,10 @@ impl P2PServiceProtocol for SimpleProtocolHandler {
         );
         if let Ok(mut shared) = self.shared.write() {
             shared.remove_protocol(&context.session.id, &context.proto_id());
+            shared.record_event(ConnectorEvent::ProtocolClosed {
+                session_id: context.session.id,
+                protocol_id: context.proto_id(),
+            });
         }
     }
 
@@ -69,7 +121,17 @@ impl P2PServiceProtocol for SimpleProtocolHandler {
             self.protocol.name(),
             context.session
         );
-        if let Ok(shared) = self.shared.write() {
+        if let Ok(mut shared) = self.shared.write() {
+            shared.capture(
+                Direction::Inbound,
+                context.session.id,
+                context.proto_id(),
+                false,
+                &data,
+            );
+            if context.proto_id() == SupportProtocols::DisconnectMessage.protocol_id() {
+                shared.set_disconnect_message(context.session, data.clone());
+            }
             let sender = shared
                 .get_protocol_sender(&context.session.id, &context.proto_id())
                 .unwrap_or_else(|| {
@@ -1,3 +1,4 @@
+use super::event::{ConnectorErrorKind, ConnectorEvent};
 use super::SharedState;
 use p2p::{
     context::ServiceContext as P2PServiceContext, service::ServiceError as P2PServiceError,
@@ -6,7 +7,7 @@ use p2p::{
 use std::sync::{Arc, RwLock};
 
 /// TestServiceHandler is an implementation of `P2PServiceHandle` which handle service-wise
-/// events and errors.
+/// events and errors. All of them are recorded into the event log of `SharedState`.
 #[derive(Clone)]
 pub struct SimpleServiceHandler {
     shared: Arc<RwLock<SharedState>>,
@@ -22,6 +23,25 @@ impl P2PServiceHandle for SimpleServiceHandler {
     /// Handling runtime errors
     fn handle_error(&mut self, _control: &mut P2PServiceContext, error: P2PServiceError) {
         crate::error!("TestServiceHandler detect error: {:?}", error);
+        let kind = match error {
+            P2PServiceError::DialerError { ref address, .. } => ConnectorErrorKind::Dialer {
+                address: address.clone(),
+            },
+            P2PServiceError::ListenError { ref address, .. } => ConnectorErrorKind::Listen {
+                address: address.clone(),
+            },
+            P2PServiceError::ProtocolError { id, proto_id, .. } => ConnectorErrorKind::Protocol {
+                session_id: id,
+                protocol_id: proto_id,
+            },
+            _ => ConnectorErrorKind::Other,
+        };
+        let _ = self.shared.write().map(|mut shared| {
+            shared.record_event(ConnectorEvent::Error {
+                kind,
+                message: format!("{:?}", error),
+            })
+        });
     }
 
     /// Handling session establishment and disconnection events
@@ -31,22 +51,46 @@ impl P2PServiceHandle for SimpleServiceHandler {
                 session_context: session,
             } => {
                 crate::debug!("TestServiceHandler open session: {:?}", session);
-                let _ = self
-                    .shared
-                    .write()
-                    .map(|mut shared| shared.add_session(session.as_ref().to_owned()));
+                let _ = self.shared.write().map(|mut shared| {
+                    shared.add_session(session.as_ref().to_owned());
+                    shared.record_event(ConnectorEvent::SessionOpened {
+                        session_id: session.id,
+                        peer_id: session
+                            .remote_pubkey
+                            .as_ref()
+                            .map(|pubkey| pubkey.peer_id()),
+                        address: session.address.clone(),
+                        inbound: session.ty.is_inbound(),
+                    });
+                });
             }
             P2PServiceEvent::SessionClose {
                 session_context: session,
             } => {
                 crate::debug!("TestServiceHandler close session: {:?}", session);
-                let _ = self
-                    .shared
-                    .write()
-                    .map(|mut shared| shared.remove_session(&session.id));
+                let _ = self.shared.write().map(|mut shared| {
+                    shared.remove_session(&session.id);
+                    shared.record_event(ConnectorEvent::SessionClosed {
+                        session_id: session.id,
+                        peer_id: session
+                            .remote_pubkey
+                            .as_ref()
+                            .map(|pubkey| pubkey.peer_id()),
+                        address: session.address.clone(),
+                    });
+                });
             }
-            _ => {
-                unimplemented!()
+            P2PServiceEvent::ListenStarted { address } => {
+                crate::debug!("TestServiceHandler listen started: {}", address);
+                let _ = self.shared.write().map(|mut shared| {
+                    shared.record_event(ConnectorEvent::ListenStarted { address })
+                });
+            }
+            P2PServiceEvent::ListenClose { address } => {
+                crate::debug!("TestServiceHandler listen closed: {}", address);
+                let _ = self.shared.write().map(|mut shared| {
+                    shared.record_event(ConnectorEvent::ListenClosed { address })
+                });
             }
         }
     }
@@ -69,6 +69,42 @@ impl SupportProtocols {
         .into()
     }
 
+    /// All supported protocols
+    pub fn all() -> Vec<SupportProtocols> {
+        vec![
+            SupportProtocols::Ping,
+            SupportProtocols::Discovery,
+            SupportProtocols::Identify,
+            SupportProtocols::Feeler,
+            SupportProtocols::DisconnectMessage,
+            SupportProtocols::Sync,
+            SupportProtocols::Relay,
+            SupportProtocols::RelayV2,
+            SupportProtocols::Time,
+            SupportProtocols::Alert,
+        ]
+    }
+
+    /// Look up the protocol by id
+    pub fn from_protocol_id(protocol_id: ProtocolId) -> Option<SupportProtocols> {
+        SupportProtocols::all()
+            .into_iter()
+            .find(|protocol| protocol.protocol_id() == protocol_id)
+    }
+
+    /// Whether the node compresses the messages of this protocol, i.e. the protocols
+    /// registered as `CKBProtocol` by the node
+    pub fn is_compressed(&self) -> bool {
+        matches!(
+            self,
+            SupportProtocols::Sync
+                | SupportProtocols::Relay
+                | SupportProtocols::RelayV2
+                | SupportProtocols::Time
+                | SupportProtocols::Alert
+        )
+    }
+
     /// Protocol name
     pub fn name(&self) -> String {
         match self {
@@ -0,0 +1,212 @@
+//! A catalogue of adversarial Sync scenarios, played by a connected [`Connector`] against a
+//! node.
+//!
+//! ```ignore
+//! let attack = SyncAttack::InvalidCompactTarget;
+//! attack.launch(&connector, node)?;
+//! assert_eq!(attack.expected_reaction(), observe_reaction(&connector, node, timeout));
+//! ```
+//!
+//! The crafted headers extend the node's chain within the current epoch, which holds for the
+//! chain specs using `Dummy` PoW with a long `genesis_epoch_length`.
+use super::message::{build_sync_in_ibd, build_sync_send_headers};
+use super::{Connector, SupportProtocols};
+use crate::util::wait_until;
+use crate::Node;
+use ckb_types::{
+    core::{BlockNumber, EpochNumberWithFraction, HeaderView},
+    packed,
+    prelude::*,
+};
+use std::fmt;
+use std::time::Duration;
+
+/// How a node reacts to a scenario.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncReaction {
+    /// The session is kept
+    Ignore,
+    /// The session is dropped without banning
+    Disconnect,
+    /// The connector is banned
+    Ban,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SyncAttack {
+    /// `SendHeaders` with a header extending the tip, whose nonce does not satisfy its compact
+    /// target. Only meaningful under a real PoW function, `Dummy` accepts any nonce.
+    InvalidPow,
+    /// `SendHeaders` with a header extending the tip, whose compact target differs from the
+    /// epoch's
+    InvalidCompactTarget,
+    /// `SendHeaders` with a header extending the tip, which enters the next epoch before the
+    /// current one ends
+    SkipEpochTransition,
+    /// `SendHeaders` with a valid fork of `length` headers from the block of `fork_point`,
+    /// which has less work than the node's chain
+    LowWorkFork {
+        fork_point: BlockNumber,
+        length: usize,
+    },
+    /// `SendHeaders` with valid linked headers whose first parent is unknown to the node
+    UnconnectedHeaders,
+    /// `SendHeaders` with headers which do not link to each other
+    DiscontinuousHeaders,
+    /// Answer the node's `GetHeaders` with `InIBD`, up to `times` times
+    EndlessInIbd { times: usize },
+}
+
+impl SyncAttack {
+    /// The reaction of a node to a scenario from an inbound peer.
+    pub fn expected_reaction(&self) -> SyncReaction {
+        match self {
+            SyncAttack::InvalidPow
+            | SyncAttack::InvalidCompactTarget
+            | SyncAttack::SkipEpochTransition
+            | SyncAttack::UnconnectedHeaders
+            | SyncAttack::DiscontinuousHeaders => SyncReaction::Ban,
+            SyncAttack::LowWorkFork { .. } => SyncReaction::Ignore,
+            SyncAttack::EndlessInIbd { .. } => SyncReaction::Disconnect,
+        }
+    }
+
+    /// Play the scenario through `connector`, which has completed the identify handshake with
+    /// `node`. Return once the messages are sent; being dropped halfway is not an error.
+    pub fn launch(&self, connector: &Connector, node: &Node) -> Result<(), String> {
+        let tip = node.get_tip_block().header();
+        match self {
+            SyncAttack::InvalidPow => {
+                let header = build_child_header(&tip)?
+                    .as_advanced_builder()
+                    .nonce(u128::MAX.pack())
+                    .build();
+                send_headers(connector, node, &[header])
+            }
+            SyncAttack::InvalidCompactTarget => {
+                let header = build_child_header(&tip)?
+                    .as_advanced_builder()
+                    .compact_target((tip.compact_target() ^ 1).pack())
+                    .build();
+                send_headers(connector, node, &[header])
+            }
+            SyncAttack::SkipEpochTransition => {
+                let header = build_child_header(&tip)?;
+                let next_epoch = EpochNumberWithFraction::new(
+                    header.epoch().number() + 1,
+                    0,
+                    header.epoch().length(),
+                );
+                let header = header
+                    .as_advanced_builder()
+                    .epoch(next_epoch.pack())
+                    .build();
+                send_headers(connector, node, &[header])
+            }
+            SyncAttack::LowWorkFork { fork_point, length } => {
+                if fork_point + *length as BlockNumber >= tip.number() {
+                    return Err(format!(
+                        "fork from {} of length {} is not low-work against tip {}",
+                        fork_point,
+                        length,
+                        tip.number()
+                    ));
+                }
+                let fork_parent = node.get_header_by_number(*fork_point);
+                let headers = build_header_chain(&fork_parent, *length)?;
+                send_headers(connector, node, &headers)
+            }
+            SyncAttack::UnconnectedHeaders => {
+                let headers = build_header_chain(&tip, 3)?;
+                send_headers(connector, node, &headers[1..])
+            }
+            SyncAttack::DiscontinuousHeaders => {
+                let mut headers = build_header_chain(&tip, 3)?;
+                headers.swap(1, 2);
+                send_headers(connector, node, &headers)
+            }
+            SyncAttack::EndlessInIbd { times } => {
+                for _ in 0..*times {
+                    let get_headers = connector.expect_message(
+                        node,
+                        &SupportProtocols::Sync,
+                        Duration::from_secs(10),
+                        |message: &packed::SyncMessageUnion| {
+                            matches!(message, packed::SyncMessageUnion::GetHeaders(_))
+                        },
+                    );
+                    if connector.get_session(node).is_none() {
+                        break;
+                    }
+                    get_headers?;
+                    connector.send(node, SupportProtocols::Sync, build_sync_in_ibd().as_bytes())?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for SyncAttack {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncAttack::LowWorkFork { fork_point, length } => {
+                write!(f, "LowWorkFork({}, {})", fork_point, length)
+            }
+            SyncAttack::EndlessInIbd { times } => write!(f, "EndlessInIbd({})", times),
+            _ => write!(f, "{:?}", self),
+        }
+    }
+}
+
+/// Wait up to `timeout` for the node to drop or ban the connector.
+pub fn observe_reaction(connector: &Connector, node: &Node, timeout: Duration) -> SyncReaction {
+    wait_until(timeout.as_secs(), || {
+        node.get_banned(connector).is_some() || connector.get_session(node).is_none()
+    });
+    if node.get_banned(connector).is_some() {
+        SyncReaction::Ban
+    } else if connector.get_session(node).is_none() {
+        SyncReaction::Disconnect
+    } else {
+        SyncReaction::Ignore
+    }
+}
+
+fn send_headers(connector: &Connector, node: &Node, headers: &[HeaderView]) -> Result<(), String> {
+    connector.send(
+        node,
+        SupportProtocols::Sync,
+        build_sync_send_headers(headers).as_bytes(),
+    )
+}
+
+// A valid child header under `Dummy` PoW, in the same epoch as `parent`
+fn build_child_header(parent: &HeaderView) -> Result<HeaderView, String> {
+    let epoch = parent.epoch();
+    if epoch.index() + 1 >= epoch.length() {
+        return Err(format!(
+            "crafting headers across epochs is unsupported, parent epoch: {}",
+            epoch
+        ));
+    }
+    let child_epoch =
+        EpochNumberWithFraction::new(epoch.number(), epoch.index() + 1, epoch.length());
+    Ok(HeaderView::new_advanced_builder()
+        .version(parent.version().pack())
+        .parent_hash(parent.hash())
+        .number((parent.number() + 1).pack())
+        .epoch(child_epoch.pack())
+        .timestamp((parent.timestamp() + 1).pack())
+        .compact_target(parent.compact_target().pack())
+        .build())
+}
+
+fn build_header_chain(parent: &HeaderView, length: usize) -> Result<Vec<HeaderView>, String> {
+    let mut headers: Vec<HeaderView> = Vec::with_capacity(length);
+    for _ in 0..length {
+        let header = build_child_header(headers.last().unwrap_or(parent))?;
+        headers.push(header);
+    }
+    Ok(headers)
+}
@@ -0,0 +1,525 @@
+use crate::{
+    always_success_collector, BuildInstruction, CellCollector, Node, TxBuilder, User,
+    TYPE_ID_CODE_HASH,
+};
+use ckb_hash::new_blake2b;
+use ckb_types::{
+    core::{cell::CellMeta, Capacity, DepType, ScriptHashType, TransactionView},
+    packed::{self, Byte32, CellDep, CellInput, CellOutput, OutPoint, OutPointVec, Script},
+    prelude::*,
+    H256,
+};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Deploy named cells onto a node, typically script code cells and dep groups of them.
+///
+/// The deploy transactions are paid by always-success cells, or by a [`Payer`] via the
+/// `*_paid_by` methods. Every deploy transaction is proposed at `tip + 1` and committed at
+/// `tip + 3`, which keeps the heights of the following blocks predictable, unless
+/// [`Deployer::commit_via_tx_pool`] is set.
+///
+/// ```ignore
+/// let mut deployer = Deployer::new();
+/// deployer.deploy_with_type_id(node, "my_lock", output, code.pack());
+/// deployer.deploy_dep_group(node, "my_lock_group", &["my_lock", "my_lib"]);
+/// let lock = deployer.script_via_type("my_lock").as_builder().args(args).build();
+/// deployer.save_manifest("deployment.json")?;
+///
+/// // paid by a single secp256k1 user
+/// let mut collector = single_secp256k1_collector(node, &user);
+/// let mut payer = Payer::single_secp256k1(&user, &mut collector);
+/// deployer.deploy_paid_by(node, "my_lib", output, code.pack(), &mut payer);
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Deployer {
+    // #{ name => deployed cell }
+    deployed_cells: HashMap<String, DeployedCell>,
+    commit_via_tx_pool: bool,
+}
+
+/// The payer of deploy transactions. The inputs are supplied by `collector`, the change is
+/// locked by `change_lock`, `cell_deps` are the cell deps of the payer's locks, and `signers`
+/// sign the payer's script groups, as well as the upgraded cells'.
+pub struct Payer<'a> {
+    pub collector: &'a mut dyn CellCollector,
+    pub cell_deps: Vec<CellDep>,
+    pub change_lock: Script,
+    pub signers: Vec<User>,
+}
+
+impl<'a> Payer<'a> {
+    /// Pay by always-success cells, e.g. the ones collected by [`always_success_collector`].
+    pub fn always_success(node: &Node, collector: &'a mut dyn CellCollector) -> Self {
+        Self {
+            collector,
+            cell_deps: vec![node.always_success_cell_dep()],
+            change_lock: node.always_success_script(),
+            signers: Vec::new(),
+        }
+    }
+
+    /// Pay by `user`'s single secp256k1 cells, e.g. the ones collected by
+    /// [`single_secp256k1_collector`](crate::single_secp256k1_collector).
+    pub fn single_secp256k1(user: &User, collector: &'a mut dyn CellCollector) -> Self {
+        Self {
+            collector,
+            cell_deps: vec![user.single_secp256k1_cell_dep()],
+            change_lock: user.single_secp256k1_lock_script_via_type(),
+            signers: vec![user.clone()],
+        }
+    }
+
+    fn build(&mut self, builder: TxBuilder) -> Result<TransactionView, String> {
+        builder
+            .cell_deps(self.cell_deps.clone())
+            .change_lock(self.change_lock.clone())
+            .signers(self.signers.clone())
+            .build(&mut *self.collector)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct DeployedCell {
+    cell: CellMeta,
+    dep_type: DepType,
+}
+
+/// An entry of the deployment manifest, see [`Deployer::save_manifest`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ManifestEntry {
+    pub out_point: OutPoint,
+    pub data_hash: Byte32,
+    pub type_hash: Option<Byte32>,
+    pub dep_type: DepType,
+}
+
+impl Deployer {
+    pub fn new() -> Deployer {
+        Default::default()
+    }
+
+    /// Commit the deploy transactions by sending them to the tx-pool and mining until they are
+    /// committed, instead of proposing and committing them at fixed heights.
+    pub fn commit_via_tx_pool(mut self, commit_via_tx_pool: bool) -> Self {
+        self.commit_via_tx_pool = commit_via_tx_pool;
+        self
+    }
+
+    /// Deploy a cell of `output` and `output_data`, named `cell_name`.
+    pub fn deploy<S: ToString>(
+        &mut self,
+        node: &Node,
+        cell_name: S,
+        output: CellOutput,
+        output_data: packed::Bytes,
+    ) {
+        let mut collector = always_success_collector(node);
+        let mut payer = Payer::always_success(node, &mut collector);
+        self.deploy_paid_by(node, cell_name, output, output_data, &mut payer)
+    }
+
+    /// Like [`Deployer::deploy`], paid by `payer`.
+    pub fn deploy_paid_by<S: ToString>(
+        &mut self,
+        node: &Node,
+        cell_name: S,
+        output: CellOutput,
+        output_data: packed::Bytes,
+        payer: &mut Payer,
+    ) {
+        let cell_name = cell_name.to_string();
+        self.ensure_not_deployed(node, &cell_name);
+
+        // Construct transaction, the deployed cell is the first output
+        let tx = payer
+            .build(TxBuilder::new().output(output, output_data.unpack()))
+            .unwrap_or_else(|err| panic!("failed to deploy \"{}\", error: {}", cell_name, err));
+        self.commit_and_save(node, cell_name, &tx, DepType::Code);
+    }
+
+    /// Deploy a cell whose type script is a Type ID script, so that the cell can be referenced
+    /// via its type hash and upgraded by [`Deployer::upgrade`]. The type script of `output` is
+    /// overwritten.
+    pub fn deploy_with_type_id<S: ToString>(
+        &mut self,
+        node: &Node,
+        cell_name: S,
+        output: CellOutput,
+        output_data: packed::Bytes,
+    ) {
+        let mut collector = always_success_collector(node);
+        let mut payer = Payer::always_success(node, &mut collector);
+        self.deploy_with_type_id_paid_by(node, cell_name, output, output_data, &mut payer)
+    }
+
+    /// Like [`Deployer::deploy_with_type_id`], paid by `payer`.
+    pub fn deploy_with_type_id_paid_by<S: ToString>(
+        &mut self,
+        node: &Node,
+        cell_name: S,
+        output: CellOutput,
+        output_data: packed::Bytes,
+        payer: &mut Payer,
+    ) {
+        let cell_name = cell_name.to_string();
+        self.ensure_not_deployed(node, &cell_name);
+
+        // The Type ID args is derived from the first input, so we pick it before building
+        let first_input = payer.collector.collect().unwrap_or_else(|| {
+            panic!(
+                "failed to deploy \"{}\", error: the payer has no spendable cells",
+                cell_name
+            )
+        });
+        let type_script = type_id_script(&CellInput::new(first_input.out_point.clone(), 0), 0);
+        let output = output.as_builder().type_(Some(type_script).pack()).build();
+        let tx = payer
+            .build(
+                TxBuilder::new()
+                    .input(first_input)
+                    .output(output, output_data.unpack()),
+            )
+            .unwrap_or_else(|err| panic!("failed to deploy \"{}\", error: {}", cell_name, err));
+        self.commit_and_save(node, cell_name, &tx, DepType::Code);
+    }
+
+    /// Deploy a dep group cell which references the deployed cells named `cell_names`, in
+    /// order. The dep group is locked by always-success.
+    ///
+    /// NOTE: A dep group references out-points, it is not updated when the referenced cells
+    /// are upgraded.
+    pub fn deploy_dep_group<S: ToString>(
+        &mut self,
+        node: &Node,
+        cell_name: S,
+        cell_names: &[&str],
+    ) {
+        let mut collector = always_success_collector(node);
+        let mut payer = Payer::always_success(node, &mut collector);
+        self.deploy_dep_group_paid_by(node, cell_name, cell_names, &mut payer)
+    }
+
+    /// Like [`Deployer::deploy_dep_group`], paid by `payer`.
+    pub fn deploy_dep_group_paid_by<S: ToString>(
+        &mut self,
+        node: &Node,
+        cell_name: S,
+        cell_names: &[&str],
+        payer: &mut Payer,
+    ) {
+        let cell_name = cell_name.to_string();
+        self.ensure_not_deployed(node, &cell_name);
+
+        let output_data = OutPointVec::new_builder()
+            .set(
+                cell_names
+                    .iter()
+                    .map(|name| self.get_out_point(name))
+                    .collect(),
+            )
+            .build()
+            .as_bytes();
+        let output = CellOutput::new_builder()
+            .lock(node.always_success_script())
+            .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
+            .unwrap();
+        let tx = payer
+            .build(TxBuilder::new().output(output, output_data))
+            .unwrap_or_else(|err| panic!("failed to deploy \"{}\", error: {}", cell_name, err));
+        self.commit_and_save(node, cell_name, &tx, DepType::DepGroup);
+    }
+
+    /// Replace the data of the Type ID cell named `cell_name` with `output_data`. The upgraded
+    /// cell keeps the lock and type script, hence the type hash; its capacity is the occupied
+    /// capacity.
+    ///
+    /// The cell must be locked by always-success.
+    pub fn upgrade<S: ToString>(&mut self, node: &Node, cell_name: S, output_data: packed::Bytes) {
+        let mut collector = always_success_collector(node);
+        let mut payer = Payer::always_success(node, &mut collector);
+        self.upgrade_paid_by(node, cell_name, output_data, &mut payer)
+    }
+
+    /// Like [`Deployer::upgrade`], paid by `payer`. The cell must be locked by always-success,
+    /// or by a lock which `payer.signers` sign and whose cell deps are in `payer.cell_deps`.
+    pub fn upgrade_paid_by<S: ToString>(
+        &mut self,
+        node: &Node,
+        cell_name: S,
+        output_data: packed::Bytes,
+        payer: &mut Payer,
+    ) {
+        let cell_name = cell_name.to_string();
+        let deployed = self.get_cell(&cell_name);
+        let is_type_id = deployed
+            .cell_output
+            .type_()
+            .to_opt()
+            .map(|type_| {
+                type_.code_hash() == TYPE_ID_CODE_HASH.pack()
+                    && type_.hash_type() == ScriptHashType::Type.into()
+            })
+            .unwrap_or(false);
+        assert!(
+            is_type_id,
+            "cell \"{}\" is not deployed with Type ID, cannot upgrade",
+            cell_name
+        );
+        crate::debug!(
+            "[Node {}] upgrading cell \"{}\"",
+            node.node_name(),
+            cell_name
+        );
+
+        let output = deployed
+            .cell_output
+            .clone()
+            .as_builder()
+            .build_exact_capacity(Capacity::bytes(output_data.len()).unwrap())
+            .unwrap();
+        let mut builder = TxBuilder::new().output(output, output_data.unpack());
+        if deployed.cell_output.lock() == node.always_success_script()
+            && !payer.cell_deps.contains(&node.always_success_cell_dep())
+        {
+            builder = builder.cell_dep(node.always_success_cell_dep());
+        }
+        let tx = payer
+            .build(builder.input(deployed))
+            .unwrap_or_else(|err| panic!("failed to upgrade \"{}\", error: {}", cell_name, err));
+        self.deployed_cells.remove(&cell_name);
+        self.commit_and_save(node, cell_name, &tx, DepType::Code);
+    }
+
+    pub fn get_out_point<S: ToString>(&self, cell_name: S) -> OutPoint {
+        self.get_cell(cell_name).out_point
+    }
+
+    pub fn get_cell<S: ToString>(&self, cell_name: S) -> CellMeta {
+        self.get_deployed(cell_name).cell.clone()
+    }
+
+    pub fn get_cells(&self) -> HashMap<String, CellMeta> {
+        self.deployed_cells
+            .iter()
+            .map(|(name, deployed)| (name.clone(), deployed.cell.clone()))
+            .collect()
+    }
+
+    /// The cell dep referencing the deployed cell, `DepType::DepGroup` for dep groups.
+    pub fn cell_dep<S: ToString>(&self, cell_name: S) -> CellDep {
+        let deployed = self.get_deployed(cell_name);
+        CellDep::new_builder()
+            .out_point(deployed.cell.out_point.clone())
+            .dep_type(deployed.dep_type.into())
+            .build()
+    }
+
+    /// The script whose code is the deployed cell, referenced via data hash.
+    pub fn script_via_data<S: ToString>(&self, cell_name: S) -> Script {
+        let data_hash = self
+            .get_cell(cell_name)
+            .mem_cell_data_hash
+            .expect("deployed cell data hash is loaded");
+        Script::new_builder()
+            .code_hash(data_hash)
+            .hash_type(ScriptHashType::Data.into())
+            .build()
+    }
+
+    /// The script whose code is the deployed cell, referenced via type hash.
+    pub fn script_via_type<S: ToString>(&self, cell_name: S) -> Script {
+        let cell_name = cell_name.to_string();
+        let type_hash = self
+            .get_cell(&cell_name)
+            .cell_output
+            .type_()
+            .to_opt()
+            .unwrap_or_else(|| panic!("cell \"{}\" has no type script", cell_name))
+            .calc_script_hash();
+        Script::new_builder()
+            .code_hash(type_hash)
+            .hash_type(ScriptHashType::Type.into())
+            .build()
+    }
+
+    pub fn manifest(&self) -> BTreeMap<String, ManifestEntry> {
+        self.deployed_cells
+            .iter()
+            .map(|(name, deployed)| {
+                let entry = ManifestEntry {
+                    out_point: deployed.cell.out_point.clone(),
+                    data_hash: deployed
+                        .cell
+                        .mem_cell_data_hash
+                        .clone()
+                        .expect("deployed cell data hash is loaded"),
+                    type_hash: deployed
+                        .cell
+                        .cell_output
+                        .type_()
+                        .to_opt()
+                        .map(|type_| type_.calc_script_hash()),
+                    dep_type: deployed.dep_type,
+                };
+                (name.clone(), entry)
+            })
+            .collect()
+    }
+
+    /// Persist the manifest, `#{ name => { out_point, data_hash, type_hash, dep_type } }`, as
+    /// JSON.
+    pub fn save_manifest<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let mut json = serde_json::Map::new();
+        for (name, entry) in self.manifest() {
+            let out_point: ckb_jsonrpc_types::OutPoint = entry.out_point.into();
+            let dep_type: ckb_jsonrpc_types::DepType = entry.dep_type.into();
+            let data_hash: H256 = entry.data_hash.unpack();
+            let type_hash: Option<H256> = entry.type_hash.map(|hash| hash.unpack());
+            json.insert(
+                name,
+                serde_json::json!({
+                    "out_point": out_point,
+                    "data_hash": data_hash,
+                    "type_hash": type_hash,
+                    "dep_type": dep_type,
+                }),
+            );
+        }
+        let content = serde_json::to_string_pretty(&json).map_err(|err| err.to_string())?;
+        fs::write(path.as_ref(), content).map_err(|err| {
+            format!(
+                "failed to write manifest {}, error: {}",
+                path.as_ref().display(),
+                err
+            )
+        })
+    }
+
+    /// Load a deployer from the manifest saved by [`Deployer::save_manifest`]. The deployed
+    /// cells must be live on `node`.
+    pub fn load_manifest<P: AsRef<Path>>(node: &Node, path: P) -> Result<Deployer, String> {
+        let content = fs::read_to_string(path.as_ref()).map_err(|err| {
+            format!(
+                "failed to read manifest {}, error: {}",
+                path.as_ref().display(),
+                err
+            )
+        })?;
+        let json: BTreeMap<String, serde_json::Value> =
+            serde_json::from_str(&content).map_err(|err| err.to_string())?;
+        let mut deployer = Deployer::new();
+        for (name, value) in json {
+            let field = |key: &str| {
+                value
+                    .get(key)
+                    .cloned()
+                    .ok_or_else(|| format!("manifest entry \"{}\" misses \"{}\"", name, key))
+            };
+            let out_point: ckb_jsonrpc_types::OutPoint =
+                serde_json::from_value(field("out_point")?).map_err(|err| err.to_string())?;
+            let dep_type: ckb_jsonrpc_types::DepType =
+                serde_json::from_value(field("dep_type")?).map_err(|err| err.to_string())?;
+            let data_hash: H256 =
+                serde_json::from_value(field("data_hash")?).map_err(|err| err.to_string())?;
+
+            let _ = node.indexer();
+            let cell = node
+                .get_cell_meta(out_point.into())
+                .ok_or_else(|| format!("cell \"{}\" of manifest is not live on the node", name))?;
+            if cell.mem_cell_data_hash != Some(data_hash.pack()) {
+                return Err(format!(
+                    "cell \"{}\" data hash mismatches the manifest, expected: {:#x}",
+                    name, data_hash
+                ));
+            }
+            deployer.deployed_cells.insert(
+                name,
+                DeployedCell {
+                    cell,
+                    dep_type: dep_type.into(),
+                },
+            );
+        }
+        Ok(deployer)
+    }
+
+    fn get_deployed<S: ToString>(&self, cell_name: S) -> &DeployedCell {
+        let cell_name = cell_name.to_string();
+        self.deployed_cells
+            .get(&cell_name)
+            .unwrap_or_else(|| panic!("deployer cannot find cell {}", cell_name))
+    }
+
+    fn ensure_not_deployed(&self, node: &Node, cell_name: &str) {
+        crate::debug!(
+            "[Node {}] deploying cell \"{}\"",
+            node.node_name(),
+            cell_name
+        );
+        assert!(
+            !self.deployed_cells.contains_key(cell_name),
+            "cell \"{}\" already deployed",
+            cell_name,
+        );
+    }
+
+    // Commit `tx` and save its first output as the deployed cell named `cell_name`
+    fn commit_and_save(
+        &mut self,
+        node: &Node,
+        cell_name: String,
+        tx: &TransactionView,
+        dep_type: DepType,
+    ) {
+        if self.commit_via_tx_pool {
+            node.rpc_client()
+                .send_transaction_result(tx.data().into())
+                .unwrap_or_else(|err| panic!("failed to deploy \"{}\", error: {}", cell_name, err));
+            while !node.is_transaction_committed(tx) {
+                node.mine(1);
+            }
+        } else {
+            let tip_number = node.get_tip_block_number();
+            node.build_according_to_instructions(
+                tip_number + 3,
+                vec![
+                    BuildInstruction::Propose {
+                        template_number: tip_number + 1,
+                        proposal_short_id: tx.proposal_short_id(),
+                    },
+                    BuildInstruction::Commit {
+                        template_number: tip_number + 3,
+                        transaction: tx.clone(),
+                    },
+                ],
+            )
+            .unwrap_or_else(|err| panic!("failed to deploy \"{}\", error: {}", cell_name, err));
+        }
+
+        // Make sure the indexer has indexed the deployed cell
+        let _ = node.indexer();
+        let out_point = OutPoint::new(tx.hash(), 0);
+        let cell = node
+            .get_cell_meta(out_point)
+            .unwrap_or_else(|| panic!("deployer should already committed tx {:#x}", tx.hash()));
+        self.deployed_cells
+            .insert(cell_name, DeployedCell { cell, dep_type });
+    }
+}
+
+/// The Type ID script of the output at `output_index` of the transaction whose first input is
+/// `first_input`.
+pub fn type_id_script(first_input: &CellInput, output_index: u64) -> Script {
+    let mut blake2b = new_blake2b();
+    blake2b.update(first_input.as_slice());
+    blake2b.update(&output_index.to_le_bytes());
+    let mut args = [0u8; 32];
+    blake2b.finalize(&mut args);
+    Script::new_builder()
+        .code_hash(TYPE_ID_CODE_HASH.pack())
+        .hash_type(ScriptHashType::Type.into())
+        .args(args.to_vec().pack())
+        .build()
+}
@@ -1,30 +1,66 @@
+mod binaries;
 pub mod connector;
+mod deployer;
 pub mod logger;
 mod node;
 mod nodes;
 mod rpc;
 #[cfg(feature = "with_subscribe")]
 mod subscribe;
+mod test_scripts;
+mod tx_builder;
 mod user;
 pub mod util;
 
-pub use connector::{compress, decompress, Connector, ConnectorBuilder, SupportProtocols};
+pub use binaries::{ckb_binaries, ckb_binary, register_ckb_binary, CkbBinary};
+pub use connector::{
+    compress, decompress, Connector, ConnectorBuilder, FakeRelayPeer, FakeRelayPeerBuilder,
+    FakeSyncPeer, FakeSyncPeerBuilder, P2PFuzzer, SupportProtocols,
+};
+pub use deployer::{type_id_script, Deployer, ManifestEntry, Payer};
 pub use logger::LOG_TARGET;
-pub use node::{BuildInstruction, Node, NodeOptions};
+pub use node::{
+    dao_minimal_unlock_epoch, kill_nodes_of, node_log_paths_of, BanGuard, BanTarget,
+    BuildInstruction, BulkMiningOptions, Node, NodeOptions, UpgradeOptions, DAO_LOCK_PERIOD_EPOCHS,
+};
 pub use nodes::Nodes;
-pub use user::User;
+pub use test_scripts::{ExecParams, LoadSyscall, Source, SpawnParams, TestScript};
+pub use tx_builder::{
+    always_success_collector, single_secp256k1_collector, CellCollector, NoCollector, TxBuilder,
+    DEFAULT_FEE_RATE,
+};
+pub use user::{
+    placeholder_transaction, sighash_all_message, sign_transaction, MultisigConfig, User,
+};
 
 pub use ckb_crypto;
+pub use ckb_hash;
 pub use ckb_jsonrpc_types;
 pub use ckb_types;
 pub use p2p;
 
 use ckb_types::{h256, H256};
 
+/// Cargo features this crate is built with.
+pub fn enabled_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "with_subscribe") {
+        features.push("with_subscribe");
+    }
+    features
+}
+
+pub const SYSTEM_CELL_DAO_INDEX: u32 = 2;
+pub const SYSTEM_CELL_MULTISIG_ALL_INDEX: u32 = 4;
 pub const SYSTEM_CELL_ALWAYS_SUCCESS_INDEX: u32 = 5;
 pub const GENESIS_DEP_GROUP_TRANSACTION_INDEX: usize = 1;
 pub const GENESIS_SIGHASH_ALL_DEP_GROUP_CELL_INDEX: usize = 0;
+pub const GENESIS_MULTISIG_ALL_DEP_GROUP_CELL_INDEX: usize = 1;
 pub const SIGHASH_ALL_TYPE_HASH: H256 =
     h256!("0x9bd7e06f3ecf4be0f2fcd2188b23f1b9fcc88e5d4b65a8637b17723bbda3cce8");
 pub const SIGHASH_ALL_DATA_HASH: H256 =
     h256!("0x709f3fda12f561cfacf92273c57a98fede188a3f1a59b1f888d113f9cce08649");
+pub const MULTISIG_ALL_TYPE_HASH: H256 =
+    h256!("0x5c5069eb0857efc65e1bca0c07df34c31663b3622fd3876c876320fc9634e2a8");
+pub const TYPE_ID_CODE_HASH: H256 =
+    h256!("0x00000000000000000000000000000000000000000000000000545950455f4944");
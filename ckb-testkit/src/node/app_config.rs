@@ -0,0 +1,200 @@
+use super::node::copy_dir_content;
+use std::fs;
+use std::path::Path;
+
+/// The patch file of an app config directory, see [`copy_app_config`].
+const PATCH_FILE: &str = "ckb.patch.toml";
+
+/// Copy the app config directory `source` into `target`.
+///
+/// A directory holding `ckb.patch.toml` derives its `ckb.toml` from another directory rather
+/// than copying one, so that a case configuring a few options does not duplicate the whole
+/// `ckb.toml`:
+///
+/// ```toml
+/// base = "testdata/config/ckb2021"
+///
+/// [network]
+/// ping_interval_secs = 1
+/// ```
+///
+/// `base` is copied first, it may be patched itself. Then every `key = value` line under a
+/// `[section]` of the patch replaces the line of the same key in the same section of
+/// `ckb.toml`, or is appended to the section if absent. Sections absent from `ckb.toml` are
+/// appended. Comments of the patch are dropped.
+pub(super) fn copy_app_config(source: &str, target: &Path) {
+    let patch_path = Path::new(source).join(PATCH_FILE);
+    if !patch_path.exists() {
+        copy_dir_content(source, target);
+        return;
+    }
+    let patch = fs::read_to_string(&patch_path)
+        .unwrap_or_else(|err| panic!("failed to read {}, error: {}", patch_path.display(), err));
+    let base = patch_base(&patch)
+        .unwrap_or_else(|err| panic!("invalid {}, error: {}", patch_path.display(), err));
+    copy_app_config(&base, target);
+
+    let app_config = target.join("ckb.toml");
+    let content = fs::read_to_string(&app_config)
+        .unwrap_or_else(|err| panic!("failed to read {}, error: {}", app_config.display(), err));
+    let content = patch_app_config(&content, &patch)
+        .unwrap_or_else(|err| panic!("invalid {}, error: {}", patch_path.display(), err));
+    fs::write(&app_config, content)
+        .unwrap_or_else(|err| panic!("failed to write {}, error: {}", app_config.display(), err));
+}
+
+// The `base = "..."` line before the first section
+fn patch_base(patch: &str) -> Result<String, String> {
+    for line in meaningful_lines(patch) {
+        if line.starts_with('[') {
+            break;
+        }
+        if let Some(("base", value)) = split_key_value(line) {
+            return Ok(value.trim_matches('"').to_string());
+        }
+    }
+    Err(format!(
+        "{} misses `base = \"<app config dir>\"`",
+        PATCH_FILE
+    ))
+}
+
+fn patch_app_config(content: &str, patch: &str) -> Result<String, String> {
+    let mut lines: Vec<String> = content.lines().map(ToString::to_string).collect();
+    let mut section: Option<&str> = None;
+    for line in meaningful_lines(patch) {
+        if line.starts_with('[') {
+            section = Some(line);
+            continue;
+        }
+        let section = match section {
+            Some(section) => section,
+            // `base`, before the first section
+            None => continue,
+        };
+        let (key, _) = split_key_value(line)
+            .ok_or_else(|| format!("expect `key = value`, got \"{}\"", line))?;
+
+        let start = match lines.iter().position(|l| l.trim() == section) {
+            Some(start) => start,
+            None => {
+                lines.push(String::new());
+                lines.push(section.to_string());
+                lines.len() - 1
+            }
+        };
+        let end = lines[start + 1..]
+            .iter()
+            .position(|l| l.trim().starts_with('['))
+            .map(|offset| start + 1 + offset)
+            .unwrap_or_else(|| lines.len());
+        let existing = lines[start + 1..end].iter().position(|l| {
+            split_key_value(l.trim())
+                .map(|(k, _)| k == key)
+                .unwrap_or(false)
+        });
+        match existing {
+            Some(offset) => lines[start + 1 + offset] = line.to_string(),
+            None => {
+                // Insert before the blank lines and comments ending the section
+                let mut insert_at = end;
+                while insert_at > start + 1 {
+                    let previous = lines[insert_at - 1].trim();
+                    if previous.is_empty() || previous.starts_with('#') {
+                        insert_at -= 1;
+                    } else {
+                        break;
+                    }
+                }
+                lines.insert(insert_at, line.to_string());
+            }
+        }
+    }
+    let mut patched = lines.join("\n");
+    patched.push('\n');
+    Ok(patched)
+}
+
+fn meaningful_lines(text: &str) -> impl Iterator<Item = &str> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+fn split_key_value(line: &str) -> Option<(&str, &str)> {
+    if line.starts_with('#') || line.starts_with('[') {
+        return None;
+    }
+    let mut parts = line.splitn(2, '=');
+    let key = parts.next()?.trim();
+    let value = parts.next()?.trim();
+    if key.is_empty() {
+        None
+    } else {
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONTENT: &str = "\
+data_dir = \"data\"
+
+[network]
+listen_addresses = [\"/ip4/0.0.0.0/tcp/__P2P_PORT__\"]
+# 2 minutes
+ping_interval_secs = 120
+max_peers = 125
+
+[rpc]
+listen_address = \"127.0.0.1:__RPC_PORT__\"
+";
+
+    #[test]
+    fn patch_base_is_required() {
+        assert_eq!(
+            patch_base("# comment\nbase = \"testdata/config/ckb2021\"\n[network]\n"),
+            Ok("testdata/config/ckb2021".to_string())
+        );
+        assert!(patch_base("[network]\nbase = \"testdata/config/ckb2021\"\n").is_err());
+    }
+
+    #[test]
+    fn patch_replaces_and_appends_keys() {
+        let patch = "\
+base = \"testdata/config/ckb2021\"
+
+[network]
+# 1 second
+ping_interval_secs = 1
+ping_timeout_secs = 3
+
+[alert_signature]
+signatures_threshold = 1
+";
+        let expected = "\
+data_dir = \"data\"
+
+[network]
+listen_addresses = [\"/ip4/0.0.0.0/tcp/__P2P_PORT__\"]
+# 2 minutes
+ping_interval_secs = 1
+max_peers = 125
+ping_timeout_secs = 3
+
+[rpc]
+listen_address = \"127.0.0.1:__RPC_PORT__\"
+
+[alert_signature]
+signatures_threshold = 1
+";
+        assert_eq!(patch_app_config(CONTENT, patch), Ok(expected.to_string()));
+    }
+
+    #[test]
+    fn patch_rejects_lines_without_value() {
+        assert!(patch_app_config(CONTENT, "[network]\nping_interval_secs\n").is_err());
+    }
+}
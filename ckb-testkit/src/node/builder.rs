@@ -174,8 +174,7 @@ impl Node {
                 node_name: String::from(node_name),
                 ..self.node_options().clone()
             };
-            let is_ckb2021 = self.rpc_client().ckb2021;
-            Node::init("cloned_node", node_options, is_ckb2021)
+            Node::init("cloned_node", node_options)
         };
         target_node.start();
 
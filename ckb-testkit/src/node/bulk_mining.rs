@@ -0,0 +1,516 @@
+use crate::{Node, User};
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        BlockBuilder, BlockNumber, BlockView, Capacity, EpochNumberWithFraction, HeaderView,
+        TransactionBuilder, TransactionView,
+    },
+    packed::{
+        Byte32, CellDep, CellInput, CellOutput, CellbaseWitness, OutPoint, Script, WitnessArgs,
+    },
+    prelude::*,
+};
+use std::collections::{HashMap, VecDeque};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// The proposer's share of transaction fees, see ckb's `PROPOSER_REWARD_RATIO`
+const PROPOSER_REWARD_NUMER: u64 = 4;
+const PROPOSER_REWARD_DENOM: u64 = 10;
+
+/// Options of [`Node::bulk_mine`].
+#[derive(Clone, Default)]
+pub struct BulkMiningOptions {
+    /// Number of transfer transactions to commit in each block. Every transaction spends one
+    /// cell and splits it into two, see `transfer_user`.
+    pub transactions_per_block: usize,
+    /// The owner of the transferred cells. The transactions spend the user's mature single
+    /// secp256k1 cells, are signed by the user and pay `fee_rate`. Default transfers
+    /// always-success cells, which need no signature.
+    pub transfer_user: Option<User>,
+    /// Fee rate of the transfer transactions, in shannons per kilobyte. The fees are
+    /// rewarded to the committing and the proposing blocks, like ckb does.
+    pub fee_rate: u64,
+    /// The lock of generated cellbases' witnesses, i.e. the receiver of block rewards.
+    /// Default is the always-success script.
+    pub cellbase_lock: Option<Script>,
+    /// Feed blocks via RPC `submit_block` which fully verifies blocks, instead of
+    /// `process_block_without_verify`. It is slower but checks the generated blocks.
+    pub verify: bool,
+}
+
+impl Node {
+    /// Generate `n_blocks` blocks locally and feed them to the node.
+    ///
+    /// Unlike [`Node::mine`], it does not request block templates from the node. Blocks are
+    /// built from the consensus: dummy PoW, dao field and cellbase computed according to the
+    /// reward rules. It only supports chains with `permanent_difficulty_in_dummy`.
+    ///
+    /// Transactions are not proposed in the last `proposal_window.closest` blocks, so that all
+    /// the generated transactions are committed within the generated blocks.
+    ///
+    /// NOTE: The rewards of blocks before the generated ones are computed without transaction
+    /// fees, so make sure the last `proposal_window.farthest + 1` blocks before do not contain
+    /// fee-paying transactions. Conversely, mine the blocks right after a bulk mining which
+    /// paid fees via [`Node::mine`], which gets the rewards from the node.
+    pub fn bulk_mine(&self, n_blocks: u64, options: BulkMiningOptions) {
+        assert!(self.consensus().permanent_difficulty_in_dummy);
+        let end_number = self.get_tip_block_number() + n_blocks;
+        let mut generator = BulkGenerator::new(self, options, end_number);
+        for _ in 0..n_blocks {
+            let block = generator.next_block();
+            if generator.options.verify {
+                self.rpc_client()
+                    .submit_block("".to_owned(), block.data().into())
+                    .unwrap_or_else(|err| {
+                        panic!(
+                            "failed to submit bulk block {}, error: {}",
+                            block.number(),
+                            err
+                        )
+                    });
+            } else {
+                self.rpc_client()
+                    .process_block_without_verify(block.data().into(), false)
+                    .unwrap_or_else(|| panic!("failed to process bulk block {}", block.number()));
+            }
+            if block.number() % 1000 == 0 {
+                crate::debug!(
+                    "[Node {}] bulk mined block {}",
+                    self.node_name(),
+                    block.number()
+                );
+            }
+        }
+        self.wait_for_tx_pool();
+    }
+
+    pub fn bulk_mine_to(&self, target_height: BlockNumber, options: BulkMiningOptions) {
+        let tip_number = self.get_tip_block_number();
+        if tip_number < target_height {
+            self.bulk_mine(target_height - tip_number, options);
+        }
+    }
+}
+
+struct BulkGenerator<'a> {
+    node: &'a Node,
+    options: BulkMiningOptions,
+    cellbase_lock: Script,
+    finalization_delay_length: BlockNumber,
+    // the number of the last generated block
+    end_number: BlockNumber,
+    // recent headers and cellbase locks, indexed by block number
+    headers: HashMap<BlockNumber, HeaderView>,
+    cellbase_locks: HashMap<BlockNumber, Script>,
+    // transactions which were proposed and wait for being committed, [(commit_number, txs)]
+    proposed: VecDeque<(BlockNumber, Vec<TransactionView>)>,
+    // spendable cells, [(out_point, cell_output)]
+    live_cells: VecDeque<(OutPoint, CellOutput)>,
+    // miner's shares of the transaction fees, indexed by the proposing or committing block
+    // number
+    fees: HashMap<BlockNumber, u64>,
+}
+
+impl<'a> BulkGenerator<'a> {
+    fn new(node: &'a Node, options: BulkMiningOptions, end_number: BlockNumber) -> Self {
+        let tip = node.get_tip_block();
+        let cellbase_lock = options
+            .cellbase_lock
+            .clone()
+            .unwrap_or_else(|| node.always_success_script());
+        let finalization_delay_length = node.consensus().tx_proposal_window.farthest.value() + 1;
+        let live_cells = if options.transactions_per_block > 0 {
+            // A cellbase is mature if `block_epoch + cellbase_maturity <= tip_epoch`, compared as
+            // rationals like ckb does. The transactions are committed after the tip, so mature
+            // cellbases stay mature.
+            let maturity =
+                EpochNumberWithFraction::from_full_value(node.consensus().cellbase_maturity.into())
+                    .to_rational();
+            let tip_epoch = tip.epoch().to_rational();
+            // `get_cell_meta` does not fill `transaction_info.block_epoch`
+            let mut block_epochs = HashMap::new();
+            let cells = match options.transfer_user {
+                Some(ref user) => user.get_spendable_single_secp256k1_cells(node),
+                None => node.get_spendable_always_success_cells(),
+            };
+            cells
+                .into_iter()
+                .filter(|cell| cell.data_bytes == 0 && cell.cell_output.type_().is_none())
+                .filter(|cell| {
+                    let txinfo = cell
+                        .transaction_info
+                        .as_ref()
+                        .expect("committed tx has transaction_info");
+                    if !txinfo.is_cellbase() {
+                        return true;
+                    }
+                    let block_epoch = *block_epochs
+                        .entry(txinfo.block_number)
+                        .or_insert_with(|| node.get_header_by_number(txinfo.block_number).epoch());
+                    block_epoch.to_rational() + maturity.clone() <= tip_epoch
+                })
+                .map(|cell| (cell.out_point, cell.cell_output))
+                .collect()
+        } else {
+            VecDeque::new()
+        };
+        let mut generator = Self {
+            node,
+            options,
+            cellbase_lock,
+            finalization_delay_length,
+            end_number,
+            headers: HashMap::new(),
+            cellbase_locks: HashMap::new(),
+            proposed: VecDeque::new(),
+            live_cells,
+            fees: HashMap::new(),
+        };
+        generator.insert(&tip);
+        generator
+    }
+
+    fn insert(&mut self, block: &BlockView) {
+        let number = block.number();
+        self.headers.insert(number, block.header());
+        self.cellbase_locks
+            .insert(number, cellbase_witness_lock(block));
+        let expired = number.saturating_sub(self.finalization_delay_length + 1);
+        self.headers.remove(&expired);
+        self.cellbase_locks.remove(&expired);
+    }
+
+    fn header(&mut self, number: BlockNumber) -> HeaderView {
+        if !self.headers.contains_key(&number) {
+            let block = self.node.get_block_by_number(number);
+            self.headers.insert(number, block.header());
+            self.cellbase_locks
+                .insert(number, cellbase_witness_lock(&block));
+        }
+        self.headers[&number].clone()
+    }
+
+    fn cellbase_lock(&mut self, number: BlockNumber) -> Script {
+        let _ = self.header(number);
+        self.cellbase_locks[&number].clone()
+    }
+
+    fn next_block(&mut self) -> BlockView {
+        let parent = self.headers[&self.tip_number()].clone();
+        let number = parent.number() + 1;
+        let epoch = next_epoch(parent.epoch());
+
+        let cellbase = self.build_cellbase(number);
+        let transactions = self.take_committing_transactions(number);
+        let proposals = self.build_proposing_transactions(number);
+        let dao = self.calculate_dao(&parent, epoch, &cellbase, &transactions);
+        let timestamp = {
+            let now = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .expect("system time")
+                .as_millis() as u64;
+            ::std::cmp::max(parent.timestamp() + 1, now)
+        };
+
+        let block = BlockBuilder::default()
+            .version(self.node.consensus().block_version.value().pack())
+            .parent_hash(parent.hash())
+            .number(number.pack())
+            .epoch(epoch.full_value().pack())
+            .timestamp(timestamp.pack())
+            .compact_target(parent.compact_target().pack())
+            .dao(dao)
+            .transaction(cellbase)
+            .transactions(transactions)
+            .proposals(proposals.iter().map(|tx| tx.proposal_short_id()))
+            .build();
+        self.insert(&block);
+        block
+    }
+
+    fn tip_number(&self) -> BlockNumber {
+        *self.headers.keys().max().expect("at least tip")
+    }
+
+    fn build_cellbase(&mut self, number: BlockNumber) -> TransactionView {
+        let witness = CellbaseWitness::new_builder()
+            .lock(self.cellbase_lock.clone())
+            .build();
+        let builder = TransactionBuilder::default()
+            .input(CellInput::new_cellbase_input(number))
+            .witness(witness.as_bytes().pack());
+        if number <= self.finalization_delay_length {
+            return builder.build();
+        }
+
+        let target_number = number - self.finalization_delay_length;
+        let target = self.header(target_number);
+        let target_parent = self.header(target_number - 1);
+        let target_lock = self.cellbase_lock(target_number);
+        let consensus = self.node.consensus();
+        let primary = block_reward(
+            target.epoch(),
+            consensus.initial_primary_epoch_reward.value(),
+            consensus.primary_epoch_reward_halving_interval.value(),
+        );
+        let secondary = {
+            let target_g2 =
+                secondary_block_issuance(target.epoch(), consensus.secondary_epoch_reward.value());
+            let (_, target_parent_c, _, target_parent_u) = extract_dao_data(&target_parent.dao());
+            (u128::from(target_g2) * u128::from(target_parent_u) / u128::from(target_parent_c))
+                as u64
+        };
+        let fees = self.fees.remove(&target_number).unwrap_or(0);
+        let output = CellOutput::new_builder()
+            .capacity((primary + secondary + fees).pack())
+            .lock(target_lock)
+            .build();
+        if output
+            .is_lack_of_capacity(Capacity::zero())
+            .expect("check capacity")
+        {
+            builder.build()
+        } else {
+            builder
+                .output(output)
+                .output_data(Default::default())
+                .build()
+        }
+    }
+
+    fn take_committing_transactions(&mut self, number: BlockNumber) -> Vec<TransactionView> {
+        match self.proposed.front() {
+            Some((commit_number, _)) if *commit_number == number => {
+                self.proposed.pop_front().expect("checked").1
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // Build transactions that will be proposed in block `number` and committed in block
+    // `number + proposal_window.closest`.
+    fn build_proposing_transactions(&mut self, number: BlockNumber) -> Vec<TransactionView> {
+        let closest = self.node.consensus().tx_proposal_window.closest.value();
+        if self.options.transactions_per_block == 0 || number + closest > self.end_number {
+            return Vec::new();
+        }
+        let cell_dep = match self.options.transfer_user {
+            Some(ref user) => user.single_secp256k1_cell_dep(),
+            None => self.node.always_success_cell_dep(),
+        };
+        let mut transactions = Vec::with_capacity(self.options.transactions_per_block);
+        while transactions.len() < self.options.transactions_per_block {
+            let (out_point, cell_output) = match self.live_cells.pop_front() {
+                Some(cell) => cell,
+                None => break,
+            };
+            let (tx, fee) = match self.build_transfer(out_point, cell_output, &cell_dep) {
+                Some(transfer) => transfer,
+                None => continue,
+            };
+            let proposer_share = fee * PROPOSER_REWARD_NUMER / PROPOSER_REWARD_DENOM;
+            *self.fees.entry(number).or_insert(0) += proposer_share;
+            *self.fees.entry(number + closest).or_insert(0) += fee - proposer_share;
+            for (index, output) in tx.outputs().into_iter().enumerate() {
+                self.live_cells
+                    .push_back((OutPoint::new(tx.hash(), index as u32), output));
+            }
+            transactions.push(tx);
+        }
+        self.proposed
+            .push_back((number + closest, transactions.clone()));
+        transactions
+    }
+
+    // Split the cell into two, or keep it as one if it is too small. Return the transaction and
+    // its fee, or `None` if the cell cannot afford the fee.
+    fn build_transfer(
+        &self,
+        out_point: OutPoint,
+        cell_output: CellOutput,
+        cell_dep: &CellDep,
+    ) -> Option<(TransactionView, u64)> {
+        let capacity: u64 = cell_output.capacity().unpack();
+        let minimal: u64 = cell_output
+            .occupied_capacity(Capacity::zero())
+            .expect("occupied capacity")
+            .as_u64();
+        let build = |outputs: Vec<CellOutput>, witness: Bytes| {
+            TransactionBuilder::default()
+                .input(CellInput::new(out_point.clone(), 0))
+                .outputs_data(outputs.iter().map(|_| Default::default()))
+                .outputs(outputs)
+                .cell_dep(cell_dep.clone())
+                .witnesses(if witness.is_empty() {
+                    Vec::new()
+                } else {
+                    vec![witness.pack()]
+                })
+                .build()
+        };
+        let split = |rest: u64| {
+            if rest >= minimal * 2 {
+                vec![
+                    cell_output
+                        .clone()
+                        .as_builder()
+                        .capacity((rest / 2).pack())
+                        .build(),
+                    cell_output
+                        .clone()
+                        .as_builder()
+                        .capacity((rest - rest / 2).pack())
+                        .build(),
+                ]
+            } else {
+                vec![cell_output
+                    .clone()
+                    .as_builder()
+                    .capacity(rest.pack())
+                    .build()]
+            }
+        };
+
+        let user = match self.options.transfer_user {
+            Some(ref user) => user,
+            None => return Some((build(split(capacity), Bytes::new()), 0)),
+        };
+        // The signature has a fixed size, so the fee is computed from a placeholder witness
+        let placeholder = WitnessArgs::new_builder()
+            .lock(Some(Bytes::from(vec![0u8; 65])).pack())
+            .build();
+        let size = build(split(capacity), placeholder.as_bytes())
+            .data()
+            .as_reader()
+            .serialized_size_in_block() as u64;
+        let fee = self.options.fee_rate.saturating_mul(size) / 1000;
+        if capacity < fee + minimal {
+            return None;
+        }
+        let unsigned_tx = build(split(capacity - fee), Bytes::new());
+        let witness = user.single_secp256k1_signed_witness(&unsigned_tx);
+        // Keeping the cell as one makes the transaction smaller, so the fee still suffices
+        Some((build(split(capacity - fee), witness.as_bytes()), fee))
+    }
+
+    fn calculate_dao(
+        &self,
+        parent: &HeaderView,
+        epoch: EpochNumberWithFraction,
+        cellbase: &TransactionView,
+        transactions: &[TransactionView],
+    ) -> Byte32 {
+        let consensus = self.node.consensus();
+        let occupied = |output: &CellOutput, data_len: usize| -> u64 {
+            output
+                .occupied_capacity(Capacity::bytes(data_len).expect("data capacity"))
+                .expect("occupied capacity")
+                .as_u64()
+        };
+        let added_occupied: u64 = ::std::iter::once(cellbase)
+            .chain(transactions.iter())
+            .flat_map(|tx| {
+                tx.outputs_with_data_iter()
+                    .map(|(output, data)| occupied(&output, data.len()))
+                    .collect::<Vec<_>>()
+            })
+            .sum();
+        // Inputs of bulk transactions are cells without data and type, whose locks are the
+        // same as the outputs'.
+        let freed_occupied: u64 = transactions
+            .iter()
+            .map(|tx| {
+                let minimal = occupied(&tx.outputs().get(0).expect("at least 1 output"), 0);
+                minimal * tx.inputs().len() as u64
+            })
+            .sum();
+
+        let (parent_ar, parent_c, parent_s, parent_u) = extract_dao_data(&parent.dao());
+        let current_g2 = secondary_block_issuance(epoch, consensus.secondary_epoch_reward.value());
+        let current_g = block_reward(
+            epoch,
+            consensus.initial_primary_epoch_reward.value(),
+            consensus.primary_epoch_reward_halving_interval.value(),
+        ) + current_g2;
+        let miner_issuance =
+            (u128::from(current_g2) * u128::from(parent_u) / u128::from(parent_c)) as u64;
+        let nervosdao_issuance = current_g2 - miner_issuance;
+        let current_c = parent_c + current_g;
+        let current_u = parent_u + added_occupied - freed_occupied;
+        let current_s = parent_s + nervosdao_issuance;
+        let ar_increase =
+            (u128::from(parent_ar) * u128::from(current_g2) / u128::from(parent_c)) as u64;
+        let current_ar = parent_ar + ar_increase;
+        pack_dao_data(current_ar, current_c, current_s, current_u)
+    }
+}
+
+fn cellbase_witness_lock(block: &BlockView) -> Script {
+    block
+        .transaction(0)
+        .and_then(|cellbase| cellbase.witnesses().get(0))
+        .and_then(|witness| CellbaseWitness::from_slice(&witness.raw_data()).ok())
+        .map(|witness| witness.lock())
+        .unwrap_or_default()
+}
+
+fn next_epoch(parent_epoch: EpochNumberWithFraction) -> EpochNumberWithFraction {
+    if parent_epoch.index() + 1 == parent_epoch.length() {
+        EpochNumberWithFraction::new(parent_epoch.number() + 1, 0, parent_epoch.length())
+    } else {
+        EpochNumberWithFraction::new(
+            parent_epoch.number(),
+            parent_epoch.index() + 1,
+            parent_epoch.length(),
+        )
+    }
+}
+
+// The primary block reward of the block at `epoch`, see `EpochExt::block_reward`
+fn block_reward(
+    epoch: EpochNumberWithFraction,
+    initial_primary_epoch_reward: u64,
+    halving_interval: u64,
+) -> u64 {
+    let halvings = epoch.number() / halving_interval;
+    let primary_epoch_reward = initial_primary_epoch_reward >> halvings;
+    let base_block_reward = primary_epoch_reward / epoch.length();
+    let remainder_reward = primary_epoch_reward % epoch.length();
+    if epoch.index() < remainder_reward {
+        base_block_reward + 1
+    } else {
+        base_block_reward
+    }
+}
+
+// The secondary block issuance of the block at `epoch`, see `EpochExt::secondary_block_issuance`
+fn secondary_block_issuance(epoch: EpochNumberWithFraction, secondary_epoch_reward: u64) -> u64 {
+    let g2 = secondary_epoch_reward / epoch.length();
+    let remainder = secondary_epoch_reward % epoch.length();
+    if epoch.index() < remainder {
+        g2 + 1
+    } else {
+        g2
+    }
+}
+
+/// Extract `(ar, c, s, u)` from the dao field.
+pub(crate) fn extract_dao_data(dao: &Byte32) -> (u64, u64, u64, u64) {
+    let data = dao.raw_data();
+    let read = |offset: usize| {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&data[offset..offset + 8]);
+        u64::from_le_bytes(buf)
+    };
+    (read(8), read(0), read(16), read(24))
+}
+
+/// Pack `(ar, c, s, u)` into the dao field.
+pub(crate) fn pack_dao_data(ar: u64, c: u64, s: u64, u: u64) -> Byte32 {
+    let mut buf = [0u8; 32];
+    buf[0..8].copy_from_slice(&c.to_le_bytes());
+    buf[8..16].copy_from_slice(&ar.to_le_bytes());
+    buf[16..24].copy_from_slice(&s.to_le_bytes());
+    buf[24..32].copy_from_slice(&u.to_le_bytes());
+    Byte32::from_slice(&buf).expect("32 bytes")
+}
@@ -0,0 +1,210 @@
+use super::bulk_mining::extract_dao_data;
+use crate::util::since_from_absolute_epoch_number_with_fraction;
+use crate::{always_success_collector, NoCollector, Node, TxBuilder, SYSTEM_CELL_DAO_INDEX};
+use ckb_types::{
+    bytes::Bytes,
+    core::{
+        cell::CellMeta, Capacity, EpochNumber, EpochNumberWithFraction, HeaderView, ScriptHashType,
+        TransactionView,
+    },
+    packed::{Byte32, CellDep, CellOutput, OutPoint, Script, WitnessArgs},
+    prelude::*,
+};
+
+/// The minimal lock period of Nervos DAO, in epochs.
+pub const DAO_LOCK_PERIOD_EPOCHS: EpochNumber = 180;
+
+impl Node {
+    pub fn dao_type_script(&self) -> Script {
+        let type_ = self
+            .genesis_block()
+            .transaction(0)
+            .expect("genesis cellbase")
+            .output(SYSTEM_CELL_DAO_INDEX as usize)
+            .expect("genesis dao cell")
+            .type_()
+            .to_opt()
+            .expect("genesis dao cell should have type_=type-id script");
+        Script::new_builder()
+            .code_hash(type_.calc_script_hash())
+            .hash_type(ScriptHashType::Type.into())
+            .build()
+    }
+
+    pub fn dao_cell_dep(&self) -> CellDep {
+        CellDep::new_builder()
+            .out_point(OutPoint::new(
+                self.genesis_cellbase_hash(),
+                SYSTEM_CELL_DAO_INDEX,
+            ))
+            .build()
+    }
+
+    /// A deposit transaction builder whose first output is a DAO cell of `capacity` locked
+    /// by `lock`. The caller supplies the inputs, change lock and signers.
+    pub fn dao_deposit_builder(&self, lock: Script, capacity: u64) -> TxBuilder {
+        let output = CellOutput::new_builder()
+            .lock(lock)
+            .type_(Some(self.dao_type_script()).pack())
+            .capacity(capacity.pack())
+            .build();
+        TxBuilder::new()
+            .output(output, Bytes::from(vec![0u8; 8]))
+            .cell_dep(self.dao_cell_dep())
+    }
+
+    /// A phase-1 withdraw transaction builder. It spends the `deposited` cell into a
+    /// withdrawing cell, the first output, of the same capacity, lock and type, whose data is
+    /// the deposit block number. The deposit block header is the first header dep.
+    pub fn dao_withdraw_phase1_builder(&self, deposited: &CellMeta) -> TxBuilder {
+        let deposit_block_number = deposited
+            .transaction_info
+            .as_ref()
+            .expect("deposited cell is committed")
+            .block_number;
+        TxBuilder::new()
+            .input(deposited.clone())
+            .output(
+                deposited.cell_output.clone(),
+                Bytes::from(deposit_block_number.to_le_bytes().to_vec()),
+            )
+            .cell_dep(self.dao_cell_dep())
+            .header_dep(self.dao_deposit_header(deposited).hash())
+    }
+
+    /// A phase-2 withdraw transaction builder. It spends the `withdrawing` cell, which
+    /// contributes its maximum withdraw. The input since is the minimal unlock epoch, the header
+    /// deps are `[deposit header, withdrawing header]`, and the witness `input_type` is the
+    /// index of the deposit header in header deps.
+    pub fn dao_withdraw_phase2_builder(&self, withdrawing: &CellMeta) -> TxBuilder {
+        let deposit_header = self.dao_deposit_header(withdrawing);
+        let withdrawing_header = self.get_block(cell_block_hash(withdrawing)).header();
+        let since = since_from_absolute_epoch_number_with_fraction(dao_minimal_unlock_epoch(
+            &deposit_header,
+            &withdrawing_header,
+        ));
+        let witness = WitnessArgs::new_builder()
+            .input_type(Some(Bytes::from(0u64.to_le_bytes().to_vec())).pack())
+            .build();
+        TxBuilder::new()
+            .input_with_capacity(
+                withdrawing.clone(),
+                since,
+                witness,
+                self.dao_maximum_withdraw(withdrawing),
+            )
+            .cell_dep(self.dao_cell_dep())
+            .header_dep(deposit_header.hash())
+            .header_dep(withdrawing_header.hash())
+    }
+
+    /// Deposit `capacity` into an always-success locked DAO cell.
+    pub fn dao_deposit(&self, capacity: u64) -> TransactionView {
+        self.dao_deposit_builder(self.always_success_script(), capacity)
+            .cell_dep(self.always_success_cell_dep())
+            .change_lock(self.always_success_script())
+            .build(&mut always_success_collector(self))
+            .unwrap_or_else(|err| panic!("failed to build dao deposit transaction, error: {}", err))
+    }
+
+    pub fn dao_withdraw_phase1(&self, deposited: &CellMeta) -> TransactionView {
+        self.dao_withdraw_phase1_builder(deposited)
+            .cell_dep(self.always_success_cell_dep())
+            .change_lock(self.always_success_script())
+            .build(&mut always_success_collector(self))
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to build dao withdraw phase-1 transaction, error: {}",
+                    err
+                )
+            })
+    }
+
+    pub fn dao_withdraw_phase2(&self, withdrawing: &CellMeta) -> TransactionView {
+        self.dao_withdraw_phase2_builder(withdrawing)
+            .cell_dep(self.always_success_cell_dep())
+            .change_lock(self.always_success_script())
+            .build(&mut NoCollector)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to build dao withdraw phase-2 transaction, error: {}",
+                    err
+                )
+            })
+    }
+
+    /// The maximum capacity a withdrawing cell can withdraw,
+    /// `(capacity - occupied) * ar(withdrawing) / ar(deposit) + occupied`.
+    pub fn dao_maximum_withdraw(&self, withdrawing: &CellMeta) -> u64 {
+        let deposit_header = self.dao_deposit_header(withdrawing);
+        let withdrawing_header = self.get_block(cell_block_hash(withdrawing)).header();
+        let (deposit_ar, _, _, _) = extract_dao_data(&deposit_header.dao());
+        let (withdrawing_ar, _, _, _) = extract_dao_data(&withdrawing_header.dao());
+        let occupied = withdrawing
+            .cell_output
+            .occupied_capacity(Capacity::bytes(8).unwrap())
+            .expect("occupied capacity")
+            .as_u64();
+        let capacity = withdrawing.capacity().as_u64();
+        let counted =
+            u128::from(capacity - occupied) * u128::from(withdrawing_ar) / u128::from(deposit_ar);
+        counted as u64 + occupied
+    }
+
+    // The deposit header of a deposited cell, or of a withdrawing cell whose data is the
+    // deposit block number. Panic if the cell is not a DAO cell.
+    fn dao_deposit_header(&self, cell: &CellMeta) -> HeaderView {
+        assert_eq!(
+            cell.cell_output.type_().to_opt(),
+            Some(self.dao_type_script()),
+            "cell {} is not a DAO cell",
+            cell.out_point
+        );
+        let data = cell.mem_cell_data.as_ref().expect("cell data is loaded");
+        assert_eq!(
+            data.len(),
+            8,
+            "DAO cell {} has {} bytes of data, expected 8",
+            cell.out_point,
+            data.len()
+        );
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&data[0..8]);
+        match u64::from_le_bytes(buf) {
+            0 => self.get_block(cell_block_hash(cell)).header(),
+            deposit_block_number => self.get_block_by_number(deposit_block_number).header(),
+        }
+    }
+}
+
+/// The earliest epoch when the withdrawing cell can be unlocked, i.e. the deposit epoch plus
+/// the passed epochs rounded up to a multiple of [`DAO_LOCK_PERIOD_EPOCHS`].
+pub fn dao_minimal_unlock_epoch(
+    deposit_header: &HeaderView,
+    withdrawing_header: &HeaderView,
+) -> EpochNumberWithFraction {
+    let deposit_point = deposit_header.epoch();
+    let withdrawing_point = withdrawing_header.epoch();
+    let withdrawing_fraction = withdrawing_point.index() * deposit_point.length();
+    let deposit_fraction = deposit_point.index() * withdrawing_point.length();
+    let passed_epochs = if withdrawing_fraction > deposit_fraction {
+        withdrawing_point.number() - deposit_point.number() + 1
+    } else {
+        withdrawing_point.number() - deposit_point.number()
+    };
+    let lock_epochs = (passed_epochs + (DAO_LOCK_PERIOD_EPOCHS - 1)) / DAO_LOCK_PERIOD_EPOCHS
+        * DAO_LOCK_PERIOD_EPOCHS;
+    EpochNumberWithFraction::new(
+        deposit_point.number() + lock_epochs,
+        deposit_point.index(),
+        deposit_point.length(),
+    )
+}
+
+fn cell_block_hash(cell: &CellMeta) -> Byte32 {
+    cell.transaction_info
+        .as_ref()
+        .expect("cell is committed")
+        .block_hash
+        .clone()
+}
@@ -1,5 +1,8 @@
 mod always_success;
+mod app_config;
 mod builder;
+mod bulk_mining;
+mod dao;
 mod genesis_block_info;
 mod get_transaction;
 mod get_transaction_cycles;
@@ -8,10 +11,17 @@ mod mining;
 mod node;
 mod node_options;
 mod p2p;
+mod processes;
 mod rpc;
 #[cfg(feature = "with_subscribe")]
 mod subscribe;
+mod upgrade;
 
+pub use self::p2p::{BanGuard, BanTarget};
 pub use builder::BuildInstruction;
+pub use bulk_mining::BulkMiningOptions;
+pub use dao::{dao_minimal_unlock_epoch, DAO_LOCK_PERIOD_EPOCHS};
 pub use node::Node;
 pub use node_options::NodeOptions;
+pub use processes::{kill_nodes_of, node_log_paths_of};
+pub use upgrade::UpgradeOptions;
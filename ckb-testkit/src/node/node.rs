@@ -1,7 +1,8 @@
+use super::app_config::copy_app_config;
 use crate::error;
 use crate::rpc::RpcClient;
 use crate::util::{find_available_port, temp_path};
-use crate::NodeOptions;
+use crate::{ckb_binaries, ckb_binary, CkbBinary, NodeOptions};
 use ckb_indexer::{
     indexer::Indexer,
     store::{RocksdbStore, Store},
@@ -11,20 +12,25 @@ use ckb_types::core::BlockView;
 use fs_extra::dir::CopyOptions;
 use reqwest::Url;
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::{self, Child, Command, Stdio};
+use std::sync::{Arc, Mutex};
 use std::thread::sleep;
 use std::time::{Duration, Instant};
 
-struct ProcessGuard(pub Child);
+struct ProcessGuard(pub Arc<Mutex<Child>>);
 
 impl Drop for ProcessGuard {
     fn drop(&mut self) {
-        let _x = self
-            .0
+        let mut child = self.0.lock().unwrap();
+        // Already exited, e.g. stopped by `Node::graceful_stop`
+        if let Ok(Some(_)) = child.try_wait() {
+            return;
+        }
+        let _x = child
             .kill()
             .map_err(|err| error!("failed to kill ckb process, error: {}", err));
-        let _y = self.0.wait();
+        let _y = child.wait();
     }
 }
 
@@ -94,8 +100,10 @@ impl Clone for Node {
 }
 
 impl Node {
-    pub fn init<S: ToString>(case_name: S, node_options: NodeOptions, is_ckb2021: bool) -> Self {
+    /// Panics if `node_options.ckb_binary` is not registered, see `register_ckb_binary`.
+    pub fn init<S: ToString>(case_name: S, node_options: NodeOptions) -> Self {
         let case_name = case_name.to_string();
+        let is_ckb2021 = registered_binary(&node_options.ckb_binary).is_ckb2021();
         let rpc_port = find_available_port();
         let p2p_port = find_available_port();
         let working_dir = prepare_working_dir(&case_name, &node_options, rpc_port, p2p_port);
@@ -197,7 +205,21 @@ impl Node {
     }
 
     pub fn start(&mut self) {
-        let binary = &self.node_options.ckb_binary;
+        if let Err((code, message)) = self.start_process() {
+            error!("{}", message);
+            process::exit(code);
+        }
+    }
+
+    /// Like `start`, but return an error instead of exiting the process if the node exits
+    /// before its RPC is up, e.g. when it refuses to open the database.
+    pub fn try_start(&mut self) -> Result<(), String> {
+        self.start_process().map_err(|(_, message)| message)
+    }
+
+    // Return the exit code and the reason if the node exits before its RPC is up
+    fn start_process(&mut self) -> Result<(), (i32, String)> {
+        let binary = self.ckb_binary().path;
         let mut child_process = Command::new(&binary)
             .env("RUST_BACKTRACE", "full")
             .args(&[
@@ -218,7 +240,7 @@ impl Node {
                     err
                 )
             });
-        let local_node_info = self.wait_for_node_up(&mut child_process);
+        let local_node_info = self.wait_for_node_up(&mut child_process)?;
         let consensus = self.rpc_client().get_consensus();
         let genesis_block = self.get_block_by_number(0);
         let indexer = {
@@ -229,6 +251,8 @@ impl Node {
 
         self.consensus = Some(consensus);
         self.genesis_block = Some(genesis_block);
+        let child_process = Arc::new(Mutex::new(child_process));
+        super::processes::register(&child_process, self.log_path());
         self._guard = Some(ProcessGuard(child_process));
         self.node_id = Some(local_node_info.node_id);
         self.p2p_address = Some(local_node_info.addresses[0].address.clone());
@@ -240,6 +264,7 @@ impl Node {
             self.p2p_address.as_ref().expect("checked"),
             self.log_path().display()
         );
+        Ok(())
     }
 
     pub fn node_name(&self) -> &str {
@@ -250,6 +275,10 @@ impl Node {
         &self.node_options
     }
 
+    pub fn ckb_binary(&self) -> CkbBinary {
+        registered_binary(&self.node_options.ckb_binary)
+    }
+
     pub fn working_dir(&self) -> PathBuf {
         self.working_dir.clone()
     }
@@ -307,32 +336,50 @@ impl Node {
         }
     }
 
-    fn wait_for_node_up(&self, child_process: &mut Child) -> LocalNode {
+    /// Stop the node by SIGTERM and wait for it to exit, so that ckb shuts down cleanly and
+    /// flushes its database, unlike `stop` which kills it. The node is killed if it does not
+    /// exit within 60 seconds. On non-Unix platforms it is the same as `stop`.
+    pub fn graceful_stop(&mut self) {
+        crate::info!(
+            "[Node {}] GRACEFUL STOP log_path: {}",
+            self.node_name(),
+            self.log_path().display(),
+        );
+        if let Some(guard) = self._guard.take() {
+            terminate(&guard.0);
+        }
+    }
+
+    fn wait_for_node_up(&self, child_process: &mut Child) -> Result<LocalNode, (i32, String)> {
         let start_time = Instant::now();
         while start_time.elapsed() <= Duration::from_secs(60) {
             if let Ok(local_node_info) = self.rpc_client().inner().local_node_info() {
                 let _x = self.rpc_client().tx_pool_info();
-                return local_node_info;
+                return Ok(local_node_info);
             }
             match child_process.try_wait() {
                 Ok(None) => sleep(std::time::Duration::from_secs(1)),
                 Ok(Some(status)) => {
-                    error!(
-                        "{} node crashed, {}, log_path: {}",
-                        self.node_name(),
-                        status,
-                        self.log_path().display()
-                    );
-                    process::exit(status.code().unwrap());
+                    return Err((
+                        status.code().unwrap_or(255),
+                        format!(
+                            "{} node crashed, {}, log_path: {}",
+                            self.node_name(),
+                            status,
+                            self.log_path().display()
+                        ),
+                    ));
                 }
                 Err(error) => {
-                    error!(
-                        "{} node crashed with reason: {}, log_path: {}",
-                        self.node_name(),
-                        error,
-                        self.log_path().display()
-                    );
-                    process::exit(255);
+                    return Err((
+                        255,
+                        format!(
+                            "{} node crashed with reason: {}, log_path: {}",
+                            self.node_name(),
+                            error,
+                            self.log_path().display()
+                        ),
+                    ));
                 }
             }
         }
@@ -359,56 +406,36 @@ fn prepare_working_dir(
             err
         )
     });
+    copy_dir_content(source_database, target_database);
+    copy_dir_content(source_chain_spec, &working_dir);
+    copy_app_config(source_app_config, &working_dir);
+    configure_ports(&working_dir, rpc_port, p2p_port);
+
+    working_dir
+}
+
+pub(super) fn copy_dir_content(source: &str, target: &Path) {
     fs_extra::dir::copy(
-        source_database,
-        target_database,
-        &CopyOptions {
-            content_only: true,
-            ..Default::default()
-        },
-    )
-    .unwrap_or_else(|err| {
-        panic!(
-            "failed to copy {} to {}, error: {}",
-            source_database,
-            target_database.display(),
-            err
-        )
-    });
-    fs_extra::dir::copy(
-        source_chain_spec,
-        &working_dir,
-        &CopyOptions {
-            content_only: true,
-            ..Default::default()
-        },
-    )
-    .unwrap_or_else(|err| {
-        panic!(
-            "failed to copy {} to {}, error: {}",
-            source_chain_spec,
-            working_dir.display(),
-            err
-        )
-    });
-    fs_extra::dir::copy(
-        source_app_config,
-        &working_dir,
+        source,
+        target,
         &CopyOptions {
             content_only: true,
+            overwrite: true,
             ..Default::default()
         },
     )
     .unwrap_or_else(|err| {
         panic!(
             "failed to copy {} to {}, error: {}",
-            source_app_config,
-            working_dir.display(),
+            source,
+            target.display(),
             err
         )
     });
+}
 
-    // Modify rpc port and p2p port in ckb.toml
+// Modify rpc port and p2p port in ckb.toml
+pub(super) fn configure_ports(working_dir: &Path, rpc_port: u16, p2p_port: u16) {
     let app_config = working_dir.join("ckb.toml");
     let content = fs::read_to_string(&app_config)
         .unwrap_or_else(|err| panic!("failed to read {}, error: {}", app_config.display(), err));
@@ -417,6 +444,42 @@ fn prepare_working_dir(
         .replace("__P2P_PORT__", &p2p_port.to_string());
     fs::write(&app_config, content)
         .unwrap_or_else(|err| panic!("failed to write {}, error: {}", app_config.display(), err));
+}
 
-    working_dir
+#[cfg(unix)]
+fn terminate(child: &Mutex<Child>) {
+    let pid = child.lock().unwrap().id();
+    let _x = Command::new("kill")
+        .args(["-TERM", &pid.to_string()])
+        .status()
+        .map_err(|err| error!("failed to terminate ckb process {}, error: {}", pid, err));
+    let start_time = Instant::now();
+    while start_time.elapsed() <= Duration::from_secs(60) {
+        // Do not hold the lock while sleeping, see `kill_nodes_of`
+        match child.lock().unwrap().try_wait() {
+            Ok(None) => {}
+            Ok(Some(_)) | Err(_) => return,
+        }
+        sleep(Duration::from_millis(500));
+    }
+    error!(
+        "ckb process {} is still running after SIGTERM, kill it",
+        pid
+    );
+}
+
+#[cfg(not(unix))]
+fn terminate(_child: &Mutex<Child>) {}
+
+pub(super) fn registered_binary(name: &str) -> CkbBinary {
+    ckb_binary(name).unwrap_or_else(|| {
+        panic!(
+            "unregistered ckb binary \"{}\", registered: {:?}",
+            name,
+            ckb_binaries()
+                .into_iter()
+                .map(|binary| binary.name)
+                .collect::<Vec<_>>()
+        )
+    })
 }
@@ -1,10 +1,11 @@
-use std::path::PathBuf;
-
 #[derive(Debug, Clone, Default)]
 pub struct NodeOptions {
     pub node_name: String,
-    pub ckb_binary: PathBuf,
+    /// Name of the ckb binary, see `register_ckb_binary`
+    pub ckb_binary: String,
     pub initial_database: &'static str,
     pub chain_spec: &'static str,
+    /// The directory holding `ckb.toml`, or `ckb.patch.toml` deriving it from another
+    /// directory
     pub app_config: &'static str,
 }
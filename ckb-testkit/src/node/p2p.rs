@@ -1,5 +1,10 @@
 use crate::util::wait_until;
-use crate::Node;
+use crate::{Connector, Node};
+use ckb_jsonrpc_types::BannedAddr;
+use p2p::multiaddr::{Multiaddr, Protocol};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
 
 impl Node {
     pub fn is_p2p_connected(&self, other: &Node) -> bool {
@@ -72,46 +77,193 @@ impl Node {
         }
     }
 
-    // TODO so confusing
-    // workaround for banned address checking (because we are using loopback address)
-    // 1. checking banned addresses is empty
-    // 2. connecting outbound peer and checking banned addresses is not empty
-    // 3. clear banned addresses
+    /// Connect to `other` and wait until `other` is banned by this node, e.g. because it is on
+    /// a different chain. The ban list is restored afterwards.
     pub fn p2p_connect_and_wait_ban(&self, other: &Node) {
-        let other_node_id = other.node_id().to_string();
-        let other_p2p_address = other.p2p_address();
-        let rpc_client = self.rpc_client();
-        assert!(
-            rpc_client.get_banned_addresses().is_empty(),
-            "banned addresses should be empty"
-        );
-        rpc_client.add_node(other_node_id, other_p2p_address);
-        let banned = wait_until(10, || {
-            let banned_addresses = rpc_client.get_banned_addresses();
-            !banned_addresses.is_empty()
-        });
-
-        // Clear
-        if banned {
-            let banned_addresses = rpc_client.get_banned_addresses();
-            for banned_address in banned_addresses {
-                rpc_client.set_ban(
-                    banned_address.address,
-                    "delete".to_owned(),
-                    None,
-                    None,
-                    None,
-                )
-            }
-        } else {
+        let _ban_guard = self.ban_guard();
+        self.p2p_connect_uncheck(other);
+        if let Err(err) = self.wait_banned(other, Duration::from_secs(10)) {
             panic!(
                 "timeout to connect_and_wait_ban peer, \
-                self node name: {}, self node id: {}, other node name: {}, other node id: {}",
+                self node name: {}, self node id: {}, other node name: {}, other node id: {}, error: {}",
                 self.node_name(),
                 self.node_id(),
                 other.node_name(),
                 other.node_id(),
+                err,
+            );
+        }
+    }
+
+    /// Return the ban entry of `target` if it is banned by this node.
+    pub fn get_banned<T: BanTarget + ?Sized>(&self, target: &T) -> Option<BannedAddr> {
+        let address = target.ban_address();
+        self.rpc_client()
+            .get_banned_addresses()
+            .into_iter()
+            .find(|banned| banned.address == address)
+    }
+
+    /// Wait until `target` is banned by this node, return the ban entry which carries
+    /// `ban_reason` and `ban_until`.
+    pub fn wait_banned<T: BanTarget + ?Sized>(
+        &self,
+        target: &T,
+        timeout: Duration,
+    ) -> Result<BannedAddr, String> {
+        let start_time = Instant::now();
+        loop {
+            if let Some(banned) = self.get_banned(target) {
+                return Ok(banned);
+            }
+            if start_time.elapsed() >= timeout {
+                return Err(format!(
+                    "timeout when waiting \"{}\" to ban {}, banned addresses: {:?}",
+                    self.node_name(),
+                    target.ban_address(),
+                    self.rpc_client().get_banned_addresses()
+                ));
+            }
+            sleep(Duration::from_millis(100));
+        }
+    }
+
+    pub fn assert_not_banned<T: BanTarget + ?Sized>(&self, target: &T) {
+        if let Some(banned) = self.get_banned(target) {
+            panic!(
+                "\"{}\" should not ban {}, but banned until {} for reason \"{}\"",
+                self.node_name(),
+                banned.address,
+                banned.ban_until.value(),
+                banned.ban_reason
             );
         }
     }
+
+    /// Remove `target` from the ban list if it is banned.
+    pub fn unban<T: BanTarget + ?Sized>(&self, target: &T) {
+        if let Some(banned) = self.get_banned(target) {
+            self.rpc_client()
+                .set_ban(banned.address, "delete".to_owned(), None, None, None);
+        }
+    }
+
+    /// Snapshot the ban list, which is restored when the returned guard drops.
+    ///
+    /// ```ignore
+    /// let _ban_guard = node.ban_guard();
+    /// let banned = node.wait_banned(&connector, Duration::from_secs(5))?;
+    /// ```
+    pub fn ban_guard(&self) -> BanGuard<'_> {
+        BanGuard {
+            node: self,
+            snapshot: self.rpc_client().get_banned_addresses(),
+        }
+    }
+}
+
+/// Something which can be banned by a node. Nodes ban IP networks, e.g. `"127.0.0.1/32"`, as
+/// listed by the `get_banned_addresses` RPC.
+pub trait BanTarget {
+    fn ban_address(&self) -> String;
+}
+
+impl BanTarget for str {
+    /// Accept an IP, e.g. `"127.0.0.1"`, or an IP network, e.g. `"127.0.0.1/32"`.
+    fn ban_address(&self) -> String {
+        match self.parse::<IpAddr>() {
+            Ok(ip) => ip_network(ip),
+            Err(_) => self.to_string(),
+        }
+    }
+}
+
+impl BanTarget for String {
+    fn ban_address(&self) -> String {
+        self.as_str().ban_address()
+    }
+}
+
+impl BanTarget for Node {
+    fn ban_address(&self) -> String {
+        let address = self
+            .p2p_address()
+            .parse::<Multiaddr>()
+            .unwrap_or_else(|err| panic!("invalid p2p address of {}: {}", self.node_name(), err));
+        ip_network(multiaddr_ip(&address).unwrap_or(Ipv4Addr::LOCALHOST.into()))
+    }
+}
+
+impl BanTarget for Connector {
+    /// Connectors run on the same host as the nodes, so a connector is banned by the IP of its
+    /// listening addresses, or the loopback IP if it does not listen.
+    fn ban_address(&self) -> String {
+        let ip = self
+            .listening_addresses()
+            .iter()
+            .find_map(multiaddr_ip)
+            .unwrap_or(Ipv4Addr::LOCALHOST.into());
+        ip_network(ip)
+    }
+}
+
+/// Restore the ban list of a node when dropped, see [`Node::ban_guard`].
+pub struct BanGuard<'a> {
+    node: &'a Node,
+    snapshot: Vec<BannedAddr>,
+}
+
+impl Drop for BanGuard<'_> {
+    fn drop(&mut self) {
+        let rpc_client = self.node.rpc_client();
+        let current = rpc_client.get_banned_addresses();
+        for banned in current.iter() {
+            if self
+                .snapshot
+                .iter()
+                .all(|snapshot| snapshot.address != banned.address)
+            {
+                rpc_client.set_ban(
+                    banned.address.clone(),
+                    "delete".to_owned(),
+                    None,
+                    None,
+                    None,
+                );
+            }
+        }
+        for snapshot in self.snapshot.iter() {
+            if current
+                .iter()
+                .all(|banned| banned.address != snapshot.address)
+            {
+                rpc_client.set_ban(
+                    snapshot.address.clone(),
+                    "insert".to_owned(),
+                    Some(snapshot.ban_until),
+                    Some(true),
+                    Some(snapshot.ban_reason.clone()),
+                );
+            }
+        }
+    }
+}
+
+// Treat the unspecified IP as the loopback one, as nodes listening on `0.0.0.0` are dialed
+// via the loopback interface
+fn multiaddr_ip(address: &Multiaddr) -> Option<IpAddr> {
+    address.iter().find_map(|protocol| match protocol {
+        Protocol::Ip4(ip) if ip.is_unspecified() => Some(Ipv4Addr::LOCALHOST.into()),
+        Protocol::Ip4(ip) => Some(ip.into()),
+        Protocol::Ip6(ip) if ip.is_unspecified() => Some(Ipv6Addr::LOCALHOST.into()),
+        Protocol::Ip6(ip) => Some(ip.into()),
+        _ => None,
+    })
+}
+
+fn ip_network(ip: IpAddr) -> String {
+    match ip {
+        IpAddr::V4(ip) => format!("{}/32", ip),
+        IpAddr::V6(ip) => format!("{}/128", ip),
+    }
 }
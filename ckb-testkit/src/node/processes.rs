@@ -0,0 +1,78 @@
+//! The ckb processes started by `Node::start`, grouped by the `LOG_TARGET` of the starting
+//! thread, which is the name of the running case. A runner uses it to kill the nodes of a case
+//! that exceeds its timeout, and to collect their log paths.
+use lazy_static::lazy_static;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::process::Child;
+use std::sync::{Arc, Mutex, Weak};
+
+lazy_static! {
+    static ref PROCESSES: Mutex<Processes> = Mutex::new(Processes::default());
+}
+
+#[derive(Default)]
+struct Processes {
+    started: HashMap<String, Vec<StartedProcess>>,
+    killed: HashSet<String>,
+}
+
+struct StartedProcess {
+    child: Weak<Mutex<Child>>,
+    log_path: PathBuf,
+}
+
+pub(super) fn register(child: &Arc<Mutex<Child>>, log_path: PathBuf) {
+    let log_target = crate::LOG_TARGET.with(|c| c.borrow().clone());
+    let mut processes = PROCESSES.lock().unwrap();
+    // The case has timed out while this node was starting
+    if processes.killed.contains(&log_target) {
+        kill(child);
+    }
+    processes
+        .started
+        .entry(log_target)
+        .or_default()
+        .push(StartedProcess {
+            child: Arc::downgrade(child),
+            log_path,
+        });
+}
+
+/// Kill the running ckb processes started under `log_target`. The processes started under it
+/// afterwards are killed at once.
+pub fn kill_nodes_of(log_target: &str) {
+    let mut processes = PROCESSES.lock().unwrap();
+    processes.killed.insert(log_target.to_string());
+    if let Some(started) = processes.started.get(log_target) {
+        for process in started.iter() {
+            if let Some(child) = process.child.upgrade() {
+                kill(&child);
+            }
+        }
+    }
+}
+
+/// Log paths of the nodes started under `log_target`, including the stopped ones.
+pub fn node_log_paths_of(log_target: &str) -> Vec<PathBuf> {
+    PROCESSES
+        .lock()
+        .unwrap()
+        .started
+        .get(log_target)
+        .map(|started| {
+            started
+                .iter()
+                .map(|process| process.log_path.clone())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// The `ProcessGuard` reaps the process when the node is dropped
+fn kill(child: &Mutex<Child>) {
+    let mut child = child.lock().unwrap();
+    if let Err(err) = child.kill() {
+        crate::error!("failed to kill ckb process {}, error: {}", child.id(), err);
+    }
+}
@@ -0,0 +1,118 @@
+use super::app_config::copy_app_config;
+use super::node::{configure_ports, copy_dir_content, registered_binary};
+use crate::rpc::RpcClient;
+use crate::Node;
+use reqwest::Url;
+use std::process::{Command, Stdio};
+
+/// Options of [`Node::upgrade_binary`].
+#[derive(Debug, Clone, Default)]
+pub struct UpgradeOptions {
+    /// Replace the chain spec in the working directory, e.g. with one configuring the hardfork
+    /// features the new binary supports. Default keeps the current one.
+    pub chain_spec: Option<&'static str>,
+    /// Replace `ckb.toml` in the working directory. The ports are kept. Default keeps the
+    /// current one.
+    pub app_config: Option<&'static str>,
+    /// Run `ckb migrate` with the new binary before restarting, which ckb requires when the
+    /// new version changes the database schema. Only binaries since v0.100.0 support it.
+    pub migrate: bool,
+}
+
+impl Node {
+    /// Restart the node on the same data directory with the ckb binary registered under
+    /// `ckb_binary`, like an operator upgrading, or downgrading, a node: stop it gracefully,
+    /// swap the binary, optionally migrate the database, and start it again with the RPC
+    /// flavor of the new binary.
+    ///
+    /// The node must have been started. Clones of it must be dropped before, as they hold the
+    /// indexer database open.
+    pub fn upgrade_binary(&mut self, ckb_binary: &str, options: UpgradeOptions) {
+        if let Err(err) = self.try_upgrade_binary(ckb_binary, options) {
+            panic!("[Node {}] {}", self.node_name(), err);
+        }
+    }
+
+    /// Like `upgrade_binary`, but return an error if `ckb migrate` fails or the new binary
+    /// exits on starting, e.g. an old binary refusing the database migrated by a newer one.
+    /// The node is left stopped then, and can be upgraded again.
+    pub fn try_upgrade_binary(
+        &mut self,
+        ckb_binary: &str,
+        options: UpgradeOptions,
+    ) -> Result<(), String> {
+        let binary = registered_binary(ckb_binary);
+        let rpc_url = self.rpc_client().url().to_string();
+        let rpc_port = Url::parse(&rpc_url)
+            .ok()
+            .and_then(|url| url.port())
+            .unwrap_or_else(|| panic!("invalid rpc url \"{}\"", rpc_url));
+        let p2p_port = self
+            .p2p_address
+            .as_ref()
+            .and_then(|address| address.rsplit('/').next())
+            .and_then(|port| port.parse::<u16>().ok())
+            .unwrap_or_else(|| panic!("[Node {}] upgrade a started node", self.node_name()));
+        crate::info!(
+            "[Node {}] UPGRADE from \"{}\" to \"{}\", {}",
+            self.node_name(),
+            self.node_options.ckb_binary,
+            binary.name,
+            binary.version
+        );
+
+        self.graceful_stop();
+        // Release the indexer database, which `start` opens again
+        self.indexer = None;
+
+        self.node_options.ckb_binary = binary.name.clone();
+        if let Some(chain_spec) = options.chain_spec {
+            copy_dir_content(chain_spec, &self.working_dir());
+            self.node_options.chain_spec = chain_spec;
+        }
+        if let Some(app_config) = options.app_config {
+            copy_app_config(app_config, &self.working_dir());
+            configure_ports(&self.working_dir(), rpc_port, p2p_port);
+            self.node_options.app_config = app_config;
+        }
+        if options.migrate {
+            let status = Command::new(&binary.path)
+                .args([
+                    "-C",
+                    &self.working_dir().to_string_lossy(),
+                    "migrate",
+                    "--force",
+                ])
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::inherit())
+                .status()
+                .map_err(|err| {
+                    format!(
+                        "failed to run ckb migrate, binary: {}, error: {}",
+                        binary.path.display(),
+                        err
+                    )
+                })?;
+            if !status.success() {
+                return Err(format!(
+                    "ckb migrate exited with {}, binary: {}",
+                    status,
+                    binary.path.display()
+                ));
+            }
+        }
+
+        self.rpc_client = RpcClient::new(&rpc_url, binary.is_ckb2021());
+        self.try_start()?;
+        let version = self.rpc_client().local_node_info().version;
+        if !version.starts_with(binary.version_number()) {
+            return Err(format!(
+                "expected version {} after upgrading, got \"{}\"",
+                binary.version_number(),
+                version
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+//! Prebuilt RISC-V scripts bundled with ckb-testkit, located in `testdata/script/`.
+//!
+//! Every binary comes with a `<name>.md` noting where it is built from. The scripts written for
+//! ckb-testkit are assembled from `testdata/script/src/`, see `testdata/script/src/build.sh`.
+
+use crate::{always_success_collector, Deployer, NoCollector, Node, TxBuilder};
+use ckb_types::{
+    bytes::Bytes,
+    core::{Capacity, ScriptHashType, TransactionView},
+    packed::{Byte32, CellOutput, OutPoint, Script, WitnessArgs},
+    prelude::*,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TestScript {
+    /// Always exit with 0, the same as the genesis always-success cell.
+    AlwaysSuccess,
+    /// Always exit with 0, but differs from `AlwaysSuccess` in binary, hence data hash.
+    AnotherAlwaysSuccess,
+    /// `exec` the cell specified by its script args, which is an `ExecParams`, see
+    /// https://github.com/keroro520/ckb_exec_test_script/tree/master/contracts/exec_caller
+    ExecCaller,
+    /// Exit with 0 if it is executed by `ExecCaller` with the expected argv, see
+    /// https://github.com/keroro520/ckb_exec_test_script/tree/master/contracts/exec_callee
+    ExecCallee,
+    /// Always exit with 1.
+    AlwaysFailure,
+    /// Loop N times then exit with 0, costing at least N cycles, see
+    /// [`TestScript::cycle_burner_args`].
+    CycleBurner,
+    /// `spawn` the cell specified by its script args, wait for it and exit with its exit code,
+    /// see [`SpawnParams`]. `spawn` is a syscall of ckb2023, so the script fails with
+    /// InvalidEcall on earlier VMs.
+    SpawnCaller,
+    /// Invoke a loading syscall specified by its script args and exit with the syscall's return
+    /// code, see [`TestScript::syscall_exerciser_args`].
+    SyscallExerciser,
+    /// Exit with 0 if its script args equal the first witness of its script group, otherwise
+    /// exit with 1, or 2 if the witness is missing.
+    ArgsAsserter,
+}
+
+/// The `source` argument of CKB syscalls.
+#[repr(u64)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Source {
+    Input = 0x0000000000000001,
+    Output = 0x0000000000000002,
+    CellDep = 0x0000000000000003,
+    HeaderDep = 0x0000000000000004,
+    GroupInput = 0x0100000000000001,
+    GroupOutput = 0x0100000000000002,
+}
+
+/// The loading syscalls invoked by [`TestScript::SyscallExerciser`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LoadSyscall {
+    Header,
+    Cell,
+    Witness,
+    CellData,
+    Input,
+}
+
+/// The script args of [`TestScript::ExecCaller`], which passes them to `exec` as is.
+///
+/// `place` is 0 for cell data and 1 for witness. Serialized as the molecule table
+/// `{ source: Uint32, place: Uint32, index: Uint32, bounds: Uint64 }`, the same as
+/// `ExecParams` of https://github.com/keroro520/ckb_exec_test_script.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ExecParams {
+    pub source: u32,
+    pub place: u32,
+    pub index: u32,
+    pub bounds: u64,
+}
+
+/// The script args of [`TestScript::SpawnCaller`], which passes them to `spawn` as is.
+///
+/// `place` is 0 for cell data and 1 for witness. Serialized as the 4 fields in u64
+/// little-endian, in the order `index | source | place | bounds`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpawnParams {
+    pub index: u64,
+    pub source: Source,
+    pub place: u64,
+    pub bounds: u64,
+}
+
+impl ExecParams {
+    pub fn serialize(&self) -> Bytes {
+        let fields: [Vec<u8>; 4] = [
+            self.source.to_le_bytes().to_vec(),
+            self.place.to_le_bytes().to_vec(),
+            self.index.to_le_bytes().to_vec(),
+            self.bounds.to_le_bytes().to_vec(),
+        ];
+        let header_size = 4 * (1 + fields.len());
+        let total_size = header_size + fields.iter().map(Vec::len).sum::<usize>();
+        let mut bytes = Vec::with_capacity(total_size);
+        bytes.extend_from_slice(&(total_size as u32).to_le_bytes());
+        let mut offset = header_size;
+        for field in fields.iter() {
+            bytes.extend_from_slice(&(offset as u32).to_le_bytes());
+            offset += field.len();
+        }
+        for field in fields.iter() {
+            bytes.extend_from_slice(field);
+        }
+        Bytes::from(bytes)
+    }
+}
+
+impl SpawnParams {
+    pub fn serialize(&self) -> Bytes {
+        let mut bytes = Vec::with_capacity(32);
+        bytes.extend_from_slice(&self.index.to_le_bytes());
+        bytes.extend_from_slice(&(self.source as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.place.to_le_bytes());
+        bytes.extend_from_slice(&self.bounds.to_le_bytes());
+        Bytes::from(bytes)
+    }
+}
+
+impl TestScript {
+    pub fn all() -> Vec<TestScript> {
+        vec![
+            TestScript::AlwaysSuccess,
+            TestScript::AnotherAlwaysSuccess,
+            TestScript::ExecCaller,
+            TestScript::ExecCallee,
+            TestScript::AlwaysFailure,
+            TestScript::CycleBurner,
+            TestScript::SpawnCaller,
+            TestScript::SyscallExerciser,
+            TestScript::ArgsAsserter,
+        ]
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TestScript::AlwaysSuccess => "always_success",
+            TestScript::AnotherAlwaysSuccess => "another_always_success",
+            TestScript::ExecCaller => "exec_caller",
+            TestScript::ExecCallee => "exec_callee",
+            TestScript::AlwaysFailure => "always_failure",
+            TestScript::CycleBurner => "cycle_burner",
+            TestScript::SpawnCaller => "spawn_caller",
+            TestScript::SyscallExerciser => "syscall_exerciser",
+            TestScript::ArgsAsserter => "args_asserter",
+        }
+    }
+
+    pub fn binary(&self) -> Bytes {
+        let binary: &'static [u8] = match self {
+            TestScript::AlwaysSuccess => include_bytes!("../testdata/script/always_success"),
+            TestScript::AnotherAlwaysSuccess => {
+                include_bytes!("../testdata/script/another_always_success")
+            }
+            TestScript::ExecCaller => include_bytes!("../testdata/script/exec_caller"),
+            TestScript::ExecCallee => include_bytes!("../testdata/script/exec_callee"),
+            TestScript::AlwaysFailure => include_bytes!("../testdata/script/always_failure"),
+            TestScript::CycleBurner => include_bytes!("../testdata/script/cycle_burner"),
+            TestScript::SpawnCaller => include_bytes!("../testdata/script/spawn_caller"),
+            TestScript::SyscallExerciser => {
+                include_bytes!("../testdata/script/syscall_exerciser")
+            }
+            TestScript::ArgsAsserter => include_bytes!("../testdata/script/args_asserter"),
+        };
+        Bytes::from_static(binary)
+    }
+
+    pub fn data_hash(&self) -> Byte32 {
+        CellOutput::calc_data_hash(&self.binary())
+    }
+
+    /// The script args of `CycleBurner` looping `n` times.
+    pub fn cycle_burner_args(n: u64) -> Bytes {
+        Bytes::from(n.to_le_bytes().to_vec())
+    }
+
+    /// The script args of `SyscallExerciser` loading the `index`-th item from `source` via
+    /// `syscall`.
+    pub fn syscall_exerciser_args(syscall: LoadSyscall, index: u64, source: Source) -> Bytes {
+        let syscall: u8 = match syscall {
+            LoadSyscall::Header => 0,
+            LoadSyscall::Cell => 1,
+            LoadSyscall::Witness => 2,
+            LoadSyscall::CellData => 3,
+            LoadSyscall::Input => 4,
+        };
+        let mut bytes = vec![syscall];
+        bytes.extend_from_slice(&index.to_le_bytes());
+        bytes.extend_from_slice(&(source as u64).to_le_bytes());
+        Bytes::from(bytes)
+    }
+}
+
+impl Deployer {
+    /// Deploy `script` named as [`TestScript::name`], locked by always-success.
+    pub fn deploy_test_script(&mut self, node: &Node, script: TestScript) {
+        let binary = script.binary();
+        let output = CellOutput::new_builder()
+            .lock(node.always_success_script())
+            .build_exact_capacity(Capacity::bytes(binary.len()).unwrap())
+            .unwrap();
+        self.deploy(node, script.name(), output, binary.pack())
+    }
+
+    /// The script running the deployed `script` with `args`, referenced via data hash with
+    /// `hash_type`, i.e. `Data` runs it on VM0 and `Data1` on VM1.
+    pub fn test_script(
+        &self,
+        script: TestScript,
+        hash_type: ScriptHashType,
+        args: Bytes,
+    ) -> Script {
+        Script::new_builder()
+            .code_hash(script.data_hash())
+            .hash_type(hash_type.into())
+            .args(args.pack())
+            .build()
+    }
+
+    /// Build the transaction running `lock`, whose code is the deployed `script`, by spending a
+    /// cell locked by it.
+    ///
+    /// Create a cell locked by `lock` and commit it, then return the transaction built from
+    /// `builder` plus the locked cell as an input with `witness`, the cell dep of `script` and
+    /// the always-success change. The returned transaction is not sent.
+    pub fn build_test_script_invocation(
+        &self,
+        node: &Node,
+        script: TestScript,
+        lock: Script,
+        witness: WitnessArgs,
+        builder: TxBuilder,
+    ) -> Result<TransactionView, String> {
+        let output = CellOutput::new_builder()
+            .lock(lock)
+            .capacity(Capacity::bytes(1000).unwrap().pack())
+            .build();
+        let create_tx = TxBuilder::new()
+            .output(output, Default::default())
+            .cell_dep(node.always_success_cell_dep())
+            .change_lock(node.always_success_script())
+            .build(&mut always_success_collector(node))?;
+        node.rpc_client()
+            .send_transaction_result(create_tx.data().into())
+            .map_err(|err| err.to_string())?;
+        while !node.is_transaction_committed(&create_tx) {
+            node.mine(1);
+        }
+
+        let locked = node
+            .get_cell_meta(OutPoint::new(create_tx.hash(), 0))
+            .ok_or_else(|| format!("cell locked by {} is not live", script.name()))?;
+        builder
+            .input_with_witness(locked, 0, witness)
+            .cell_dep(self.cell_dep(script.name()))
+            .change_lock(node.always_success_script())
+            .build(&mut NoCollector)
+    }
+
+    /// Send the transaction of [`Deployer::build_test_script_invocation`]. Return the sent
+    /// transaction, or the error of `send_transaction`, which carries the script error.
+    pub fn invoke_test_script(
+        &self,
+        node: &Node,
+        script: TestScript,
+        lock: Script,
+        witness: WitnessArgs,
+        builder: TxBuilder,
+    ) -> Result<TransactionView, String> {
+        let tx = self.build_test_script_invocation(node, script, lock, witness, builder)?;
+        node.rpc_client()
+            .send_transaction_result(tx.data().into())
+            .map_err(|err| err.to_string())?;
+        Ok(tx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exec_params_serialize() {
+        let params = ExecParams {
+            source: Source::CellDep as u32,
+            place: 1,
+            index: 2,
+            bounds: 0x0000_0010_0000_0020,
+        };
+        let expected: Vec<u8> = vec![
+            40, 0, 0, 0, // total size
+            20, 0, 0, 0, 24, 0, 0, 0, 28, 0, 0, 0, 32, 0, 0, 0, // offsets
+            3, 0, 0, 0, // source
+            1, 0, 0, 0, // place
+            2, 0, 0, 0, // index
+            0x20, 0, 0, 0, 0x10, 0, 0, 0, // bounds
+        ];
+        assert_eq!(params.serialize(), Bytes::from(expected));
+    }
+
+    #[test]
+    fn syscall_exerciser_args() {
+        let args = TestScript::syscall_exerciser_args(LoadSyscall::Witness, 1, Source::GroupInput);
+        let mut expected = vec![2, 1, 0, 0, 0, 0, 0, 0, 0];
+        expected.extend_from_slice(&[1, 0, 0, 0, 0, 0, 0, 1]);
+        assert_eq!(args, Bytes::from(expected));
+    }
+
+    #[test]
+    fn bundled_scripts_are_elf() {
+        for script in TestScript::all() {
+            assert!(
+                script.binary().starts_with(b"\x7fELF"),
+                "{} is not an ELF",
+                script.name()
+            );
+        }
+    }
+}
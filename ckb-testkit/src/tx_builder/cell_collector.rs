@@ -0,0 +1,39 @@
+use crate::{Node, User};
+use ckb_types::core::cell::CellMeta;
+use std::collections::VecDeque;
+
+/// Supplies live cells to [`TxBuilder`](crate::TxBuilder) when the given inputs are not enough
+/// to cover the outputs and the fee.
+pub trait CellCollector {
+    /// Return the next live cell, or `None` if exhausted.
+    fn collect(&mut self) -> Option<CellMeta>;
+}
+
+impl CellCollector for VecDeque<CellMeta> {
+    fn collect(&mut self) -> Option<CellMeta> {
+        self.pop_front()
+    }
+}
+
+/// Collect nothing, i.e. the given inputs must be enough.
+pub struct NoCollector;
+
+impl CellCollector for NoCollector {
+    fn collect(&mut self) -> Option<CellMeta> {
+        None
+    }
+}
+
+/// Collect the spendable always-success cells of `node`.
+pub fn always_success_collector(node: &Node) -> VecDeque<CellMeta> {
+    node.get_spendable_always_success_cells()
+        .into_iter()
+        .collect()
+}
+
+/// Collect the spendable single secp256k1 cells of `user`.
+pub fn single_secp256k1_collector(node: &Node, user: &User) -> VecDeque<CellMeta> {
+    user.get_spendable_single_secp256k1_cells(node)
+        .into_iter()
+        .collect()
+}
@@ -0,0 +1,326 @@
+mod cell_collector;
+
+pub use cell_collector::{
+    always_success_collector, single_secp256k1_collector, CellCollector, NoCollector,
+};
+
+use crate::{placeholder_transaction, sign_transaction, User};
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, Capacity, TransactionBuilder, TransactionView},
+    packed::{Byte32, CellDep, CellInput, CellOutput, Script, WitnessArgs},
+    prelude::*,
+};
+
+/// The default fee rate, in shannons per kilobyte, which equals ckb's default
+/// `min_fee_rate`.
+pub const DEFAULT_FEE_RATE: u64 = 1000;
+
+/// Build a balanced and signed transaction.
+///
+/// * The given outputs are the target outputs.
+/// * Inputs are the given inputs, followed by the cells supplied by a [`CellCollector`] until
+///   they cover the outputs and the fee.
+/// * The fee is `fee_rate * serialized_size_in_block / 1000`.
+/// * The rest capacity goes to a change output locked by `change_lock`, appended after the
+///   target outputs. When the rest is less than the change output's occupied capacity, more
+///   inputs are collected; if the collector is exhausted, building fails, unless
+///   `allow_burn_change` is set, in which case the rest is paid as fee. Without `change_lock`,
+///   all the rest is paid as fee.
+/// * The script groups are signed by `signers` once the transaction is balanced, see
+///   [`sign_transaction`]. The fee is computed from the transaction with placeholder
+///   signatures, see [`placeholder_transaction`].
+///
+/// ```ignore
+/// let tx = TxBuilder::new()
+///     .output(output, Default::default())
+///     .cell_dep(owner.single_secp256k1_cell_dep())
+///     .change_lock(owner.single_secp256k1_lock_script_via_type())
+///     .signer(owner.clone())
+///     .build(&mut single_secp256k1_collector(node, &owner))?;
+/// ```
+#[derive(Clone)]
+pub struct TxBuilder {
+    inputs: Vec<Input>,
+    outputs: Vec<(CellOutput, Bytes)>,
+    cell_deps: Vec<CellDep>,
+    header_deps: Vec<Byte32>,
+    since: u64,
+    fee_rate: u64,
+    change_lock: Option<Script>,
+    allow_burn_change: bool,
+    signers: Vec<User>,
+}
+
+impl Default for TxBuilder {
+    fn default() -> Self {
+        Self {
+            inputs: Vec::new(),
+            outputs: Vec::new(),
+            cell_deps: Vec::new(),
+            header_deps: Vec::new(),
+            since: 0,
+            fee_rate: DEFAULT_FEE_RATE,
+            change_lock: None,
+            allow_burn_change: false,
+            signers: Vec::new(),
+        }
+    }
+}
+
+impl TxBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn input(self, cell: CellMeta) -> Self {
+        let since = self.since;
+        self.input_with_since(cell, since)
+    }
+
+    pub fn input_with_since(mut self, cell: CellMeta, since: u64) -> Self {
+        self.inputs.push(Input::new(cell, since));
+        self
+    }
+
+    pub fn inputs<I: IntoIterator<Item = CellMeta>>(mut self, cells: I) -> Self {
+        let since = self.since;
+        self.inputs
+            .extend(cells.into_iter().map(|cell| Input::new(cell, since)));
+        self
+    }
+
+    /// Add an input with its witness, the lock field of the witness is filled when signing.
+    pub fn input_with_witness(mut self, cell: CellMeta, since: u64, witness: WitnessArgs) -> Self {
+        let mut input = Input::new(cell, since);
+        input.witness = Some(witness);
+        self.inputs.push(input);
+        self
+    }
+
+    /// Add an input which contributes `capacity` rather than its own capacity, e.g. a Nervos
+    /// DAO withdrawing cell contributes its maximum withdraw.
+    pub fn input_with_capacity(
+        mut self,
+        cell: CellMeta,
+        since: u64,
+        witness: WitnessArgs,
+        capacity: u64,
+    ) -> Self {
+        let mut input = Input::new(cell, since);
+        input.witness = Some(witness);
+        input.capacity = capacity;
+        self.inputs.push(input);
+        self
+    }
+
+    pub fn output(mut self, output: CellOutput, output_data: Bytes) -> Self {
+        self.outputs.push((output, output_data));
+        self
+    }
+
+    pub fn outputs<I: IntoIterator<Item = (CellOutput, Bytes)>>(mut self, outputs: I) -> Self {
+        self.outputs.extend(outputs);
+        self
+    }
+
+    pub fn cell_dep(mut self, cell_dep: CellDep) -> Self {
+        self.cell_deps.push(cell_dep);
+        self
+    }
+
+    pub fn cell_deps<I: IntoIterator<Item = CellDep>>(mut self, cell_deps: I) -> Self {
+        self.cell_deps.extend(cell_deps);
+        self
+    }
+
+    pub fn header_dep(mut self, header_dep: Byte32) -> Self {
+        self.header_deps.push(header_dep);
+        self
+    }
+
+    /// The since of the inputs added afterwards and the collected inputs.
+    pub fn since(mut self, since: u64) -> Self {
+        self.since = since;
+        self
+    }
+
+    /// Fee rate in shannons per kilobyte.
+    pub fn fee_rate(mut self, fee_rate: u64) -> Self {
+        self.fee_rate = fee_rate;
+        self
+    }
+
+    pub fn change_lock(mut self, change_lock: Script) -> Self {
+        self.change_lock = Some(change_lock);
+        self
+    }
+
+    /// Pay the rest capacity as fee when it cannot afford a change output and the collector
+    /// is exhausted, instead of failing.
+    pub fn allow_burn_change(mut self, allow_burn_change: bool) -> Self {
+        self.allow_burn_change = allow_burn_change;
+        self
+    }
+
+    pub fn signer(mut self, signer: User) -> Self {
+        self.signers.push(signer);
+        self
+    }
+
+    pub fn signers<I: IntoIterator<Item = User>>(mut self, signers: I) -> Self {
+        self.signers.extend(signers);
+        self
+    }
+
+    pub fn build<C: CellCollector + ?Sized>(
+        mut self,
+        collector: &mut C,
+    ) -> Result<TransactionView, String> {
+        loop {
+            if let Some(tx) = self.try_build(false)? {
+                return self.sign(&tx);
+            }
+            match collector.collect() {
+                Some(cell) => {
+                    let since = self.since;
+                    self.inputs.push(Input::new(cell, since));
+                }
+                None => {
+                    let tx = self.try_build(true)?.ok_or_else(|| {
+                        format!(
+                            "insufficient capacity, inputs: {}, outputs: {}",
+                            self.inputs_capacity(),
+                            self.outputs_capacity(),
+                        )
+                    })?;
+                    return self.sign(&tx);
+                }
+            }
+        }
+    }
+
+    /// The fee of the signed `tx` at this builder's fee rate.
+    pub fn calculate_fee(&self, tx: &TransactionView) -> u64 {
+        let size = tx.data().as_reader().serialized_size_in_block() as u64;
+        self.fee_rate.saturating_mul(size) / 1000
+    }
+
+    // Return the balanced but unsigned transaction, or `None` if the current inputs are not
+    // enough. When `exhausted` is true, the rest capacity that cannot afford a change output
+    // fails the building, unless `allow_burn_change` is set.
+    fn try_build(&self, exhausted: bool) -> Result<Option<TransactionView>, String> {
+        let inputs_capacity = self.inputs_capacity();
+        let outputs_capacity = self.outputs_capacity();
+        if inputs_capacity < outputs_capacity {
+            return Ok(None);
+        }
+        let rest = inputs_capacity - outputs_capacity;
+
+        if let Some(ref change_lock) = self.change_lock {
+            let change_output = CellOutput::new_builder().lock(change_lock.clone()).build();
+            let min_change_capacity = change_output
+                .occupied_capacity(Capacity::zero())
+                .map_err(|err| err.to_string())?
+                .as_u64();
+            // The capacity field is fixed-size, so the fee does not depend on its value
+            let fee = self.estimate_fee(&self.assemble(Some(change_output.clone())));
+            if rest >= fee + min_change_capacity {
+                let change_output = change_output
+                    .as_builder()
+                    .capacity((rest - fee).pack())
+                    .build();
+                return Ok(Some(self.assemble(Some(change_output))));
+            } else if !exhausted {
+                return Ok(None);
+            } else if !self.allow_burn_change {
+                return Err(format!(
+                    "insufficient capacity for change, rest: {}, fee: {}, change occupied \
+                     capacity: {}",
+                    rest, fee, min_change_capacity
+                ));
+            }
+        }
+
+        let tx = self.assemble(None);
+        if rest >= self.estimate_fee(&tx) {
+            Ok(Some(tx))
+        } else {
+            Ok(None)
+        }
+    }
+
+    // The fee of `tx` after signed
+    fn estimate_fee(&self, tx: &TransactionView) -> u64 {
+        self.calculate_fee(&placeholder_transaction(
+            tx,
+            &self.input_cells(),
+            &self.signers,
+        ))
+    }
+
+    fn assemble(&self, change_output: Option<CellOutput>) -> TransactionView {
+        let mut builder = TransactionBuilder::default()
+            .inputs(
+                self.inputs
+                    .iter()
+                    .map(|input| CellInput::new(input.cell.out_point.clone(), input.since)),
+            )
+            .outputs(self.outputs.iter().map(|(output, _)| output.clone()))
+            .outputs_data(self.outputs.iter().map(|(_, data)| data.pack()))
+            .cell_deps(self.cell_deps.clone())
+            .header_deps(self.header_deps.clone())
+            .witnesses(self.inputs.iter().map(|input| {
+                input
+                    .witness
+                    .as_ref()
+                    .map(|witness| witness.as_bytes().pack())
+                    .unwrap_or_default()
+            }));
+        if let Some(change_output) = change_output {
+            builder = builder
+                .output(change_output)
+                .output_data(Default::default());
+        }
+        builder.build()
+    }
+
+    fn sign(&self, tx: &TransactionView) -> Result<TransactionView, String> {
+        sign_transaction(tx, &self.input_cells(), &self.signers)
+    }
+
+    fn input_cells(&self) -> Vec<CellMeta> {
+        self.inputs.iter().map(|input| input.cell.clone()).collect()
+    }
+
+    fn inputs_capacity(&self) -> u64 {
+        self.inputs.iter().map(|input| input.capacity).sum()
+    }
+
+    fn outputs_capacity(&self) -> u64 {
+        self.outputs
+            .iter()
+            .map(|(output, _)| Unpack::<u64>::unpack(&output.capacity()))
+            .sum()
+    }
+}
+
+#[derive(Clone)]
+struct Input {
+    cell: CellMeta,
+    since: u64,
+    witness: Option<WitnessArgs>,
+    capacity: u64,
+}
+
+impl Input {
+    fn new(cell: CellMeta, since: u64) -> Self {
+        let capacity = cell.capacity().as_u64();
+        Self {
+            cell,
+            since,
+            witness: None,
+            capacity,
+        }
+    }
+}
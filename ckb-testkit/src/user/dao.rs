@@ -0,0 +1,47 @@
+use crate::{single_secp256k1_collector, NoCollector, Node, User};
+use ckb_types::core::{cell::CellMeta, TransactionView};
+
+impl User {
+    /// Deposit `capacity` from the user's single secp256k1 cells into a DAO cell locked by
+    /// `single_secp256k1_lock_script_via_type`.
+    pub fn dao_deposit(&self, node: &Node, capacity: u64) -> TransactionView {
+        node.dao_deposit_builder(self.single_secp256k1_lock_script_via_type(), capacity)
+            .cell_dep(self.single_secp256k1_cell_dep())
+            .change_lock(self.single_secp256k1_lock_script_via_type())
+            .signer(self.clone())
+            .build(&mut single_secp256k1_collector(node, self))
+            .unwrap_or_else(|err| panic!("failed to build dao deposit transaction, error: {}", err))
+    }
+
+    /// Phase-1 withdraw the `deposited` cell, the fee is paid by the user's single secp256k1
+    /// cells.
+    pub fn dao_withdraw_phase1(&self, node: &Node, deposited: &CellMeta) -> TransactionView {
+        node.dao_withdraw_phase1_builder(deposited)
+            .cell_dep(self.single_secp256k1_cell_dep())
+            .change_lock(self.single_secp256k1_lock_script_via_type())
+            .signer(self.clone())
+            .build(&mut single_secp256k1_collector(node, self))
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to build dao withdraw phase-1 transaction, error: {}",
+                    err
+                )
+            })
+    }
+
+    /// Phase-2 withdraw the `withdrawing` cell into a cell locked by
+    /// `single_secp256k1_lock_script_via_type`.
+    pub fn dao_withdraw_phase2(&self, node: &Node, withdrawing: &CellMeta) -> TransactionView {
+        node.dao_withdraw_phase2_builder(withdrawing)
+            .cell_dep(self.single_secp256k1_cell_dep())
+            .change_lock(self.single_secp256k1_lock_script_via_type())
+            .signer(self.clone())
+            .build(&mut NoCollector)
+            .unwrap_or_else(|err| {
+                panic!(
+                    "failed to build dao withdraw phase-2 transaction, error: {}",
+                    err
+                )
+            })
+    }
+}
@@ -1,5 +1,11 @@
+pub mod dao;
+pub mod multisig;
+pub mod sighash;
 pub mod single_secp256k1;
 
+pub use multisig::MultisigConfig;
+pub use sighash::{placeholder_transaction, sighash_all_message, sign_transaction};
+
 use ckb_crypto::secp::Privkey;
 use ckb_types::core::BlockView;
 
@@ -8,6 +14,7 @@ pub struct User {
     // a workaround to get out-point of system script cells
     genesis_block: BlockView,
     single_secp256k1_privkey: Option<Privkey>,
+    multisig_config: Option<MultisigConfig>,
 }
 
 impl User {
@@ -15,10 +22,28 @@ impl User {
         Self {
             genesis_block,
             single_secp256k1_privkey,
+            multisig_config: None,
         }
     }
 
+    pub fn with_multisig_config(mut self, multisig_config: MultisigConfig) -> Self {
+        self.multisig_config = Some(multisig_config);
+        self
+    }
+
     pub fn single_secp256k1_privkey(&self) -> Option<&Privkey> {
         self.single_secp256k1_privkey.as_ref()
     }
+
+    pub fn multisig_config(&self) -> &MultisigConfig {
+        if let Some(ref multisig_config) = self.multisig_config {
+            multisig_config
+        } else {
+            unreachable!("multisig_config unset")
+        }
+    }
+
+    pub fn has_multisig_config(&self) -> bool {
+        self.multisig_config.is_some()
+    }
 }
@@ -0,0 +1,231 @@
+use crate::{
+    sighash_all_message, Node, User, GENESIS_DEP_GROUP_TRANSACTION_INDEX,
+    GENESIS_MULTISIG_ALL_DEP_GROUP_CELL_INDEX, MULTISIG_ALL_TYPE_HASH,
+    SYSTEM_CELL_MULTISIG_ALL_INDEX,
+};
+use ckb_crypto::secp::{Pubkey, Signature};
+use ckb_hash::blake2b_256;
+use ckb_types::core::cell::CellMeta;
+use ckb_types::{
+    bytes::{Bytes, BytesMut},
+    core::{DepType, ScriptHashType, TransactionView},
+    packed::{self, CellDep, OutPoint, Script, WitnessArgs},
+    prelude::*,
+    H160, H256,
+};
+
+/// The multisig configuration of `secp256k1_blake160_multisig_all`.
+///
+/// A transaction is unlocked when it carries `threshold` signatures from `pubkeys`, and the
+/// first `require_first_n` pubkeys must all be among the signers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MultisigConfig {
+    pub require_first_n: u8,
+    pub threshold: u8,
+    pub pubkeys: Vec<Pubkey>,
+}
+
+impl MultisigConfig {
+    pub fn new(require_first_n: u8, threshold: u8, pubkeys: Vec<Pubkey>) -> Result<Self, String> {
+        if pubkeys.is_empty() || pubkeys.len() > u8::MAX as usize {
+            return Err(format!("invalid number of pubkeys: {}", pubkeys.len()));
+        }
+        if threshold == 0 || threshold as usize > pubkeys.len() {
+            return Err(format!(
+                "invalid threshold {}, should be in [1, {}]",
+                threshold,
+                pubkeys.len()
+            ));
+        }
+        if require_first_n > threshold {
+            return Err(format!(
+                "invalid require_first_n {}, should not be greater than threshold {}",
+                require_first_n, threshold
+            ));
+        }
+        for (i, pubkey) in pubkeys.iter().enumerate() {
+            if pubkeys[..i].contains(pubkey) {
+                return Err(format!("duplicated pubkey {:?}", pubkey));
+            }
+        }
+        Ok(Self {
+            require_first_n,
+            threshold,
+            pubkeys,
+        })
+    }
+
+    pub fn pubkey_hashes(&self) -> Vec<H160> {
+        self.pubkeys
+            .iter()
+            .map(|pubkey| H160::from_slice(&blake2b_256(pubkey.serialize())[0..20]).unwrap())
+            .collect()
+    }
+
+    /// The multisig script, `S | R | M | N | PubKeyHash1 | PubKeyHash2 | ...`, which is also
+    /// the prefix of the witness lock.
+    pub fn multisig_script(&self) -> Bytes {
+        let mut script = BytesMut::from(
+            &[
+                0u8,
+                self.require_first_n,
+                self.threshold,
+                self.pubkeys.len() as u8,
+            ][..],
+        );
+        for pubkey_hash in self.pubkey_hashes() {
+            script.extend_from_slice(pubkey_hash.as_bytes());
+        }
+        script.freeze()
+    }
+
+    pub fn hash160(&self) -> H160 {
+        H160::from_slice(&blake2b_256(self.multisig_script())[0..20]).unwrap()
+    }
+
+    /// Lock args, `blake160(multisig_script)`, optionally followed by the since value which
+    /// every input locked by it must satisfy.
+    pub fn lock_args(&self, since: Option<u64>) -> Bytes {
+        let mut args = BytesMut::from(self.hash160().as_bytes());
+        if let Some(since) = since {
+            args.extend_from_slice(&since.to_le_bytes());
+        }
+        args.freeze()
+    }
+
+    /// The witness used to compute the signing message, the signatures are zero-filled.
+    pub fn placeholder_witness(&self) -> WitnessArgs {
+        let mut lock = BytesMut::from(self.multisig_script().as_ref());
+        lock.extend_from_slice(&vec![0u8; 65 * self.threshold as usize]);
+        WitnessArgs::new_builder()
+            .lock(Some(lock.freeze()).pack())
+            .build()
+    }
+
+    /// Combine the signatures into the witness lock. The signatures must be exactly `threshold`.
+    pub fn combined_witness(&self, signatures: &[Signature]) -> WitnessArgs {
+        assert_eq!(
+            signatures.len(),
+            self.threshold as usize,
+            "multisig requires {} signatures, but got {}",
+            self.threshold,
+            signatures.len(),
+        );
+        let mut lock = BytesMut::from(self.multisig_script().as_ref());
+        for signature in signatures {
+            lock.extend_from_slice(&signature.serialize());
+        }
+        WitnessArgs::new_builder()
+            .lock(Some(lock.freeze()).pack())
+            .build()
+    }
+}
+
+impl User {
+    pub fn multisig_lock_script_via_type(&self) -> Script {
+        Script::new_builder()
+            .hash_type(ScriptHashType::Type.into())
+            .code_hash(MULTISIG_ALL_TYPE_HASH.pack())
+            .args(self.multisig_config().lock_args(None).pack())
+            .build()
+    }
+
+    pub fn multisig_lock_script_via_data(&self) -> Script {
+        Script::new_builder()
+            .hash_type(ScriptHashType::Data.into())
+            .code_hash(self.multisig_data_hash())
+            .args(self.multisig_config().lock_args(None).pack())
+            .build()
+    }
+
+    /// The multisig lock script whose inputs must satisfy `since`.
+    pub fn multisig_lock_script_with_since(&self, since: u64) -> Script {
+        Script::new_builder()
+            .hash_type(ScriptHashType::Type.into())
+            .code_hash(MULTISIG_ALL_TYPE_HASH.pack())
+            .args(self.multisig_config().lock_args(Some(since)).pack())
+            .build()
+    }
+
+    pub fn multisig_data_hash(&self) -> packed::Byte32 {
+        let data = self
+            .genesis_block
+            .transaction(0)
+            .expect("genesis cellbase")
+            .outputs_data()
+            .get(SYSTEM_CELL_MULTISIG_ALL_INDEX as usize)
+            .expect("multisig system cell");
+        packed::CellOutput::calc_data_hash(&data.raw_data())
+    }
+
+    pub fn multisig_out_point(&self) -> OutPoint {
+        OutPoint::new_builder()
+            .tx_hash(
+                self.genesis_block
+                    .transaction(GENESIS_DEP_GROUP_TRANSACTION_INDEX)
+                    .expect("index genesis dep-group transaction")
+                    .hash(),
+            )
+            .index(GENESIS_MULTISIG_ALL_DEP_GROUP_CELL_INDEX.pack())
+            .build()
+    }
+
+    pub fn multisig_cell_dep(&self) -> CellDep {
+        CellDep::new_builder()
+            .out_point(self.multisig_out_point())
+            .dep_type(DepType::DepGroup.into())
+            .build()
+    }
+
+    /// The message every multisig signer signs over, for the script group consisting of the
+    /// first input only. Use [`sign_transaction`] for transactions of other shapes.
+    ///
+    /// [`sign_transaction`]: crate::sign_transaction
+    pub fn multisig_signing_message(&self, tx: &TransactionView) -> H256 {
+        let placeholder = self.multisig_config().placeholder_witness();
+        sighash_all_message(tx, &[0], &placeholder)
+    }
+
+    /// Sign `tx` with this user's single_secp256k1 privkey, which must be one of the multisig
+    /// pubkeys. Collect `threshold` partial signatures and pass them to
+    /// [`MultisigConfig::combined_witness`].
+    pub fn multisig_partial_signature(&self, tx: &TransactionView) -> Signature {
+        let pubkey = self.single_secp256k1_pubkey();
+        assert!(
+            self.multisig_config().pubkeys.contains(&pubkey),
+            "user's pubkey {:?} is not a member of the multisig config",
+            pubkey
+        );
+        let message = self.multisig_signing_message(tx);
+        self.sign_recoverable(&message)
+    }
+
+    /// Sign `tx` with `signers`' single_secp256k1 privkeys and combine the signatures.
+    pub fn multisig_signed_witness(&self, tx: &TransactionView, signers: &[&User]) -> WitnessArgs {
+        let message = self.multisig_signing_message(tx);
+        let signatures = signers
+            .iter()
+            .map(|signer| signer.sign_recoverable(&message))
+            .collect::<Vec<_>>();
+        self.multisig_config().combined_witness(&signatures)
+    }
+
+    pub fn get_spendable_multisig_cells(&self, node: &Node) -> Vec<CellMeta> {
+        let mut live_out_points = Vec::new();
+        live_out_points.extend(
+            node.indexer()
+                .get_live_cells_by_lock_script(&self.multisig_lock_script_via_type())
+                .expect("indexer get_live_cells_by_lock_script"),
+        );
+        live_out_points.extend(
+            node.indexer()
+                .get_live_cells_by_lock_script(&self.multisig_lock_script_via_data())
+                .expect("indexer get_live_cells_by_lock_script"),
+        );
+        live_out_points
+            .into_iter()
+            .filter_map(|out_point| node.get_cell_meta(out_point))
+            .filter(|cell_meta| cell_meta.data_bytes == 0)
+            .collect()
+    }
+}
@@ -0,0 +1,241 @@
+use crate::{User, MULTISIG_ALL_TYPE_HASH};
+use ckb_types::{
+    bytes::Bytes,
+    core::{cell::CellMeta, ScriptHashType, TransactionView},
+    packed::{Script, WitnessArgs},
+    prelude::*,
+    H256,
+};
+use std::convert::TryInto;
+
+/// Compute the sighash-all signing message of a script group.
+///
+/// `group` is the input indexes of the script group, in ascending order, and `first_witness` is
+/// the group's first witness with its lock filled with zeros. The message is
+///
+/// ```text
+/// blake2b(
+///     tx_hash
+///     | len(first_witness) | first_witness
+///     | len(witness) | witness             for the rest witnesses of the group
+///     | len(witness) | witness             for witnesses beyond the inputs count
+/// )
+/// ```
+pub fn sighash_all_message(
+    tx: &TransactionView,
+    group: &[usize],
+    first_witness: &WitnessArgs,
+) -> H256 {
+    let witnesses = tx.witnesses();
+    let mut blake2b = ckb_hash::new_blake2b();
+    blake2b.update(&tx.hash().raw_data());
+    let first_witness = first_witness.as_bytes();
+    blake2b.update(&(first_witness.len() as u64).to_le_bytes());
+    blake2b.update(&first_witness);
+    for &index in group.iter().skip(1) {
+        let witness = witnesses
+            .get(index)
+            .map(|witness| witness.raw_data())
+            .unwrap_or_default();
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(&witness);
+    }
+    for index in tx.inputs().len()..witnesses.len() {
+        let witness = witnesses.get(index).expect("checked index").raw_data();
+        blake2b.update(&(witness.len() as u64).to_le_bytes());
+        blake2b.update(&witness);
+    }
+    let mut message = [0u8; 32];
+    blake2b.finalize(&mut message);
+    H256::from(message)
+}
+
+/// Sign every script group of `tx` which is unlockable by `users`, `inputs` are the resolved
+/// input cells of `tx`, in the same order.
+///
+/// Single secp256k1 groups are signed by the user owning the lock. Multisig groups are signed
+/// by the users whose pubkeys are in the multisig config, preferring the pubkeys in config order,
+/// so that `require_first_n` is satisfied when possible. Groups that no user recognizes, e.g.
+/// always-success, keep their witnesses untouched.
+///
+/// Return an error if a multisig group has fewer than `threshold` signers among `users`.
+pub fn sign_transaction(
+    tx: &TransactionView,
+    inputs: &[CellMeta],
+    users: &[User],
+) -> Result<TransactionView, String> {
+    let mut tx = resize_witnesses(tx, inputs);
+    for (lock, group) in script_groups(inputs) {
+        let owner = match users.iter().find(|user| user.lock_kind(&lock).is_some()) {
+            Some(owner) => owner,
+            None => continue,
+        };
+        let first_witness = parse_witness(&tx, group[0]);
+        let placeholder = first_witness
+            .clone()
+            .as_builder()
+            .lock(Some(placeholder_lock(owner, &lock)).pack())
+            .build();
+        let message = sighash_all_message(&tx, &group, &placeholder);
+        let signature = match owner.lock_kind(&lock).expect("checked") {
+            LockKind::SingleSecp256k1 => Bytes::from(owner.sign_recoverable(&message).serialize()),
+            LockKind::Multisig => {
+                let config = owner.multisig_config();
+                let signatures = config
+                    .pubkeys
+                    .iter()
+                    .filter_map(|pubkey| {
+                        users.iter().find(|user| {
+                            user.single_secp256k1_privkey().is_some()
+                                && &user.single_secp256k1_pubkey() == pubkey
+                        })
+                    })
+                    .take(config.threshold as usize)
+                    .map(|signer| signer.sign_recoverable(&message))
+                    .collect::<Vec<_>>();
+                if signatures.len() < config.threshold as usize {
+                    return Err(format!(
+                        "multisig group of input {} requires {} signers, but only {} of the \
+                         users are members of the multisig config",
+                        group[0],
+                        config.threshold,
+                        signatures.len()
+                    ));
+                }
+                config
+                    .combined_witness(&signatures)
+                    .lock()
+                    .to_opt()
+                    .expect("combined witness has lock")
+                    .raw_data()
+            }
+        };
+        let signed_witness = first_witness
+            .as_builder()
+            .lock(Some(signature).pack())
+            .build();
+        tx = set_witness(&tx, group[0], signed_witness);
+    }
+    Ok(tx)
+}
+
+/// Fill the witness locks of the script groups which [`sign_transaction`] would sign with
+/// zero-filled placeholders of the signatures' sizes. The result has the same size as the
+/// signed transaction, so it is used to compute the fee before signing.
+pub fn placeholder_transaction(
+    tx: &TransactionView,
+    inputs: &[CellMeta],
+    users: &[User],
+) -> TransactionView {
+    let mut tx = resize_witnesses(tx, inputs);
+    for (lock, group) in script_groups(inputs) {
+        let owner = match users.iter().find(|user| user.lock_kind(&lock).is_some()) {
+            Some(owner) => owner,
+            None => continue,
+        };
+        let placeholder = parse_witness(&tx, group[0])
+            .as_builder()
+            .lock(Some(placeholder_lock(owner, &lock)).pack())
+            .build();
+        tx = set_witness(&tx, group[0], placeholder);
+    }
+    tx
+}
+
+// #{ lock => [input index] }, in the order of first appearance
+fn script_groups(inputs: &[CellMeta]) -> Vec<(Script, Vec<usize>)> {
+    let mut groups: Vec<(Script, Vec<usize>)> = Vec::new();
+    for (index, input) in inputs.iter().enumerate() {
+        let lock = input.cell_output.lock();
+        match groups.iter_mut().find(|(script, _)| script == &lock) {
+            Some((_, group)) => group.push(index),
+            None => groups.push((lock, vec![index])),
+        }
+    }
+    groups
+}
+
+// Make sure every input has a witness
+fn resize_witnesses(tx: &TransactionView, inputs: &[CellMeta]) -> TransactionView {
+    assert_eq!(
+        tx.inputs().len(),
+        inputs.len(),
+        "the resolved inputs do not match the transaction's inputs"
+    );
+    let mut witnesses = tx.witnesses().into_iter().collect::<Vec<_>>();
+    if witnesses.len() < inputs.len() {
+        witnesses.resize(inputs.len(), Default::default());
+    }
+    tx.as_advanced_builder().set_witnesses(witnesses).build()
+}
+
+fn parse_witness(tx: &TransactionView, index: usize) -> WitnessArgs {
+    let raw = tx.witnesses().get(index).expect("resized").raw_data();
+    if raw.is_empty() {
+        WitnessArgs::default()
+    } else {
+        WitnessArgs::from_slice(&raw).unwrap_or_else(|err| {
+            panic!(
+                "failed to parse the witness of input {} as WitnessArgs, error: {}",
+                index, err
+            )
+        })
+    }
+}
+
+fn set_witness(tx: &TransactionView, index: usize, witness: WitnessArgs) -> TransactionView {
+    let mut witnesses = tx.witnesses().into_iter().collect::<Vec<_>>();
+    witnesses[index] = witness.as_bytes().pack();
+    tx.as_advanced_builder().set_witnesses(witnesses).build()
+}
+
+// The zero-filled witness lock of `lock`, which `owner` signs
+fn placeholder_lock(owner: &User, lock: &Script) -> Bytes {
+    match owner.lock_kind(lock).expect("owned lock") {
+        LockKind::SingleSecp256k1 => Bytes::from(vec![0u8; 65]),
+        LockKind::Multisig => owner
+            .multisig_config()
+            .placeholder_witness()
+            .lock()
+            .to_opt()
+            .expect("placeholder witness has lock")
+            .raw_data(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum LockKind {
+    SingleSecp256k1,
+    Multisig,
+}
+
+impl User {
+    /// Return which kind of lock `lock` is, if it belongs to this user.
+    pub(crate) fn lock_kind(&self, lock: &Script) -> Option<LockKind> {
+        if self.single_secp256k1_privkey().is_some()
+            && (lock == &self.single_secp256k1_lock_script_via_type()
+                || lock == &self.single_secp256k1_lock_script_via_data()
+                || lock == &self.single_secp256k1_lock_script_via_data1())
+        {
+            return Some(LockKind::SingleSecp256k1);
+        }
+        if self.has_multisig_config() {
+            let hash_type: ScriptHashType =
+                lock.hash_type().try_into().expect("checked by molecule");
+            let code_hash: H256 = lock.code_hash().unpack();
+            let is_multisig_code = match hash_type {
+                ScriptHashType::Type => code_hash == MULTISIG_ALL_TYPE_HASH,
+                _ => lock.code_hash() == self.multisig_data_hash(),
+            };
+            let args = lock.args().raw_data();
+            let hash160 = self.multisig_config().hash160();
+            if is_multisig_code
+                && (args.len() == 20 || args.len() == 28)
+                && &args[0..20] == hash160.as_bytes()
+            {
+                return Some(LockKind::Multisig);
+            }
+        }
+        None
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
-    Node, User, GENESIS_DEP_GROUP_TRANSACTION_INDEX, GENESIS_SIGHASH_ALL_DEP_GROUP_CELL_INDEX,
-    SIGHASH_ALL_DATA_HASH, SIGHASH_ALL_TYPE_HASH,
+    sighash_all_message, Node, User, GENESIS_DEP_GROUP_TRANSACTION_INDEX,
+    GENESIS_SIGHASH_ALL_DEP_GROUP_CELL_INDEX, SIGHASH_ALL_DATA_HASH, SIGHASH_ALL_TYPE_HASH,
 };
 use ckb_crypto::secp::{Message, Pubkey, Signature};
 use ckb_hash::blake2b_256;
@@ -11,7 +11,7 @@ use ckb_types::{
     core::{DepType, ScriptHashType, TransactionView},
     packed::{CellDep, OutPoint, Script, WitnessArgs},
     prelude::*,
-    H160, H256,
+    H160,
 };
 
 impl User {
@@ -71,26 +71,20 @@ impl User {
         }
     }
 
+    /// Sign the script group consisting of the first input only. Use [`sign_transaction`] for
+    /// transactions of other shapes.
+    ///
+    /// [`sign_transaction`]: crate::sign_transaction
     pub fn single_secp256k1_signed_witness(&self, tx: &TransactionView) -> WitnessArgs {
         if let Some(ref privkey) = self.single_secp256k1_privkey {
-            let tx_hash = tx.hash();
-            let mut blake2b = ckb_hash::new_blake2b();
-            let mut message = [0u8; 32];
-            blake2b.update(&tx_hash.raw_data());
             let witness_for_digest = WitnessArgs::new_builder()
                 .lock(Some(Bytes::from(vec![0u8; 65])).pack())
                 .build();
-            let witness_len = witness_for_digest.as_bytes().len() as u64;
-            blake2b.update(&witness_len.to_le_bytes());
-            blake2b.update(&witness_for_digest.as_bytes());
-            blake2b.finalize(&mut message);
-            let message = H256::from(message);
+            let message = sighash_all_message(tx, &[0], &witness_for_digest);
             let sig = privkey.sign_recoverable(&message).expect("sign");
             WitnessArgs::new_builder()
                 .lock(Some(Bytes::from(sig.serialize())).pack())
                 .build()
-            // .as_bytes()
-            // .pack()
         } else {
             unreachable!("single_secp256k1 unset")
         }
@@ -1,4 +1,7 @@
 pub mod macros;
+mod since;
+
+pub use since::{Since, SinceMetric};
 
 use ckb_types::core::{BlockNumber, EpochNumberWithFraction};
 use lazy_static::lazy_static;
@@ -0,0 +1,423 @@
+use super::{
+    FLAG_SINCE_BLOCK_NUMBER, FLAG_SINCE_EPOCH_NUMBER_WITH_FRACTION, FLAG_SINCE_RELATIVE,
+    FLAG_SINCE_TIMESTAMP,
+};
+use crate::Node;
+use ckb_types::core::{cell::CellMeta, BlockNumber, EpochNumber, EpochNumberWithFraction};
+use std::fmt;
+
+const SINCE_METRIC_MASK: u64 = 0x6000_0000_0000_0000;
+const SINCE_RESERVED_MASK: u64 = 0x1f00_0000_0000_0000;
+const SINCE_VALUE_MASK: u64 = 0x00ff_ffff_ffff_ffff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SinceMetric {
+    BlockNumber,
+    EpochNumberWithFraction,
+    Timestamp,
+}
+
+/// The `since` field of a transaction input.
+///
+/// `Display` prints the notation used in case tables:
+///
+/// * `abs_block(n)` / `rel_block(n)`
+/// * `abs(number, index, length)` / `rel(number, index, length)`
+/// * `abs_timestamp(t)` / `rel_timestamp(t)`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Since {
+    relative: bool,
+    metric: SinceMetric,
+    value: u64,
+}
+
+impl Since {
+    pub fn absolute_block_number(block_number: BlockNumber) -> Self {
+        Self::new(false, SinceMetric::BlockNumber, block_number)
+    }
+
+    pub fn relative_block_number(block_number: BlockNumber) -> Self {
+        Self::new(true, SinceMetric::BlockNumber, block_number)
+    }
+
+    pub fn absolute_epoch(epoch: EpochNumberWithFraction) -> Self {
+        Self::new(
+            false,
+            SinceMetric::EpochNumberWithFraction,
+            epoch.full_value(),
+        )
+    }
+
+    pub fn relative_epoch(epoch: EpochNumberWithFraction) -> Self {
+        Self::new(
+            true,
+            SinceMetric::EpochNumberWithFraction,
+            epoch.full_value(),
+        )
+    }
+
+    pub fn absolute_timestamp(timestamp: u64) -> Self {
+        Self::new(false, SinceMetric::Timestamp, timestamp)
+    }
+
+    pub fn relative_timestamp(timestamp: u64) -> Self {
+        Self::new(true, SinceMetric::Timestamp, timestamp)
+    }
+
+    fn new(relative: bool, metric: SinceMetric, value: u64) -> Self {
+        assert_eq!(
+            value & !SINCE_VALUE_MASK,
+            0,
+            "since value {:#x} overflows 56 bits",
+            value
+        );
+        Self {
+            relative,
+            metric,
+            value,
+        }
+    }
+
+    /// Decode a raw `since` value. Return an error when the reserved bits are set or the
+    /// metric flag is `0b11`, which ckb rejects as `InvalidSince`.
+    pub fn from_raw(raw: u64) -> Result<Self, String> {
+        if raw & SINCE_RESERVED_MASK != 0 {
+            return Err(format!("since {:#x} has reserved bits set", raw));
+        }
+        let metric = match raw & SINCE_METRIC_MASK {
+            FLAG_SINCE_BLOCK_NUMBER => SinceMetric::BlockNumber,
+            FLAG_SINCE_EPOCH_NUMBER_WITH_FRACTION => SinceMetric::EpochNumberWithFraction,
+            FLAG_SINCE_TIMESTAMP => SinceMetric::Timestamp,
+            _ => return Err(format!("since {:#x} has invalid metric flag", raw)),
+        };
+        Ok(Self {
+            relative: raw & FLAG_SINCE_RELATIVE != 0,
+            metric,
+            value: raw & SINCE_VALUE_MASK,
+        })
+    }
+
+    pub fn raw(&self) -> u64 {
+        let relative_flag = if self.relative {
+            FLAG_SINCE_RELATIVE
+        } else {
+            0
+        };
+        let metric_flag = match self.metric {
+            SinceMetric::BlockNumber => FLAG_SINCE_BLOCK_NUMBER,
+            SinceMetric::EpochNumberWithFraction => FLAG_SINCE_EPOCH_NUMBER_WITH_FRACTION,
+            SinceMetric::Timestamp => FLAG_SINCE_TIMESTAMP,
+        };
+        relative_flag | metric_flag | self.value
+    }
+
+    pub fn is_relative(&self) -> bool {
+        self.relative
+    }
+
+    pub fn metric(&self) -> SinceMetric {
+        self.metric
+    }
+
+    pub fn value(&self) -> u64 {
+        self.value
+    }
+
+    /// The epoch value, without normalization, if the metric is epoch.
+    pub fn epoch(&self) -> Option<EpochNumberWithFraction> {
+        if self.metric == SinceMetric::EpochNumberWithFraction {
+            Some(EpochNumberWithFraction::new_unchecked(
+                self.value & 0xff_ffff,
+                (self.value >> 24) & 0xffff,
+                (self.value >> 40) & 0xffff,
+            ))
+        } else {
+            None
+        }
+    }
+
+    /// Check the since value against the rules of RFC0030. Before RFC0030 activated, every
+    /// decodable since is valid. After activated, an epoch value must satisfy
+    /// `index < length`, or `index == 0 && length == 0`.
+    pub fn validate(&self, rfc0030_activated: bool) -> Result<(), String> {
+        if !rfc0030_activated {
+            return Ok(());
+        }
+        if let Some(epoch) = self.epoch() {
+            let is_zero_fraction = epoch.index() == 0 && epoch.length() == 0;
+            if !is_zero_fraction && epoch.index() >= epoch.length() {
+                return Err(format!("InvalidSince, invalid epoch fraction of {}", self));
+            }
+        }
+        Ok(())
+    }
+
+    /// The earliest block number at which a transaction spending `input` with this since
+    /// can be committed, assuming RFC0030 is activated.
+    ///
+    /// Epochs not reached yet are estimated with the current epoch's length, which is exact
+    /// when `permanent_difficulty_in_dummy` is set. Timestamp since is not supported as the
+    /// median time of future blocks is unknown.
+    pub fn earliest_block(&self, node: &Node, input: &CellMeta) -> Result<BlockNumber, String> {
+        let input_block = || {
+            let block_hash = input
+                .transaction_info
+                .as_ref()
+                .ok_or_else(|| "input cell is not committed".to_string())?
+                .block_hash
+                .clone();
+            let block = node.get_block(block_hash);
+            Ok::<_, String>((block.number(), block.epoch()))
+        };
+        self.earliest_block_with(input_block, |number| epoch_start_and_length(node, number))
+    }
+
+    // `earliest_block` given the number and epoch of the input's block, and the start number
+    // and length of an epoch
+    fn earliest_block_with<B, E>(
+        &self,
+        input_block: B,
+        epoch_start_and_length: E,
+    ) -> Result<BlockNumber, String>
+    where
+        B: Fn() -> Result<(BlockNumber, EpochNumberWithFraction), String>,
+        E: Fn(EpochNumber) -> (BlockNumber, u64),
+    {
+        self.validate(true)?;
+        match self.metric {
+            SinceMetric::BlockNumber => {
+                if self.relative {
+                    Ok(input_block()?.0 + self.value)
+                } else {
+                    Ok(self.value)
+                }
+            }
+            SinceMetric::EpochNumberWithFraction => {
+                let since_epoch = self.epoch().expect("checked metric");
+                // Represent epochs as rationals, `number + index / length`
+                let (since_numerator, since_denominator) = epoch_to_rational(since_epoch);
+                let (numerator, denominator) = if self.relative {
+                    let (base_numerator, base_denominator) = epoch_to_rational(input_block()?.1);
+                    (
+                        base_numerator * since_denominator + since_numerator * base_denominator,
+                        base_denominator * since_denominator,
+                    )
+                } else {
+                    (since_numerator, since_denominator)
+                };
+                let number = (numerator / denominator) as EpochNumber;
+                let (start_number, length) = epoch_start_and_length(number);
+                // The first block index satisfying `index / length >= fraction`
+                let remainder = numerator % denominator;
+                let index = (remainder * u128::from(length) + denominator - 1) / denominator;
+                Ok(start_number + index as u64)
+            }
+            SinceMetric::Timestamp => Err(format!(
+                "cannot compute the earliest block of timestamp since {}",
+                self
+            )),
+        }
+    }
+}
+
+impl From<Since> for u64 {
+    fn from(since: Since) -> u64 {
+        since.raw()
+    }
+}
+
+impl fmt::Display for Since {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let prefix = if self.relative { "rel" } else { "abs" };
+        match self.metric {
+            SinceMetric::BlockNumber => write!(f, "{}_block({})", prefix, self.value),
+            SinceMetric::EpochNumberWithFraction => {
+                let epoch = self.epoch().expect("checked metric");
+                write!(
+                    f,
+                    "{}({}, {}, {})",
+                    prefix,
+                    epoch.number(),
+                    epoch.index(),
+                    epoch.length()
+                )
+            }
+            SinceMetric::Timestamp => write!(f, "{}_timestamp({})", prefix, self.value),
+        }
+    }
+}
+
+// `(numerator, denominator)` of `number + index / length`, a zero length means zero fraction
+fn epoch_to_rational(epoch: EpochNumberWithFraction) -> (u128, u128) {
+    if epoch.length() == 0 {
+        (u128::from(epoch.number()), 1)
+    } else {
+        let length = u128::from(epoch.length());
+        (
+            u128::from(epoch.number()) * length + u128::from(epoch.index()),
+            length,
+        )
+    }
+}
+
+fn epoch_start_and_length(node: &Node, number: EpochNumber) -> (BlockNumber, u64) {
+    if let Some(epoch) = node.rpc_client().get_epoch_by_number(number) {
+        return (epoch.start_number.value(), epoch.length.value());
+    }
+    let current = node.rpc_client().get_current_epoch();
+    let length = current.length.value();
+    let start_number = current.start_number.value() + (number - current.number.value()) * length;
+    (start_number, length)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EPOCH_LENGTH: u64 = 1000;
+
+    fn epoch(number: u64, index: u64, length: u64) -> EpochNumberWithFraction {
+        EpochNumberWithFraction::new_unchecked(number, index, length)
+    }
+
+    // Every epoch has `EPOCH_LENGTH` blocks and the input is at block 1500, epoch (1, 500, 1000)
+    fn earliest_block(since: Since) -> Result<BlockNumber, String> {
+        since.earliest_block_with(
+            || Ok((1500, epoch(1, 500, EPOCH_LENGTH))),
+            |number| (number * EPOCH_LENGTH, EPOCH_LENGTH),
+        )
+    }
+
+    #[test]
+    fn from_raw_decodes_every_metric() {
+        let sinces = vec![
+            Since::absolute_block_number(100),
+            Since::relative_block_number(100),
+            Since::absolute_epoch(epoch(2, 1, 3)),
+            Since::relative_epoch(epoch(2, 1, 3)),
+            Since::absolute_timestamp(1_600_000_000),
+            Since::relative_timestamp(3600),
+        ];
+        for since in sinces {
+            assert_eq!(Since::from_raw(since.raw()), Ok(since));
+        }
+
+        let since = Since::from_raw(0xa000_0000_0000_0064).unwrap();
+        assert!(since.is_relative());
+        assert_eq!(since.metric(), SinceMetric::EpochNumberWithFraction);
+        assert_eq!(since.value(), 100);
+        let since = Since::from_raw(0x4000_0000_0000_0064).unwrap();
+        assert!(!since.is_relative());
+        assert_eq!(since.metric(), SinceMetric::Timestamp);
+        assert_eq!(since.value(), 100);
+    }
+
+    #[test]
+    fn from_raw_rejects_reserved_bits_and_invalid_metric() {
+        for reserved_bit in 56..61 {
+            let raw = (1 << reserved_bit) | 100;
+            assert!(
+                Since::from_raw(raw).is_err(),
+                "reserved bit {} should be rejected",
+                reserved_bit
+            );
+        }
+        assert!(Since::from_raw(0x6000_0000_0000_0064).is_err());
+        assert!(Since::from_raw(0xe000_0000_0000_0064).is_err());
+    }
+
+    #[test]
+    fn validate_epoch_fraction() {
+        let valid = vec![
+            epoch(2, 0, 0),
+            epoch(2, 0, 1),
+            epoch(2, 1, 2),
+            epoch(2, 999, 1000),
+        ];
+        let invalid = vec![epoch(2, 1, 0), epoch(2, 1, 1), epoch(2, 1001, 1000)];
+        for value in valid {
+            assert_eq!(Since::absolute_epoch(value).validate(true), Ok(()));
+            assert_eq!(Since::relative_epoch(value).validate(true), Ok(()));
+        }
+        for value in invalid {
+            for since in vec![Since::absolute_epoch(value), Since::relative_epoch(value)] {
+                assert_eq!(since.validate(false), Ok(()));
+                let err = since.validate(true).unwrap_err();
+                assert!(err.contains("InvalidSince"), "{}", err);
+            }
+        }
+        // Only epoch values have fractions
+        assert_eq!(Since::absolute_block_number(0).validate(true), Ok(()));
+        assert_eq!(Since::relative_timestamp(0).validate(true), Ok(()));
+    }
+
+    #[test]
+    fn earliest_block_of_block_number() {
+        assert_eq!(earliest_block(Since::absolute_block_number(2000)), Ok(2000));
+        assert_eq!(earliest_block(Since::relative_block_number(10)), Ok(1510));
+    }
+
+    #[test]
+    fn earliest_block_of_epoch() {
+        assert_eq!(
+            earliest_block(Since::absolute_epoch(epoch(2, 0, 0))),
+            Ok(2000)
+        );
+        assert_eq!(
+            earliest_block(Since::absolute_epoch(epoch(2, 1, 2))),
+            Ok(2500)
+        );
+        // Rounds up to the first block reaching the fraction
+        assert_eq!(
+            earliest_block(Since::absolute_epoch(epoch(2, 1, 3))),
+            Ok(2334)
+        );
+        assert_eq!(
+            earliest_block(Since::relative_epoch(epoch(1, 0, 0))),
+            Ok(2500)
+        );
+        assert_eq!(
+            earliest_block(Since::relative_epoch(epoch(0, 1, 2))),
+            Ok(2000)
+        );
+        assert_eq!(
+            earliest_block(Since::relative_epoch(epoch(1, 3, 4))),
+            Ok(3250)
+        );
+        assert!(earliest_block(Since::absolute_epoch(epoch(2, 1, 0))).is_err());
+    }
+
+    #[test]
+    fn earliest_block_of_timestamp_is_unsupported() {
+        assert!(earliest_block(Since::absolute_timestamp(1_600_000_000)).is_err());
+        assert!(earliest_block(Since::relative_timestamp(3600)).is_err());
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(
+            Since::absolute_block_number(10).to_string(),
+            "abs_block(10)"
+        );
+        assert_eq!(
+            Since::relative_block_number(10).to_string(),
+            "rel_block(10)"
+        );
+        assert_eq!(
+            Since::absolute_epoch(epoch(2, 1, 3)).to_string(),
+            "abs(2, 1, 3)"
+        );
+        assert_eq!(
+            Since::relative_epoch(epoch(0, 5, 4)).to_string(),
+            "rel(0, 5, 4)"
+        );
+        assert_eq!(
+            Since::absolute_timestamp(60).to_string(),
+            "abs_timestamp(60)"
+        );
+        assert_eq!(
+            Since::relative_timestamp(60).to_string(),
+            "rel_timestamp(60)"
+        );
+    }
+}
@@ -0,0 +1,32 @@
+#!/usr/bin/env bash
+#
+# Build the test scripts from the assembly sources in this directory into `..`. Requires
+# `llvm-mc` and `llvm-objcopy` with the RISC-V target, e.g. LLVM 14.
+#
+#     ./build.sh [script ...]
+set -euo pipefail
+
+cd "$(dirname "$0")"
+LLVM_MC="${LLVM_MC:-llvm-mc}"
+LLVM_OBJCOPY="${LLVM_OBJCOPY:-llvm-objcopy}"
+SCRIPTS=("$@")
+if [ ${#SCRIPTS[@]} -eq 0 ]; then
+    SCRIPTS=(always_failure cycle_burner syscall_exerciser args_asserter spawn_caller)
+fi
+
+TMP="$(mktemp -d)"
+trap 'rm -rf "$TMP"' EXIT
+for script in "${SCRIPTS[@]}"; do
+    # The first pass measures the size of the file, which the second pass writes to the header
+    "$LLVM_MC" -triple=riscv64 -mattr=+m -filetype=obj -defsym SIZE=0 -o "$TMP/$script.o" "$script.S"
+    "$LLVM_OBJCOPY" -O binary --only-section=.text "$TMP/$script.o" "$TMP/$script"
+    size="$(wc -c < "$TMP/$script")"
+    "$LLVM_MC" -triple=riscv64 -mattr=+m -filetype=obj -defsym SIZE="$size" -o "$TMP/$script.o" "$script.S"
+    # The ELF header is part of the code, so relocations would break it
+    if "${LLVM_READELF:-llvm-readelf}" -r "$TMP/$script.o" | grep -q "Relocation section"; then
+        echo "$script.S has relocations" >&2
+        exit 1
+    fi
+    "$LLVM_OBJCOPY" -O binary --only-section=.text "$TMP/$script.o" "../$script"
+    chmod +x "../$script"
+done